@@ -2,12 +2,128 @@
 //!
 //! This binary provides a native desktop GUI with features like
 //! native file dialogs and keyboard shortcuts.
+//!
+//! Usage:
+//! `ac-pcap-viewer [-q] [-v|-vv] [--server-ports 9000-9013,9050] [--ip addr] [capture.pcap...]`
+//! opens the given capture on launch, treating the listed UDP ports as the
+//! server end. Several captures are merged into one session. `--ip` keeps
+//! only datagrams to or from that address. `-v` logs packets and messages
+//! that fail to parse to stderr, and `-vv` adds malformed fragments.
+//! `ac-pcap-viewer tail [options] capture.pcap` opens no window: it follows a
+//! capture that is still being written and prints each new message as a JSON
+//! line, taking the same `--server-ports`, `--ip` and `-v` options.
+//! `-q`/`--quiet` drops the weenie extraction summary printed after each
+//! parse; it doesn't touch logging, so `-q -v` prints failures and nothing
+//! else.
 
 use app::PcapViewerApp;
+use common::follow::PcapFollower;
+use log::LevelFilter;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// How often `tail` checks the capture for new records
+const TAIL_INTERVAL: Duration = Duration::from_millis(500);
+
+const USAGE: &str = "\
+usage: ac-pcap-viewer [options] [capture.pcap...]
+       ac-pcap-viewer tail [options] capture.pcap
+
+options:
+  --server-ports <RANGES>  server UDP ports, e.g. 9000-9013,9050
+  --ip <ADDR>              only parse datagrams to or from ADDR
+  -q, --quiet              don't print the weenie extraction summary (logging
+                           from -v is unaffected)
+  -v, -vv                  log parse failures (and malformed fragments) to stderr
+  -h, --help               print this message";
 
 fn main() -> eframe::Result<()> {
-    // Initialize logging for desktop
-    env_logger::init();
+    let mut initial_files = Vec::new();
+    let mut server_ports = None;
+    let mut ip_filter = None;
+    let mut verbosity: u8 = 0;
+    let mut quiet = false;
+    let mut args = std::env::args_os().skip(1).peekable();
+    let tail_mode = args.next_if(|arg| arg == "tail").is_some();
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            println!("{USAGE}");
+            return Ok(());
+        } else if arg == "-v" || arg == "-vv" {
+            verbosity = (verbosity + arg.len() as u8 - 1).min(2);
+        } else if arg == "-q" || arg == "--quiet" {
+            quiet = true;
+        } else if arg == "--server-ports" {
+            let spec = args.next().unwrap_or_default();
+            match common::parse_port_ranges(&spec.to_string_lossy()) {
+                Ok(ranges) => server_ports = Some(ranges),
+                Err(e) => {
+                    eprintln!("--server-ports: {e:#}");
+                    std::process::exit(2);
+                }
+            }
+        } else if arg == "--ip" {
+            let addr = args.next().unwrap_or_default();
+            match addr.to_string_lossy().parse::<std::net::IpAddr>() {
+                Ok(addr) => ip_filter = Some(addr),
+                Err(e) => {
+                    eprintln!("--ip: {e}");
+                    std::process::exit(2);
+                }
+            }
+        } else if arg.to_string_lossy().starts_with('-') {
+            eprintln!("unknown option: {}\n\n{USAGE}", arg.to_string_lossy());
+            std::process::exit(2);
+        } else {
+            // Captures to open on launch
+            initial_files.push(std::path::PathBuf::from(arg));
+        }
+    }
+
+    // Initialize logging for desktop. The parser reports failures as tracing
+    // events from the common crate, which reach env_logger as log records, so
+    // -v/-vv only has to raise that crate's level on top of RUST_LOG.
+    let mut logger = env_logger::Builder::from_default_env();
+    if verbosity > 0 {
+        let level = if verbosity >= 2 {
+            LevelFilter::Trace
+        } else {
+            LevelFilter::Debug
+        };
+        logger.filter_module("common", level);
+    }
+    logger.init();
+
+    if tail_mode {
+        let [path] = initial_files.as_slice() else {
+            eprintln!("tail takes exactly one capture\n\n{USAGE}");
+            std::process::exit(2);
+        };
+        let mut parser = common::PacketParser::new().with_ip_filter(ip_filter);
+        if let Some(server_ports) = server_ports {
+            parser = parser.with_server_ports(server_ports);
+        }
+        if let Err(e) = tail(path, parser) {
+            eprintln!("tail: {e:#}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Several captures are read up front and merged on the first frame
+    let merged_files = if initial_files.len() > 1 {
+        let files: Result<Vec<Vec<u8>>, _> = initial_files.iter().map(std::fs::read).collect();
+        match files {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("Error reading capture: {e}");
+                std::process::exit(2);
+            }
+        }
+    } else {
+        Vec::new()
+    };
 
     let native_options = eframe::NativeOptions {
         viewport: eframe::egui::ViewportBuilder::default()
@@ -19,6 +135,40 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "AC PCAP Viewer",
         native_options,
-        Box::new(|cc| Ok(Box::new(PcapViewerApp::new(cc)))),
+        Box::new(|cc| {
+            let mut app = PcapViewerApp::new(cc);
+            if merged_files.is_empty() {
+                app.pending_file_path = initial_files.pop();
+            } else {
+                app.dropped_file_data = merged_files;
+            }
+            if let Some(server_ports) = server_ports {
+                app.server_ports = server_ports;
+            }
+            app.ip_filter = ip_filter;
+            app.quiet = quiet;
+            Ok(Box::new(app))
+        }),
     )
 }
+
+/// Print each message of a growing capture as a JSON line until interrupted
+///
+/// Returns early without an error once stdout is closed, as when piped into
+/// `head`.
+fn tail(path: &Path, parser: common::PacketParser) -> anyhow::Result<()> {
+    let mut follower = PcapFollower::with_parser(parser);
+    let mut stdout = std::io::stdout().lock();
+    loop {
+        let (_, messages) = follower.poll_file(path)?;
+        let written = messages.iter().try_for_each(|message| {
+            serde_json::to_writer(&mut stdout, message)?;
+            writeln!(stdout)
+        });
+        match written.and_then(|()| stdout.flush()) {
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+            result => result?,
+        }
+        std::thread::sleep(TAIL_INTERVAL);
+    }
+}
@@ -0,0 +1,165 @@
+//! Structural diff of JSON values for comparing two messages
+
+use serde_json::Value;
+
+/// How a value differs between the two sides of a diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// Present only in the new value
+    Added,
+    /// Present only in the old value
+    Removed,
+    /// Present in both with different values
+    Changed,
+}
+
+/// A single difference found at a path such as `Key.Nested[2]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffEntry {
+    pub path: String,
+    pub kind: DiffKind,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// Recursively diff two JSON values
+///
+/// Objects are compared key by key and arrays element by element, so only the
+/// leaves that actually differ are reported. A value whose type changes (e.g.
+/// a number becoming an object) is reported as a single change.
+pub fn diff_values(old: &Value, new: &Value) -> Vec<DiffEntry> {
+    let mut entries = Vec::new();
+    diff_at(String::new(), old, new, &mut entries);
+    entries
+}
+
+fn diff_at(path: String, old: &Value, new: &Value, entries: &mut Vec<DiffEntry>) {
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            for (key, old_value) in old_map {
+                let child = join_key(&path, key);
+                match new_map.get(key) {
+                    Some(new_value) => diff_at(child, old_value, new_value, entries),
+                    None => entries.push(DiffEntry {
+                        path: child,
+                        kind: DiffKind::Removed,
+                        old: Some(old_value.clone()),
+                        new: None,
+                    }),
+                }
+            }
+            for (key, new_value) in new_map {
+                if !old_map.contains_key(key) {
+                    entries.push(DiffEntry {
+                        path: join_key(&path, key),
+                        kind: DiffKind::Added,
+                        old: None,
+                        new: Some(new_value.clone()),
+                    });
+                }
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for index in 0..old_items.len().max(new_items.len()) {
+                let child = format!("{path}[{index}]");
+                match (old_items.get(index), new_items.get(index)) {
+                    (Some(old_value), Some(new_value)) => {
+                        diff_at(child, old_value, new_value, entries)
+                    }
+                    (Some(old_value), None) => entries.push(DiffEntry {
+                        path: child,
+                        kind: DiffKind::Removed,
+                        old: Some(old_value.clone()),
+                        new: None,
+                    }),
+                    (None, Some(new_value)) => entries.push(DiffEntry {
+                        path: child,
+                        kind: DiffKind::Added,
+                        old: None,
+                        new: Some(new_value.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ => {
+            if old != new {
+                entries.push(DiffEntry {
+                    path,
+                    kind: DiffKind::Changed,
+                    old: Some(old.clone()),
+                    new: Some(new.clone()),
+                });
+            }
+        }
+    }
+}
+
+fn join_key(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_identical_values_have_no_diff() {
+        let value = json!({"ObjectId": 1, "Key": "Value", "List": [1, 2]});
+        assert!(diff_values(&value, &value).is_empty());
+    }
+
+    #[test]
+    fn test_nested_changes_report_leaf_paths() {
+        let old = json!({"S2C": {"QualitiesUpdateInt": {"Key": "Value", "Value": 10}}});
+        let new = json!({"S2C": {"QualitiesUpdateInt": {"Key": "Value", "Value": 25}}});
+
+        let diff = diff_values(&old, &new);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].path, "S2C.QualitiesUpdateInt.Value");
+        assert_eq!(diff[0].kind, DiffKind::Changed);
+        assert_eq!(diff[0].old, Some(json!(10)));
+        assert_eq!(diff[0].new, Some(json!(25)));
+    }
+
+    #[test]
+    fn test_added_and_removed_keys() {
+        let old = json!({"A": 1, "B": 2});
+        let new = json!({"B": 2, "C": 3});
+
+        let diff = diff_values(&old, &new);
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff[0].path, "A");
+        assert_eq!(diff[0].kind, DiffKind::Removed);
+        assert_eq!(diff[1].path, "C");
+        assert_eq!(diff[1].kind, DiffKind::Added);
+    }
+
+    #[test]
+    fn test_array_length_changes() {
+        let old = json!({"Items": [1, 2, 3]});
+        let new = json!({"Items": [1, 5]});
+
+        let diff = diff_values(&old, &new);
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff[0].path, "Items[1]");
+        assert_eq!(diff[0].kind, DiffKind::Changed);
+        assert_eq!(diff[1].path, "Items[2]");
+        assert_eq!(diff[1].kind, DiffKind::Removed);
+    }
+
+    #[test]
+    fn test_type_change_is_single_change() {
+        let old = json!({"Value": 1});
+        let new = json!({"Value": {"Nested": 1}});
+
+        let diff = diff_values(&old, &new);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].kind, DiffKind::Changed);
+    }
+}
@@ -2,9 +2,14 @@
 //!
 //! Shared egui-based interface for both web and desktop applications.
 
-pub mod filter;
+pub mod diff;
+pub use common::filter;
+#[cfg(all(feature = "live", target_os = "linux"))]
+pub mod live;
+pub mod share;
 pub mod state;
 pub mod time_scrubber;
+pub mod timeline;
 pub mod ui;
 
 use common::{ParsedPacket, messages::ParsedMessage};
@@ -19,22 +24,108 @@ use state::{MOBILE_BREAKPOINT, MOBILE_SCALE, TABLET_BREAKPOINT};
 // Shared state for async loading
 pub type SharedData = Arc<Mutex<Option<Vec<u8>>>>;
 pub type SharedError = Arc<Mutex<Option<String>>>;
+pub type SharedAttachments = Arc<Mutex<Option<Vec<ui::file_panel::DiscordAttachment>>>>;
+// Capture bytes (none for several merged captures) with their parse result,
+// posted back by the parsing thread along with the parse generation that started it
+pub type SharedParse = Arc<Mutex<Option<(u64, Option<Vec<u8>>, anyhow::Result<ParsedCapture>)>>>;
+
+// Combat rows along with the scrubber range they were summarized over
+pub type CombatReport = (Option<(f64, f64)>, Vec<common::combat::DamageSummary>);
+
+/// Everything parsed from one capture, ready to replace the loaded data
+pub struct ParsedCapture {
+    pub packets: Vec<ParsedPacket>,
+    pub messages: Vec<ParsedMessage>,
+    pub weenie_db: common::weenie::WeenieDatabase,
+    // Whether parsing stopped at the message limit
+    pub truncated: bool,
+}
+
+impl ParsedCapture {
+    /// Parse `data` with `parser`, stopping at [`common::DEFAULT_MAX_MESSAGES`]
+    ///
+    /// Touches no app state, so it can run on a worker thread.
+    pub fn parse(parser: common::PacketParser, data: &[u8]) -> anyhow::Result<Self> {
+        let mut parser = parser.with_max_messages(Some(common::DEFAULT_MAX_MESSAGES));
+        let (packets, messages, weenie_db) = parser.parse_pcap_bytes(data)?;
+        Ok(Self {
+            packets,
+            messages,
+            weenie_db,
+            truncated: parser.truncated(),
+        })
+    }
+
+    /// Parse several captures of one session and merge them in time order
+    ///
+    /// See [`common::PacketParser::parse_many`]; the message limit applies to
+    /// the merged total.
+    pub fn merge(parser: common::PacketParser, files: &[Vec<u8>]) -> anyhow::Result<Self> {
+        let mut parser = parser.with_max_messages(Some(common::DEFAULT_MAX_MESSAGES));
+        let (packets, messages, weenie_db) = parser.parse_many(files.iter().map(Vec::as_slice))?;
+        Ok(Self {
+            packets,
+            messages,
+            weenie_db,
+            truncated: parser.truncated(),
+        })
+    }
+}
+
+/// Menu label for saving the loaded capture
+#[cfg(target_arch = "wasm32")]
+const SAVE_CAPTURE_LABEL: &str = "Download PCAP";
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_CAPTURE_LABEL: &str = "Save PCAP...";
+
+/// File name component of an optional path
+#[cfg(feature = "desktop")]
+fn path_file_name(path: Option<&std::path::Path>) -> Option<String> {
+    Some(path?.file_name()?.to_string_lossy().into_owned())
+}
 
 pub struct PcapViewerApp {
     // Data
     pub messages: Vec<ParsedMessage>,
     pub packets: Vec<ParsedPacket>,
     pub weenie_db: common::weenie::WeenieDatabase,
+    // Whether parsing stopped at the message limit before the end of the capture
+    pub truncated: bool,
+    // Time span of the loaded packets, for the status bar
+    pub capture_info: Option<common::CaptureInfo>,
+    // Echo round-trip times of the loaded packets, for the status bar
+    pub latency: Option<common::echo::LatencyStats>,
+    // UDP ports that mark the server end of a datagram
+    pub server_ports: Vec<std::ops::RangeInclusive<u16>>,
+    // Only parse datagrams to or from this address
+    pub ip_filter: Option<std::net::IpAddr>,
+    // Skip the weenie extraction summary the parser writes to stderr
+    pub quiet: bool,
 
     // UI State
     pub current_tab: Tab,
     pub selected_message: Option<usize>,
     pub selected_packet: Option<usize>,
     pub selected_weenie: Option<usize>,
+    // Text in the toolbar "Go to #" box
+    pub goto_message_input: String,
+    // Message row to scroll into view on the next frame
+    pub scroll_to_message: Option<usize>,
+    // "Go to" target hidden by the current filter, waiting on the user
+    pub hidden_goto_message: Option<usize>,
+    // Message pinned as the left-hand side of a diff
+    pub pinned_message: Option<usize>,
     pub search_query: String,
     pub sort_field: SortField,
     pub sort_ascending: bool,
     pub view_mode: ViewMode,
+    pub json_search: ui::detail_panel::JsonSearch,
+    // Offset the binary field scanner starts from
+    pub scan_offset: usize,
+    // Field layout chosen in the "Interpret as" tool for undecoded data
+    pub carver: ui::detail_panel::FieldCarver,
+    // Tree view collapse state per message type, keyed by dotted JSON path
+    pub tree_open_state: std::collections::HashMap<String, ui::hyper_tree::TreeOpenState>,
 
     // Status
     pub status_message: String,
@@ -46,12 +137,24 @@ pub struct PcapViewerApp {
     // Responsive layout state
     pub show_detail_panel: bool,
 
-    // Dropped file data
-    pub dropped_file_data: Option<Vec<u8>>,
+    // Contents of files dropped or opened together, loaded on the next frame
+    // and merged when there are several
+    pub dropped_file_data: Vec<Vec<u8>>,
+
+    // Bytes of the currently loaded capture, kept so they can be saved
+    pub loaded_data: Option<Vec<u8>>,
+    // Server ports and address filter the loaded capture was parsed with
+    pub loaded_settings: (Vec<std::ops::RangeInclusive<u16>>, Option<std::net::IpAddr>),
+    // File name offered when saving the loaded capture
+    pub capture_name: String,
 
     // Async loaded data (from fetch)
     pub fetched_data: SharedData,
     pub fetched_error: SharedError,
+    // Result of a capture parsed off the UI thread
+    pub parsed_capture: SharedParse,
+    // Bumped for every parse started, so results of superseded parses are dropped
+    pub parse_generation: u64,
 
     // Initial URL to load from query params (consumed on first update)
     pub initial_url: Option<String>,
@@ -59,6 +162,10 @@ pub struct PcapViewerApp {
     // Initial Discord load flag (consumed on first update)
     pub initial_discord_load: bool,
 
+    // Search filter and message id from a shared link, applied once a capture loads
+    pub initial_filter: Option<String>,
+    pub initial_selection: Option<usize>,
+
     // Base pixels_per_point for scaling calculations (set on first frame)
     pub base_pixels_per_point: Option<f32>,
 
@@ -68,11 +175,24 @@ pub struct PcapViewerApp {
     pub url_load_error: Option<String>,
     pub show_settings: bool,
     pub show_about: bool,
+    pub show_opcode_reference: bool,
+    pub opcode_reference_filter: String,
+    pub show_combat_summary: bool,
 
     // Discord loading state
+    // Where the loaded capture came from, when a share link can reopen it
+    pub share_source: Option<share::ShareSource>,
+    // Source of the capture being fetched, promoted to share_source once parsed
+    pub pending_share_source: Option<share::ShareSource>,
     pub discord_channel_id: String,
     pub discord_message_id: String,
     pub discord_load_error: Option<String>,
+    // Attachment selector (index or filename) for messages with several captures
+    pub discord_attachment: Option<String>,
+    // Attachment listing returned when no selector was given (consumed on update)
+    pub discord_attachments: SharedAttachments,
+    pub attachment_choices: Vec<ui::file_panel::DiscordAttachment>,
+    pub show_attachment_picker: bool,
 
     // Error dialog state
     pub show_error_dialog: bool,
@@ -82,6 +202,20 @@ pub struct PcapViewerApp {
     pub messages_scrubber: TimeScrubber,
     pub fragments_scrubber: TimeScrubber,
 
+    // Optional send/recv traffic graph for the Messages tab
+    pub show_timeline: bool,
+    // Lay the messages list out as Send/Recv columns in time order
+    pub split_view: bool,
+    pub traffic_timeline: timeline::TrafficTimeline,
+
+    // Restrict the messages list to one category
+    pub category_filter: Option<common::messages::MessageCategory>,
+
+    // Messages the decoder fell back on, grouped by opcode
+    pub unhandled_report: Vec<common::coverage::UnhandledOpcode>,
+    // Combat report, rebuilt when the capture or the scrubber range changes
+    pub combat_report: Option<CombatReport>,
+
     // Marking state for filtered items
     pub marked_messages: std::collections::HashSet<usize>,
     pub marked_packets: std::collections::HashSet<usize>,
@@ -89,6 +223,17 @@ pub struct PcapViewerApp {
     // Desktop: pending file from file dialog
     #[cfg(feature = "desktop")]
     pub pending_file_path: Option<std::path::PathBuf>,
+    // Captures opened from disk, most recent first
+    pub recent_files: Vec<state::RecentFile>,
+    // Last frame the Open Recent submenu was shown, to prune it on opening
+    #[cfg(feature = "desktop")]
+    pub recent_menu_frame: Option<u64>,
+
+    // Live capture: interface to bind and the running capture, if any
+    #[cfg(all(feature = "live", target_os = "linux"))]
+    pub live_interface: String,
+    #[cfg(all(feature = "live", target_os = "linux"))]
+    pub live_capture: Option<live::LiveCapture>,
 }
 
 impl Default for PcapViewerApp {
@@ -97,58 +242,434 @@ impl Default for PcapViewerApp {
             messages: Vec::new(),
             packets: Vec::new(),
             weenie_db: common::weenie::WeenieDatabase::new(),
+            truncated: false,
+            capture_info: None,
+            latency: None,
+            server_ports: vec![common::DEFAULT_SERVER_PORTS],
+            ip_filter: None,
+            quiet: false,
             current_tab: Tab::Messages,
             selected_message: None,
+            goto_message_input: String::new(),
+            scroll_to_message: None,
+            hidden_goto_message: None,
             selected_packet: None,
             selected_weenie: None,
+            pinned_message: None,
             search_query: String::new(),
             sort_field: SortField::Id,
             sort_ascending: true,
             view_mode: ViewMode::Tree,
+            json_search: Default::default(),
+            scan_offset: 0,
+            carver: Default::default(),
+            tree_open_state: std::collections::HashMap::new(),
             status_message: "Drag & drop a PCAP file or click 'Load Example'".to_string(),
             is_loading: false,
             dark_mode: true,
             show_detail_panel: false,
-            dropped_file_data: None,
+            dropped_file_data: Vec::new(),
+            loaded_data: None,
+            loaded_settings: (vec![common::DEFAULT_SERVER_PORTS], None),
+            capture_name: ui::file_panel::DEFAULT_CAPTURE_NAME.to_string(),
             fetched_data: Arc::new(Mutex::new(None)),
+            parsed_capture: Arc::new(Mutex::new(None)),
+            parse_generation: 0,
             fetched_error: Arc::new(Mutex::new(None)),
             initial_url: None,
             initial_discord_load: false,
+            initial_filter: None,
+            initial_selection: None,
             base_pixels_per_point: None,
             show_url_dialog: false,
             url_input: String::new(),
             url_load_error: None,
             show_settings: false,
             show_about: false,
+            show_opcode_reference: false,
+            opcode_reference_filter: String::new(),
+            show_combat_summary: false,
+            share_source: None,
+            pending_share_source: None,
             discord_channel_id: String::new(),
             discord_message_id: String::new(),
             discord_load_error: None,
+            discord_attachment: None,
+            discord_attachments: Arc::new(Mutex::new(None)),
+            attachment_choices: Vec::new(),
+            show_attachment_picker: false,
             show_error_dialog: false,
             error_dialog_message: String::new(),
             messages_scrubber: TimeScrubber::new(),
             fragments_scrubber: TimeScrubber::new(),
+            show_timeline: false,
+            split_view: false,
+            traffic_timeline: timeline::TrafficTimeline::new(),
+            category_filter: None,
+            unhandled_report: Vec::new(),
+            combat_report: None,
             marked_messages: std::collections::HashSet::new(),
             marked_packets: std::collections::HashSet::new(),
             #[cfg(feature = "desktop")]
             pending_file_path: None,
+            recent_files: Vec::new(),
+            #[cfg(feature = "desktop")]
+            recent_menu_frame: None,
+            #[cfg(all(feature = "live", target_os = "linux"))]
+            live_interface: live::DEFAULT_INTERFACE.to_string(),
+            #[cfg(all(feature = "live", target_os = "linux"))]
+            live_capture: None,
         }
     }
 }
 
 impl PcapViewerApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        #[allow(unused_mut)]
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         let mut app = Self::default();
 
+        // Restore UI preferences saved by a previous session
+        if let Some(settings) = cc
+            .storage
+            .and_then(|storage| storage.get_string(state::SETTINGS_KEY))
+            .and_then(|json| serde_json::from_str::<state::UiSettings>(&json).ok())
+        {
+            app.apply_settings(settings);
+        }
+        if let Some(recent_files) = cc
+            .storage
+            .and_then(|storage| storage.get_string(state::RECENT_FILES_KEY))
+            .and_then(|json| serde_json::from_str::<Vec<state::RecentFile>>(&json).ok())
+        {
+            app.recent_files = recent_files;
+            app.recent_files.retain(|file| file.path.exists());
+        }
+
         app
     }
 
+    /// Current UI preferences, as persisted across sessions
+    pub fn settings(&self) -> state::UiSettings {
+        state::UiSettings {
+            dark_mode: self.dark_mode,
+            sort_field: self.sort_field,
+            sort_ascending: self.sort_ascending,
+            view_mode: self.view_mode,
+        }
+    }
+
+    /// Apply persisted UI preferences
+    pub fn apply_settings(&mut self, settings: state::UiSettings) {
+        self.dark_mode = settings.dark_mode;
+        self.sort_field = settings.sort_field;
+        self.sort_ascending = settings.sort_ascending;
+        self.view_mode = settings.view_mode;
+    }
+
     /// Show an error dialog with the given message
     pub fn show_error(&mut self, message: impl Into<String>) {
         self.error_dialog_message = message.into();
         self.show_error_dialog = true;
     }
 
+    /// Parser configured with the app's server ports
+    pub(crate) fn packet_parser(&self) -> common::PacketParser {
+        common::PacketParser::new()
+            .with_server_ports(self.server_ports.clone())
+            .with_ip_filter(self.ip_filter)
+            .with_quiet(self.quiet)
+    }
+
+    /// Parse a PCAP capture and replace the loaded data with it
+    ///
+    /// Rebuilds the scrubbers and reports. When `data` is the capture already
+    /// loaded, as when re-parsing it with other server ports, the selected,
+    /// pinned and marked messages are kept wherever the new data has a message
+    /// with the same [`MessageKey`](common::messages::MessageKey). Otherwise the
+    /// marks and pin are cleared and the selection resets to the first item. On
+    /// error the previously loaded data is left in place. Needs no UI context,
+    /// so it can be used by embedders and tests.
+    pub fn load_bytes(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        let capture = ParsedCapture::parse(self.packet_parser(), data)?;
+        self.apply_capture(capture, Some(data.to_vec()));
+        Ok(())
+    }
+
+    /// Replace the loaded data with `capture`, already parsed from `data`
+    ///
+    /// The second half of [`load_bytes`](Self::load_bytes), for captures
+    /// parsed elsewhere. `data` is `None` for captures merged from several
+    /// files, which have no single file to save or re-parse.
+    pub fn apply_capture(&mut self, capture: ParsedCapture, data: Option<Vec<u8>>) {
+        let ParsedCapture {
+            packets,
+            messages,
+            weenie_db,
+            truncated,
+        } = capture;
+        self.truncated = truncated;
+
+        // Sequence numbers restart with every session, so a key only names the
+        // same message when the same capture is parsed again
+        let reparse = data.is_some() && self.loaded_data == data;
+        // Packets have no key of their own, so marks stay on their rows only
+        // when the same settings produce the same packets. Other settings drop
+        // or add datagrams and shift every index after them.
+        let settings = (self.server_ports.clone(), self.ip_filter);
+        if !reparse || self.loaded_settings != settings {
+            self.marked_packets.clear();
+        }
+        self.loaded_settings = settings;
+        let key_of = |idx: usize| {
+            self.messages
+                .get(idx)
+                .filter(|_| reparse)
+                .map(ParsedMessage::key)
+        };
+        let selected_key = self.selected_message.and_then(key_of);
+        let pinned_key = self.pinned_message.and_then(key_of);
+        let marked_keys: std::collections::HashSet<_> = self
+            .marked_messages
+            .iter()
+            .filter_map(|&idx| key_of(idx))
+            .collect();
+
+        self.packets = packets;
+        self.messages = messages;
+        self.weenie_db = weenie_db;
+        self.loaded_data = data;
+
+        let index_of = |key| self.messages.iter().position(|m| m.key() == key);
+        self.selected_message = selected_key
+            .and_then(index_of)
+            .or((!self.messages.is_empty()).then_some(0));
+        self.pinned_message = pinned_key.and_then(index_of);
+        self.marked_messages = self
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| marked_keys.contains(&m.key()))
+            .map(|(idx, _)| idx)
+            .collect();
+        self.selected_packet = if self.packets.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+
+        self.refresh_views();
+    }
+
+    /// Take the result posted by the parsing thread, if it is for the latest parse
+    ///
+    /// Results of parses superseded by a later one are dropped.
+    fn take_parsed_capture(&mut self) -> Option<(Option<Vec<u8>>, anyhow::Result<ParsedCapture>)> {
+        let (generation, data, result) = self.parsed_capture.try_lock().ok()?.take()?;
+        (generation == self.parse_generation).then_some((data, result))
+    }
+
+    /// Start capturing AC traffic on `live_interface`, replacing the loaded data
+    #[cfg(all(feature = "live", target_os = "linux"))]
+    pub fn start_live_capture(&mut self) {
+        match live::LiveCapture::start(&self.live_interface, self.packet_parser()) {
+            Ok(capture) => {
+                self.packets.clear();
+                self.messages.clear();
+                self.weenie_db = common::weenie::WeenieDatabase::new();
+                self.selected_message = None;
+                self.selected_packet = None;
+                self.pinned_message = None;
+                self.marked_messages.clear();
+                self.marked_packets.clear();
+                self.loaded_data = None;
+                self.truncated = false;
+                self.refresh_views();
+                self.status_message = format!("Capturing on {}...", capture.interface());
+                self.live_capture = Some(capture);
+            }
+            Err(e) => self.show_error(format!("{e:#}")),
+        }
+    }
+
+    /// Stop the live capture, keeping what was captured
+    #[cfg(all(feature = "live", target_os = "linux"))]
+    pub fn stop_live_capture(&mut self) {
+        if let Some(capture) = self.live_capture.take() {
+            self.status_message = format!(
+                "Stopped capture on {}: {} messages",
+                capture.interface(),
+                self.messages.len()
+            );
+            // Catch up on whatever arrived since the last throttled refresh
+            self.refresh_views();
+        }
+    }
+
+    /// Append messages and packets parsed since the last frame of a live capture
+    ///
+    /// New messages go straight into the weenie database; the scrubbers and
+    /// reports are rebuilt when the capture says they are due. Capturing stops
+    /// at the message limit.
+    #[cfg(all(feature = "live", target_os = "linux"))]
+    fn poll_live_capture(&mut self) {
+        let Some(capture) = self.live_capture.as_mut() else {
+            return;
+        };
+        let (packets, messages) = capture.poll();
+        let refresh = capture.take_refresh();
+        let truncated = capture.truncated();
+
+        for message in &messages {
+            for update in common::weenie_extractor::extract_weenie_updates(message) {
+                self.weenie_db.add_or_update(update);
+            }
+        }
+        self.packets.extend(packets);
+        self.messages.extend(messages);
+        if self.selected_message.is_none() && !self.messages.is_empty() {
+            self.selected_message = Some(0);
+        }
+        if self.selected_packet.is_none() && !self.packets.is_empty() {
+            self.selected_packet = Some(0);
+        }
+
+        if truncated {
+            self.truncated = true;
+            self.stop_live_capture();
+        } else if refresh {
+            self.refresh_views();
+        }
+    }
+
+    /// Rebuild the scrubbers, timeline and reports from the loaded data
+    fn refresh_views(&mut self) {
+        self.capture_info = common::CaptureInfo::from_packets(&self.packets);
+        self.latency =
+            common::echo::LatencyStats::from_round_trips(&common::echo::round_trips(&self.packets));
+
+        // Messages scrubber uses message timestamps
+        let message_timestamps: Vec<f64> = self.messages.iter().map(|m| m.timestamp).collect();
+        self.messages_scrubber.update_density(&message_timestamps);
+        let message_sizes: Vec<(f64, usize)> = self
+            .messages
+            .iter()
+            .map(|m| (m.timestamp, m.size))
+            .collect();
+        self.messages_scrubber.update_throughput(&message_sizes);
+        let marked_timestamps: Vec<f64> = self
+            .marked_messages
+            .iter()
+            .map(|&idx| self.messages[idx].timestamp)
+            .collect();
+        self.messages_scrubber
+            .set_marked_timestamps(marked_timestamps);
+        self.traffic_timeline.update(&self.messages);
+        self.unhandled_report = common::coverage::unhandled_report(&self.messages);
+        self.combat_report = None;
+
+        // Fragments scrubber uses packet timestamps
+        let packet_timestamps: Vec<f64> = self.packets.iter().map(|p| p.timestamp).collect();
+        self.fragments_scrubber.update_density(&packet_timestamps);
+        let packet_sizes: Vec<(f64, usize)> = self
+            .packets
+            .iter()
+            .map(|p| (p.timestamp, p.raw_payload.len()))
+            .collect();
+        self.fragments_scrubber.update_throughput(&packet_sizes);
+    }
+
+    /// Mark every message with the same type as the one at `idx` (replaces previous marks)
+    pub fn mark_same_type(&mut self, idx: usize) {
+        let Some(message_type) = self.messages.get(idx).map(|m| m.message_type.clone()) else {
+            return;
+        };
+
+        self.marked_messages = self
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.message_type == message_type)
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let marked_timestamps: Vec<f64> = self
+            .messages
+            .iter()
+            .filter(|m| m.message_type == message_type)
+            .map(|m| m.timestamp)
+            .collect();
+        self.messages_scrubber
+            .set_marked_timestamps(marked_timestamps);
+    }
+
+    /// Filter for the messages list: search box, category and scrubber selection
+    pub fn message_filter(&self) -> common::filter::MessageFilter {
+        common::filter::MessageFilter::new()
+            .with_search(&self.search_query)
+            .with_category(self.category_filter)
+            .with_time_range(
+                self.messages_scrubber
+                    .get_selected_range()
+                    .map(|range| range.min..=range.max),
+            )
+    }
+
+    /// Select the message with the given id and scroll it into view.
+    ///
+    /// When the message exists but the current filter hides it, nothing is
+    /// selected and the user is asked whether to clear the filter instead.
+    pub fn go_to_message(&mut self, id: usize) {
+        let Some(index) = self.messages.iter().position(|m| m.id == id) else {
+            self.status_message = format!("No message #{id}");
+            return;
+        };
+
+        if self.message_filter().matches(&self.messages[index]) {
+            self.current_tab = Tab::Messages;
+            self.selected_message = Some(index);
+            self.scroll_to_message = Some(index);
+        } else {
+            self.hidden_goto_message = Some(index);
+        }
+    }
+
+    /// Link that reopens the loaded capture with the current filter and selection
+    pub fn share_link(&self) -> Option<String> {
+        let source = self.share_source.as_ref()?;
+        Some(share::share_link(
+            &share::page_base_url()?,
+            source,
+            &self.search_query,
+            self.selected_message
+                .and_then(|idx| self.messages.get(idx))
+                .map(|m| m.id),
+        ))
+    }
+
+    /// Apply the filter and selection from a shared link after its capture loads
+    pub fn apply_initial_view(&mut self) {
+        if let Some(filter) = self.initial_filter.take() {
+            self.search_query = filter;
+        }
+        if let Some(id) = self.initial_selection.take() {
+            self.go_to_message(id);
+        }
+    }
+
+    /// Copy [`share_link`](Self::share_link) to the clipboard
+    pub fn copy_share_link(&mut self, ctx: &egui::Context) {
+        if let Some(link) = self.share_link() {
+            ctx.copy_text(link);
+            self.status_message = "Copied share link to clipboard".to_string();
+        }
+    }
+
+    /// Clear the search, category and time filters on the messages list
+    pub fn clear_message_filter(&mut self) {
+        self.search_query.clear();
+        self.category_filter = None;
+        self.messages_scrubber.reset_selection();
+    }
+
     /// Mark all currently filtered items for visual tracking (replaces previous marks)
     fn mark_filtered_items(&mut self) {
         let search = self.search_query.to_lowercase();
@@ -158,62 +679,55 @@ impl PcapViewerApp {
                 // Clear previous marks before setting new ones
                 self.marked_messages.clear();
 
-                let time_filter = self.messages_scrubber.get_selected_range().cloned();
-
-                // Filter messages based on search and time
+                // Same filter as the messages list
+                let filter = self.message_filter();
                 let filtered_indices: Vec<usize> = self
                     .messages
                     .iter()
                     .enumerate()
-                    .filter(|(_, m)| {
-                        // Apply search filter with rich filter support
-                        let matches_search = if search.is_empty() {
-                            true
-                        } else {
-                            // Parse search string into rich filters (supports hex, decimal, and text)
-                            let filters = crate::filter::parse_filter_string(&search);
-
-                            // Check if any filter matches
-                            let mut matches = false;
-
-                            // Search in message ID
-                            if crate::filter::matches_any_filter(&filters, &m.id.to_string()) {
-                                matches = true;
-                            }
-
-                            // Check opcode match
-                            if !matches && crate::filter::matches_any_filter(&filters, &m.opcode) {
-                                matches = true;
-                            }
+                    .filter(|(_, m)| filter.matches(m))
+                    .map(|(idx, _)| idx)
+                    .collect();
 
-                            // Check direction match
-                            if !matches && crate::filter::matches_any_filter(&filters, &m.direction)
-                            {
-                                matches = true;
-                            }
+                // Set marked_messages to only the filtered indices
+                self.marked_messages = filtered_indices.into_iter().collect();
 
-                            // Check if filter matches in data fields
-                            if !matches {
-                                let data_str = serde_json::to_string(&m.data).unwrap_or_default();
-                                if crate::filter::matches_any_filter(&filters, &data_str) {
-                                    matches = true;
-                                }
-                            }
+                // Update scrubber with marked timestamps
+                let marked_timestamps: Vec<f64> = self
+                    .messages
+                    .iter()
+                    .enumerate()
+                    .filter(|(idx, _)| self.marked_messages.contains(idx))
+                    .map(|(_, m)| m.timestamp)
+                    .collect();
+                self.messages_scrubber
+                    .set_marked_timestamps(marked_timestamps);
+            }
+            Tab::Packets => {
+                self.marked_packets.clear();
 
-                            // Always also do text search (type and data)
-                            if !matches {
-                                let type_matches = m.message_type.to_lowercase().contains(&search);
-                                let data_matches =
-                                    crate::state::json_contains_string(&m.data, &search);
-                                matches = type_matches || data_matches;
-                            }
+                let time_filter = self.fragments_scrubber.get_selected_range().cloned();
+                let filters = crate::filter::parse_filter_string(&search);
 
-                            matches
-                        };
+                // Match packet id, sequence, direction, or any message the packet carried
+                let filtered_indices: Vec<usize> = self
+                    .packets
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| {
+                        let matches_search = search.is_empty()
+                            || crate::filter::matches_any_filter(&filters, &p.id.to_string())
+                            || crate::filter::matches_any_filter(
+                                &filters,
+                                &p.header.sequence.to_string(),
+                            )
+                            || crate::filter::matches_any_filter(&filters, &p.direction)
+                            || p.messages
+                                .iter()
+                                .any(|m| crate::filter::json_contains_string(m, &search));
 
-                        // Apply time filter
                         let matches_time = if let Some(ref range) = time_filter {
-                            range.contains(m.timestamp)
+                            range.contains(p.timestamp)
                         } else {
                             true
                         };
@@ -223,18 +737,16 @@ impl PcapViewerApp {
                     .map(|(idx, _)| idx)
                     .collect();
 
-                // Set marked_messages to only the filtered indices
-                self.marked_messages = filtered_indices.into_iter().collect();
+                self.marked_packets = filtered_indices.into_iter().collect();
 
-                // Update scrubber with marked timestamps
                 let marked_timestamps: Vec<f64> = self
-                    .messages
+                    .packets
                     .iter()
                     .enumerate()
-                    .filter(|(idx, _)| self.marked_messages.contains(idx))
-                    .map(|(_, m)| m.timestamp)
+                    .filter(|(idx, _)| self.marked_packets.contains(idx))
+                    .map(|(_, p)| p.timestamp)
                     .collect();
-                self.messages_scrubber
+                self.fragments_scrubber
                     .set_marked_timestamps(marked_timestamps);
             }
             Tab::Weenies => {
@@ -245,21 +757,41 @@ impl PcapViewerApp {
 }
 
 impl eframe::App for PcapViewerApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if let Ok(json) = serde_json::to_string(&self.settings()) {
+            storage.set_string(state::SETTINGS_KEY, json);
+        }
+        if let Ok(json) = serde_json::to_string(&self.recent_files) {
+            storage.set_string(state::RECENT_FILES_KEY, json);
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        #[cfg(all(feature = "live", target_os = "linux"))]
+        if self.live_capture.is_some() {
+            self.poll_live_capture();
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+
         // Handle dropped files
         ctx.input(|i| {
             if !i.raw.dropped_files.is_empty() {
                 for file in &i.raw.dropped_files {
                     #[cfg(feature = "desktop")]
                     {
+                        if let Some(name) = path_file_name(file.path.as_deref()) {
+                            self.capture_name = name;
+                        } else if !file.name.is_empty() {
+                            self.capture_name = file.name.clone();
+                        }
                         if let Some(path) = &file.path {
                             self.status_message = format!("Loading {}...", path.display());
                             match std::fs::read(path) {
-                                Ok(data) => self.dropped_file_data = Some(data),
+                                Ok(data) => self.dropped_file_data.push(data),
                                 Err(e) => self.show_error(format!("Error reading file: {e}")),
                             }
                         } else if let Some(bytes) = &file.bytes {
-                            self.dropped_file_data = Some(bytes.to_vec());
+                            self.dropped_file_data.push(bytes.to_vec());
                         } else {
                             self.show_error(
                                 "Failed to read dropped file as path or byte array. Please report a bug.s",
@@ -268,8 +800,11 @@ impl eframe::App for PcapViewerApp {
                     }
                     #[cfg(not(feature = "desktop"))]
                     {
+                        if !file.name.is_empty() {
+                            self.capture_name = file.name.clone();
+                        }
                         if let Some(bytes) = &file.bytes {
-                            self.dropped_file_data = Some(bytes.to_vec());
+                            self.dropped_file_data.push(bytes.to_vec());
                         } else {
                             self.show_error(
                                 "Failed to read dropped file as byte array. Please report a bug.s",
@@ -281,16 +816,30 @@ impl eframe::App for PcapViewerApp {
         });
 
         // Process dropped file data outside the input closure
-        if let Some(data) = self.dropped_file_data.take() {
-            ui::file_panel::parse_pcap_data(self, &data);
+        let mut dropped = std::mem::take(&mut self.dropped_file_data);
+        if dropped.len() > 1 {
+            ui::file_panel::merge_pcap_data(self, dropped, ctx);
+        } else if let Some(data) = dropped.pop() {
+            ui::file_panel::parse_pcap_data(self, data, ctx);
+        }
+
+        // Pick up a capture parsed in the background
+        if let Some((data, result)) = self.take_parsed_capture() {
+            ui::file_panel::finish_parse(self, data, result);
         }
 
         // Desktop: process file from file dialog
         #[cfg(feature = "desktop")]
         if let Some(path) = self.pending_file_path.take() {
+            if let Some(name) = path_file_name(Some(&path)) {
+                self.capture_name = name;
+            }
             self.status_message = format!("Loading {}...", path.display());
             match std::fs::read(&path) {
-                Ok(data) => ui::file_panel::parse_pcap_data(self, &data),
+                Ok(data) => {
+                    state::remember_recent_file(&mut self.recent_files, path, state::unix_time());
+                    ui::file_panel::parse_pcap_data(self, data, ctx);
+                }
                 Err(e) => self.show_error(format!("Error reading file: {e}")),
             }
         }
@@ -302,7 +851,7 @@ impl eframe::App for PcapViewerApp {
             None
         };
         if let Some(data) = fetched_data {
-            ui::file_panel::parse_pcap_data(self, &data);
+            ui::file_panel::parse_pcap_data(self, data, ctx);
         }
 
         // Check for async fetch errors
@@ -312,11 +861,25 @@ impl eframe::App for PcapViewerApp {
             None
         };
         if let Some(error) = fetched_error {
+            self.pending_share_source = None;
             self.url_load_error = Some(error.clone());
             self.discord_load_error = Some(error);
             self.is_loading = false;
         }
 
+        // Check for an attachment listing from a multi-attachment Discord message
+        let attachments = if let Ok(mut listing) = self.discord_attachments.try_lock() {
+            listing.take()
+        } else {
+            None
+        };
+        if let Some(attachments) = attachments {
+            self.is_loading = false;
+            self.attachment_choices = attachments;
+            self.show_attachment_picker = true;
+            self.status_message = "Select an attachment to load".to_string();
+        }
+
         // Handle initial URL from query params (auto-load on first frame)
         if let Some(url) = self.initial_url.take() {
             ui::file_panel::load_from_url(self, url, ctx);
@@ -405,6 +968,129 @@ impl eframe::App for PcapViewerApp {
                         }
                     });
 
+                    #[cfg(all(not(target_arch = "wasm32"), feature = "desktop"))]
+                    ui::file_panel::show_recent_files_menu(self, ui);
+
+                    #[cfg(target_arch = "wasm32")]
+                    if ui
+                        .add_enabled(
+                            self.share_source.is_some(),
+                            egui::Button::new("Copy Share Link"),
+                        )
+                        .on_hover_text(
+                            "Copy a link that reopens this capture with the current filter",
+                        )
+                        .clicked()
+                    {
+                        self.copy_share_link(ui.ctx());
+                        ui.close_menu();
+                    }
+
+                    if ui
+                        .add_enabled(
+                            self.loaded_data.is_some(),
+                            egui::Button::new(SAVE_CAPTURE_LABEL),
+                        )
+                        .clicked()
+                    {
+                        ui::file_panel::save_capture(self);
+                        ui.close_menu();
+                    }
+
+                    #[cfg(all(not(target_arch = "wasm32"), feature = "desktop"))]
+                    if ui
+                        .add_enabled(
+                            !self.messages.is_empty(),
+                            egui::Button::new("Export JSON..."),
+                        )
+                        .clicked()
+                    {
+                        ui::file_panel::export_json_dialog(self);
+                        ui.close_menu();
+                    }
+
+                    #[cfg(all(not(target_arch = "wasm32"), feature = "desktop"))]
+                    if ui
+                        .add_enabled(
+                            !self.messages.is_empty(),
+                            egui::Button::new("Export Messages JSONL..."),
+                        )
+                        .clicked()
+                    {
+                        ui::file_panel::export_jsonl_dialog(self);
+                        ui.close_menu();
+                    }
+
+                    #[cfg(all(not(target_arch = "wasm32"), feature = "desktop"))]
+                    if ui
+                        .add_enabled(
+                            !self.messages.is_empty(),
+                            egui::Button::new("Export Summary JSON..."),
+                        )
+                        .on_hover_text("Totals, direction splits, top message types and duration")
+                        .clicked()
+                    {
+                        ui::file_panel::export_summary_dialog(self);
+                        ui.close_menu();
+                    }
+
+                    #[cfg(all(not(target_arch = "wasm32"), feature = "desktop"))]
+                    if ui
+                        .add_enabled(
+                            !self.messages.is_empty(),
+                            egui::Button::new("Export Opcode Histogram..."),
+                        )
+                        .clicked()
+                    {
+                        ui::file_panel::export_opcode_histogram_dialog(self);
+                        ui.close_menu();
+                    }
+
+                    #[cfg(all(not(target_arch = "wasm32"), feature = "desktop"))]
+                    if ui
+                        .button("Export JSON Schema...")
+                        .on_hover_text("Schema of the Export JSON document")
+                        .clicked()
+                    {
+                        ui::file_panel::export_schema_dialog(self);
+                        ui.close_menu();
+                    }
+
+                    if ui
+                        .add_enabled(
+                            !self.messages.is_empty(),
+                            egui::Button::new("Combat Summary..."),
+                        )
+                        .on_hover_text("Damage per attacker and target in the selected time range")
+                        .clicked()
+                    {
+                        self.show_combat_summary = true;
+                        ui.close_menu();
+                    }
+
+                    #[cfg(all(feature = "live", target_os = "linux"))]
+                    {
+                        ui.separator();
+                        if self.live_capture.is_some() {
+                            if ui.button("Stop Live Capture").clicked() {
+                                self.stop_live_capture();
+                                ui.close_menu();
+                            }
+                        } else {
+                            ui.horizontal(|ui| {
+                                ui.label("Interface:");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.live_interface)
+                                        .desired_width(80.0),
+                                );
+                            });
+                            if ui.button("Start Live Capture").clicked() {
+                                self.start_live_capture();
+                                ui.close_menu();
+                            }
+                        }
+                    }
+
                     ui.separator();
 
                     if ui.button("Settings...").clicked() {
@@ -421,6 +1107,10 @@ impl eframe::App for PcapViewerApp {
                 });
 
                 ui.menu_button("About", |ui| {
+                    if ui.button("Message Types...").clicked() {
+                        self.show_opcode_reference = true;
+                        ui.close_menu();
+                    }
                     if ui.button("About AC PCAP Viewer").clicked() {
                         self.show_about = true;
                         ui.close_menu();
@@ -474,6 +1164,12 @@ impl eframe::App for PcapViewerApp {
                         {
                             self.current_tab = Tab::Messages;
                         }
+                        if ui
+                            .selectable_label(self.current_tab == Tab::Packets, "Pkt")
+                            .clicked()
+                        {
+                            self.current_tab = Tab::Packets;
+                        }
                         if ui
                             .selectable_label(self.current_tab == Tab::Weenies, "Obj")
                             .clicked()
@@ -516,6 +1212,7 @@ impl eframe::App for PcapViewerApp {
                         // Reset marks button (enabled when there are marks)
                         let has_marks = match self.current_tab {
                             Tab::Messages => !self.marked_messages.is_empty(),
+                            Tab::Packets => !self.marked_packets.is_empty(),
                             Tab::Weenies => false, // TODO: Implement weenie marking
                         };
                         ui.add_enabled_ui(has_marks, |ui| {
@@ -525,6 +1222,10 @@ impl eframe::App for PcapViewerApp {
                                         self.marked_messages.clear();
                                         self.messages_scrubber.clear_marked_timestamps();
                                     }
+                                    Tab::Packets => {
+                                        self.marked_packets.clear();
+                                        self.fragments_scrubber.clear_marked_timestamps();
+                                    }
                                     Tab::Weenies => {
                                         // TODO: Implement weenie marking
                                     }
@@ -564,6 +1265,12 @@ impl eframe::App for PcapViewerApp {
                     {
                         self.current_tab = Tab::Messages;
                     }
+                    if ui
+                        .selectable_label(self.current_tab == Tab::Packets, "Packets")
+                        .clicked()
+                    {
+                        self.current_tab = Tab::Packets;
+                    }
                     if ui
                         .selectable_label(self.current_tab == Tab::Weenies, "Weenies")
                         .clicked()
@@ -609,6 +1316,7 @@ impl eframe::App for PcapViewerApp {
                     // Reset marks button (enabled when there are marks)
                     let has_marks = match self.current_tab {
                         Tab::Messages => !self.marked_messages.is_empty(),
+                        Tab::Packets => !self.marked_packets.is_empty(),
                         Tab::Weenies => false, // TODO: Implement weenie marking
                     };
                     ui.add_enabled_ui(has_marks, |ui| {
@@ -618,6 +1326,10 @@ impl eframe::App for PcapViewerApp {
                                     self.marked_messages.clear();
                                     self.messages_scrubber.clear_marked_timestamps();
                                 }
+                                Tab::Packets => {
+                                    self.marked_packets.clear();
+                                    self.fragments_scrubber.clear_marked_timestamps();
+                                }
                                 Tab::Weenies => {
                                     // TODO: Implement weenie marking
                                 }
@@ -625,6 +1337,27 @@ impl eframe::App for PcapViewerApp {
                         }
                     });
 
+                    if self.current_tab == Tab::Messages {
+                        ui.separator();
+                        ui::packet_list::show_goto_box(self, ui);
+                    }
+
+                    ui.separator();
+                    if ui
+                        .selectable_label(self.show_timeline, "Timeline")
+                        .on_hover_text("Show send/recv traffic over time")
+                        .clicked()
+                    {
+                        self.show_timeline = !self.show_timeline;
+                    }
+                    if ui
+                        .selectable_label(self.split_view, "Split")
+                        .on_hover_text("Show sent and received messages side by side in time order")
+                        .clicked()
+                    {
+                        self.split_view = !self.split_view;
+                    }
+
                     // Theme toggle on far right
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                         ui::packet_list::draw_theme_toggle(self, ui);
@@ -653,6 +1386,16 @@ impl eframe::App for PcapViewerApp {
                     ui.label(format!("{} msgs {}", self.messages.len(), debug_info));
                 } else {
                     ui.label(format!("{} {}", &self.status_message, debug_info));
+                    if let Some(info) = &self.capture_info {
+                        ui.separator();
+                        ui.label(format_capture_span(info))
+                            .on_hover_text(format!("{:.6} to {:.6}", info.start, info.end));
+                    }
+                    if let Some(latency) = &self.latency {
+                        ui.separator();
+                        ui.label(format_latency(latency))
+                            .on_hover_text(format_latency_details(latency));
+                    }
                 }
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -779,6 +1522,7 @@ impl eframe::App for PcapViewerApp {
             // Check which scrubber has data
             let scrubber_has_data = match self.current_tab {
                 Tab::Messages => self.messages_scrubber.has_data(),
+                Tab::Packets => self.fragments_scrubber.has_data(),
                 Tab::Weenies => false, // Weenies don't have time scrubbers
             };
 
@@ -789,6 +1533,7 @@ impl eframe::App for PcapViewerApp {
                         // Show appropriate scrubber
                         let result = match self.current_tab {
                             Tab::Messages => self.messages_scrubber.show(ui),
+                            Tab::Packets => self.fragments_scrubber.show(ui),
                             Tab::Weenies => unreachable!("Weenies don't have time scrubbers"),
                         };
 
@@ -796,6 +1541,7 @@ impl eframe::App for PcapViewerApp {
                         if result.clicked_index.is_some() {
                             clicked_time = match self.current_tab {
                                 Tab::Messages => self.messages_scrubber.get_hover_time(),
+                                Tab::Packets => self.fragments_scrubber.get_hover_time(),
                                 Tab::Weenies => unreachable!("Weenies don't have time scrubbers"),
                             };
                         }
@@ -807,6 +1553,10 @@ impl eframe::App for PcapViewerApp {
                                     self.marked_messages.clear();
                                     self.messages_scrubber.clear_marked_timestamps();
                                 }
+                                Tab::Packets => {
+                                    self.marked_packets.clear();
+                                    self.fragments_scrubber.clear_marked_timestamps();
+                                }
                                 Tab::Weenies => {
                                     // TODO: Implement weenie marking
                                 }
@@ -816,24 +1566,49 @@ impl eframe::App for PcapViewerApp {
             }
         }
 
-        // Handle click-to-scroll from time scrubber
+        // Traffic timeline panel (Messages tab only), shown above the time scrubber
+        if has_data
+            && self.show_timeline
+            && self.current_tab == Tab::Messages
+            && self.traffic_timeline.has_data()
+        {
+            egui::TopBottomPanel::bottom("traffic_timeline_panel")
+                .resizable(false)
+                .show(ctx, |ui| {
+                    if let Some(time) = self.traffic_timeline.show(ui) {
+                        clicked_time = Some(time);
+                    }
+                });
+        }
+
+        // Handle click-to-scroll from time scrubber or timeline
         if let Some(time) = clicked_time {
-            // Find the closest message to the clicked time
-            let closest_idx = self
-                .messages
-                .iter()
-                .enumerate()
-                .min_by(|(_, a), (_, b)| {
-                    let dist_a = (a.timestamp - time).abs();
-                    let dist_b = (b.timestamp - time).abs();
-                    dist_a
-                        .partial_cmp(&dist_b)
-                        .unwrap_or(std::cmp::Ordering::Equal)
-                })
-                .map(|(idx, _)| idx);
-
-            if let Some(idx) = closest_idx {
-                self.selected_message = Some(idx);
+            let closest = |timestamps: &mut dyn Iterator<Item = f64>| {
+                timestamps
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| {
+                        let dist_a = (a - time).abs();
+                        let dist_b = (b - time).abs();
+                        dist_a
+                            .partial_cmp(&dist_b)
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    })
+                    .map(|(idx, _)| idx)
+            };
+
+            match self.current_tab {
+                Tab::Packets => {
+                    // Find the closest packet to the clicked time
+                    if let Some(idx) = closest(&mut self.packets.iter().map(|p| p.timestamp)) {
+                        self.selected_packet = Some(idx);
+                    }
+                }
+                _ => {
+                    // Find the closest message to the clicked time
+                    if let Some(idx) = closest(&mut self.messages.iter().map(|m| m.timestamp)) {
+                        self.selected_message = Some(idx);
+                    }
+                }
             }
         }
 
@@ -976,6 +1751,7 @@ impl eframe::App for PcapViewerApp {
                 // On mobile, auto-show detail when selecting an item
                 match self.current_tab {
                     Tab::Messages => ui::packet_list::show_messages_list(self, ui, is_mobile),
+                    Tab::Packets => ui::packet_list::show_packets_list(self, ui, is_mobile),
                     Tab::Weenies => ui::weenie_panel::show_weenie_panel(self, ui, is_mobile),
                 }
             }
@@ -996,9 +1772,351 @@ impl eframe::App for PcapViewerApp {
             ui::file_panel::show_about_dialog(self, ctx);
         }
 
+        // Message type reference
+        if self.show_opcode_reference {
+            ui::opcode_reference::show_opcode_reference(self, ctx);
+        }
+
+        // Combat damage report
+        if self.show_combat_summary {
+            ui::combat_panel::show_combat_summary(self, ctx);
+        }
+
+        // "Go to" target hidden by the filter
+        if self.hidden_goto_message.is_some() {
+            ui::packet_list::show_hidden_goto_prompt(self, ctx);
+        }
+
+        // Discord attachment picker
+        if self.show_attachment_picker {
+            ui::file_panel::show_attachment_picker(self, ctx);
+        }
+
         // Error dialog
         ui::error_dialog::show_error_dialog(self, ctx);
     }
 }
 
-impl PcapViewerApp {}
+/// Capture start and end as UTC times of day, with the duration
+fn format_capture_span(info: &common::CaptureInfo) -> String {
+    let time_of_day = |timestamp: f64| {
+        let secs = timestamp.rem_euclid(86_400.0) as u64;
+        format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    };
+    let duration = info.duration_secs as u64;
+    let duration = if duration >= 3600 {
+        format!("{}h {}m", duration / 3600, duration / 60 % 60)
+    } else if duration >= 60 {
+        format!("{}m {}s", duration / 60, duration % 60)
+    } else {
+        format!("{:.1}s", info.duration_secs)
+    };
+    format!(
+        "{}\u{2013}{} UTC ({duration})",
+        time_of_day(info.start),
+        time_of_day(info.end)
+    )
+}
+
+/// Median echo round-trip time, for the status bar
+fn format_latency(latency: &common::echo::LatencyStats) -> String {
+    format!("RTT {:.0} ms", latency.median * 1000.0)
+}
+
+/// Spread of the echo round-trip times, shown when hovering the latency
+fn format_latency_details(latency: &common::echo::LatencyStats) -> String {
+    let ms = |secs: f64| secs * 1000.0;
+    format!(
+        "{} echoes\nmin {:.1} ms, median {:.1} ms, p95 {:.1} ms, max {:.1} ms\nmean {:.1} ms",
+        latency.count,
+        ms(latency.min),
+        ms(latency.median),
+        ms(latency.p95),
+        ms(latency.max),
+        ms(latency.mean)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A little-endian Ethernet pcap file header with no records
+    fn empty_pcap() -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+        out.extend_from_slice(&2u16.to_le_bytes());
+        out.extend_from_slice(&4u16.to_le_bytes());
+        out.extend_from_slice(&0i32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&65535u32.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_parse_pcap_data_in_background() {
+        let ctx = egui::Context::default();
+        let mut app = PcapViewerApp::default();
+        ui::file_panel::parse_pcap_data(&mut app, empty_pcap(), &ctx);
+        assert!(app.is_loading);
+
+        let (data, result) = loop {
+            if let Some(parsed) = app.take_parsed_capture() {
+                break parsed;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        };
+        ui::file_panel::finish_parse(&mut app, data, result);
+        assert!(!app.is_loading);
+        assert_eq!(app.loaded_data, Some(empty_pcap()));
+        assert_eq!(
+            app.status_message,
+            "Loaded 0 packets, 0 messages, 0 weenies"
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_merged_captures_have_no_single_file() {
+        let ctx = egui::Context::default();
+        let mut app = PcapViewerApp {
+            loaded_data: Some(empty_pcap()),
+            ..Default::default()
+        };
+        ui::file_panel::merge_pcap_data(&mut app, vec![empty_pcap(), empty_pcap()], &ctx);
+
+        let (data, result) = loop {
+            if let Some(parsed) = app.take_parsed_capture() {
+                break parsed;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        };
+        assert_eq!(data, None);
+        ui::file_panel::finish_parse(&mut app, data, result);
+        assert_eq!(app.loaded_data, None);
+        assert!(app.status_message.starts_with("Loaded 0 packets"));
+    }
+
+    #[test]
+    fn test_superseded_parse_is_dropped() {
+        let mut app = PcapViewerApp {
+            parse_generation: 2,
+            ..Default::default()
+        };
+        let post = |app: &PcapViewerApp, generation| {
+            *app.parsed_capture.lock().unwrap() =
+                Some((generation, None, Err(anyhow::anyhow!("unused"))));
+        };
+
+        post(&app, 1);
+        assert!(app.take_parsed_capture().is_none());
+        post(&app, 2);
+        assert!(app.take_parsed_capture().is_some());
+    }
+
+    #[test]
+    fn test_load_bytes_resets_selection() {
+        let mut app = PcapViewerApp {
+            selected_message: Some(5),
+            pinned_message: Some(5),
+            ..Default::default()
+        };
+
+        app.load_bytes(&empty_pcap()).unwrap();
+        assert!(app.messages.is_empty());
+        assert_eq!(app.selected_message, None);
+        assert_eq!(app.pinned_message, None);
+    }
+
+    #[test]
+    fn test_apply_capture_keeps_marks_only_on_reparse() {
+        let with_sequence = |fragment_sequence| ParsedMessage {
+            fragment_sequence,
+            ..message("Magic_UpdateEnchantment", 1.0)
+        };
+        let capture = || ParsedCapture {
+            packets: Vec::new(),
+            messages: vec![with_sequence(2), with_sequence(1)],
+            weenie_db: common::weenie::WeenieDatabase::new(),
+            truncated: false,
+        };
+        let mut app = PcapViewerApp {
+            messages: vec![with_sequence(1), with_sequence(2)],
+            loaded_data: Some(b"first".to_vec()),
+            selected_message: Some(1),
+            pinned_message: Some(1),
+            marked_messages: [1].into(),
+            marked_packets: [0].into(),
+            ..Default::default()
+        };
+
+        // The same bytes parsed again follow the messages to their new rows
+        app.apply_capture(capture(), Some(b"first".to_vec()));
+        assert_eq!(
+            (app.selected_message, app.pinned_message),
+            (Some(0), Some(0))
+        );
+        assert_eq!(app.marked_messages, [0].into());
+        assert_eq!(app.marked_packets, [0].into());
+
+        // Other parser settings keep message marks but not packet rows
+        app.ip_filter = Some(std::net::Ipv4Addr::LOCALHOST.into());
+        app.apply_capture(capture(), Some(b"first".to_vec()));
+        assert_eq!(app.marked_messages, [0].into());
+        assert!(app.marked_packets.is_empty());
+
+        // Another capture reuses the sequence numbers for unrelated messages
+        app.apply_capture(capture(), Some(b"second".to_vec()));
+        assert_eq!((app.selected_message, app.pinned_message), (Some(0), None));
+        assert!(app.marked_messages.is_empty());
+        assert!(app.marked_packets.is_empty());
+        assert_eq!(app.loaded_data.as_deref(), Some(&b"second"[..]));
+    }
+
+    fn message(message_type: &str, timestamp: f64) -> ParsedMessage {
+        ParsedMessage {
+            message_type: message_type.to_string(),
+            direction: "Recv".to_string(),
+            opcode: "F7B0".to_string(),
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_initial_view() {
+        let mut app = PcapViewerApp {
+            messages: vec![
+                message("Magic_UpdateEnchantment", 1.0),
+                ParsedMessage {
+                    id: 1,
+                    ..message("Magic_UpdateEnchantment", 2.0)
+                },
+            ],
+            initial_filter: Some("Magic".to_string()),
+            initial_selection: Some(1),
+            ..Default::default()
+        };
+
+        app.apply_initial_view();
+        assert_eq!(app.search_query, "Magic");
+        assert_eq!(app.selected_message, Some(1));
+        assert_eq!((app.initial_filter, app.initial_selection), (None, None));
+    }
+
+    #[test]
+    fn test_format_capture_span() {
+        let info = common::CaptureInfo {
+            start: 1_700_000_000.0,
+            end: 1_700_000_329.5,
+            duration_secs: 329.5,
+            packet_count: 10,
+        };
+        assert_eq!(
+            format_capture_span(&info),
+            "22:13:20\u{2013}22:18:49 UTC (5m 29s)"
+        );
+
+        let info = common::CaptureInfo {
+            end: 1_700_000_002.5,
+            duration_secs: 2.5,
+            ..info
+        };
+        assert!(format_capture_span(&info).ends_with("(2.5s)"));
+    }
+
+    #[test]
+    fn test_format_latency() {
+        let latency = common::echo::LatencyStats {
+            count: 3,
+            min: 0.05,
+            median: 0.0825,
+            p95: 0.12,
+            max: 0.12,
+            mean: 0.0842,
+        };
+        assert_eq!(format_latency(&latency), "RTT 82 ms");
+        assert!(format_latency_details(&latency).starts_with("3 echoes\nmin 50.0 ms"));
+    }
+
+    #[test]
+    fn test_go_to_message() {
+        let mut app = PcapViewerApp {
+            messages: vec![
+                ParsedMessage {
+                    id: 7,
+                    ..message("Magic_UpdateEnchantment", 1.0)
+                },
+                ParsedMessage {
+                    id: 8,
+                    ..message("Item_ServerSaysRemove", 2.0)
+                },
+            ],
+            ..Default::default()
+        };
+
+        app.go_to_message(8);
+        assert_eq!(app.selected_message, Some(1));
+        assert_eq!(app.scroll_to_message, Some(1));
+
+        app.selected_message = None;
+        app.search_query = "Magic".to_string();
+        app.go_to_message(8);
+        assert_eq!(app.selected_message, None);
+        assert_eq!(app.hidden_goto_message, Some(1));
+
+        app.go_to_message(99);
+        assert_eq!(app.status_message, "No message #99");
+    }
+
+    #[test]
+    fn test_remember_recent_file() {
+        let mut recent = Vec::new();
+        for i in 0..=state::MAX_RECENT_FILES {
+            state::remember_recent_file(&mut recent, format!("{i}.pcap").into(), i as f64);
+        }
+        assert_eq!(recent.len(), state::MAX_RECENT_FILES);
+        assert_eq!(recent[0].path, std::path::PathBuf::from("10.pcap"));
+
+        // Reopening moves the entry to the front instead of duplicating it
+        state::remember_recent_file(&mut recent, "5.pcap".into(), 20.0);
+        assert_eq!(recent.len(), state::MAX_RECENT_FILES);
+        assert_eq!(recent[0].path, std::path::PathBuf::from("5.pcap"));
+        assert_eq!(
+            recent.iter().filter(|f| f.path == recent[0].path).count(),
+            1
+        );
+
+        assert_eq!(state::format_age(0.0, 30.0), "just now");
+        assert_eq!(state::format_age(0.0, 7200.0), "2 h ago");
+    }
+
+    #[test]
+    fn test_mark_same_type() {
+        let mut app = PcapViewerApp {
+            messages: vec![
+                message("Magic_UpdateEnchantment", 1.0),
+                message("Item_ServerSaysRemove", 2.0),
+                message("Magic_UpdateEnchantment", 3.0),
+            ],
+            ..Default::default()
+        };
+        app.marked_messages.insert(1);
+
+        app.mark_same_type(2);
+        assert_eq!(app.marked_messages, [0, 2].into_iter().collect());
+    }
+
+    #[test]
+    fn test_load_bytes_error_keeps_state() {
+        let mut app = PcapViewerApp {
+            selected_message: Some(5),
+            ..Default::default()
+        };
+
+        assert!(app.load_bytes(b"not a pcap").is_err());
+        assert_eq!(app.selected_message, Some(5));
+    }
+}
@@ -0,0 +1,383 @@
+//! Live capture from a network interface (Linux only, `live` feature)
+//!
+//! Reads Ethernet frames from an `AF_PACKET` raw socket on a background thread
+//! and hands them to the UI thread, which parses them with a long-lived
+//! [`PacketParser`] so fragments reassemble across frames. Opening the socket
+//! needs root or `CAP_NET_RAW`. Parsing stops at
+//! [`DEFAULT_MAX_MESSAGES`](common::DEFAULT_MAX_MESSAGES), as for files.
+
+use anyhow::{Context, Result, bail};
+use common::{Linktype, PacketParser, ParsedPacket, messages::ParsedMessage};
+use std::ffi::CString;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Interface offered by default, for a client running on the same machine
+pub const DEFAULT_INTERFACE: &str = "lo";
+
+/// How often the capture thread wakes to check whether it should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Largest frame read from the socket
+const MAX_FRAME: usize = 65536;
+
+/// Shortest time between rebuilds of the scrubbers and reports while capturing
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A frame read from the socket: capture time, link type and bytes
+type Frame = (f64, Linktype, Vec<u8>);
+
+/// A running capture and the parser state for the frames it delivers
+pub struct LiveCapture {
+    interface: String,
+    frames: Receiver<Frame>,
+    stop: Arc<AtomicBool>,
+    parser: PacketParser,
+    next_packet_id: usize,
+    next_message_id: usize,
+    max_messages: usize,
+    // Whether the message limit has been reached; later frames are dropped
+    truncated: bool,
+    // Whether frames arrived since the views were last rebuilt
+    stale: bool,
+    refreshed_at: Instant,
+}
+
+impl LiveCapture {
+    /// Open a raw socket on `interface` and start reading frames from it
+    ///
+    /// Frames are parsed with `parser`, which keeps its settings.
+    pub fn start(interface: &str, parser: PacketParser) -> Result<Self> {
+        let socket =
+            open_socket(interface).with_context(|| format!("Failed to capture on {interface}"))?;
+        let (sender, frames) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_stop = Arc::clone(&stop);
+        std::thread::Builder::new()
+            .name("live-capture".to_string())
+            .spawn(move || read_frames(socket, sender, thread_stop))
+            .context("Failed to start capture thread")?;
+
+        Ok(Self::from_frames(
+            interface,
+            frames,
+            stop,
+            parser,
+            common::DEFAULT_MAX_MESSAGES,
+        ))
+    }
+
+    fn from_frames(
+        interface: &str,
+        frames: Receiver<Frame>,
+        stop: Arc<AtomicBool>,
+        parser: PacketParser,
+        max_messages: usize,
+    ) -> Self {
+        Self {
+            interface: interface.to_string(),
+            frames,
+            stop,
+            parser,
+            next_packet_id: 0,
+            next_message_id: 0,
+            max_messages,
+            truncated: false,
+            stale: false,
+            refreshed_at: Instant::now(),
+        }
+    }
+
+    pub fn interface(&self) -> &str {
+        &self.interface
+    }
+
+    /// Parse the frames received since the last call
+    ///
+    /// Returns nothing more once the message limit is reached; the messages of
+    /// the frame that crossed it are cut at the limit.
+    pub fn poll(&mut self) -> (Vec<ParsedPacket>, Vec<ParsedMessage>) {
+        let mut packets = Vec::new();
+        let mut messages = Vec::new();
+        while !self.truncated
+            && let Ok((timestamp, linktype, frame)) = self.frames.try_recv()
+        {
+            if let Ok((mut frame_packets, frame_messages)) = self.parser.parse_frame(
+                linktype,
+                &frame,
+                timestamp,
+                &mut self.next_packet_id,
+                &mut self.next_message_id,
+            ) {
+                packets.append(&mut frame_packets);
+                messages.extend(frame_messages);
+            }
+            if self.next_message_id >= self.max_messages {
+                let excess = self.next_message_id - self.max_messages;
+                messages.truncate(messages.len().saturating_sub(excess));
+                self.truncated = true;
+                self.stop.store(true, Ordering::Relaxed);
+            }
+        }
+        self.stale |= !packets.is_empty() || !messages.is_empty();
+        (packets, messages)
+    }
+
+    /// Whether parsing stopped at the message limit
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Whether the views should be rebuilt for the data polled so far
+    ///
+    /// Rebuilding scans the whole capture, so while frames keep arriving it
+    /// happens at most once per [`REFRESH_INTERVAL`].
+    pub fn take_refresh(&mut self) -> bool {
+        if !self.stale || self.refreshed_at.elapsed() < REFRESH_INTERVAL {
+            return false;
+        }
+        self.stale = false;
+        self.refreshed_at = Instant::now();
+        true
+    }
+}
+
+impl Drop for LiveCapture {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Open an `AF_PACKET` socket bound to `interface`, with a receive timeout
+fn open_socket(interface: &str) -> Result<OwnedFd> {
+    let protocol = (libc::ETH_P_ALL as u16).to_be();
+    let name = CString::new(interface).context("Interface name contains a NUL byte")?;
+
+    // SAFETY: plain libc calls; the fd is owned by OwnedFd as soon as it is valid
+    // and every pointer passed refers to a live, correctly sized value
+    unsafe {
+        let fd = libc::socket(libc::AF_PACKET, libc::SOCK_RAW, protocol as libc::c_int);
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let socket = OwnedFd::from_raw_fd(fd);
+
+        let index = libc::if_nametoindex(name.as_ptr());
+        if index == 0 {
+            bail!("No such interface");
+        }
+
+        let mut address: libc::sockaddr_ll = std::mem::zeroed();
+        address.sll_family = libc::AF_PACKET as libc::c_ushort;
+        address.sll_protocol = protocol;
+        address.sll_ifindex = index as libc::c_int;
+        if libc::bind(
+            fd,
+            &address as *const libc::sockaddr_ll as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+        ) < 0
+        {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        let timeout = libc::timeval {
+            tv_sec: 0,
+            tv_usec: POLL_INTERVAL.as_micros() as libc::suseconds_t,
+        };
+        if libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_RCVTIMEO,
+            &timeout as *const libc::timeval as *const libc::c_void,
+            std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+        ) < 0
+        {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        Ok(socket)
+    }
+}
+
+/// Link type of a frame read from an `AF_PACKET` socket, or `None` to skip it
+///
+/// `hatype` and `pkttype` come from the frame's `sockaddr_ll`. Loopback
+/// delivers each packet twice, once as outgoing and once as incoming, so the
+/// outgoing copy is dropped. Interfaces without a link layer (tun devices)
+/// deliver bare IP; other link types aren't supported.
+fn frame_linktype(hatype: u16, pkttype: u8) -> Option<Linktype> {
+    match hatype {
+        libc::ARPHRD_LOOPBACK if pkttype == libc::PACKET_OUTGOING => None,
+        libc::ARPHRD_ETHER | libc::ARPHRD_LOOPBACK => Some(Linktype::ETHERNET),
+        libc::ARPHRD_NONE => Some(Linktype::RAW),
+        _ => None,
+    }
+}
+
+/// Forward frames to the UI thread until stopped or the receiver goes away
+fn read_frames(socket: OwnedFd, sender: Sender<Frame>, stop: Arc<AtomicBool>) {
+    let mut buffer = vec![0u8; MAX_FRAME];
+    while !stop.load(Ordering::Relaxed) {
+        // SAFETY: buffer and address outlive the call and their sizes are passed along
+        let (len, address) = unsafe {
+            let mut address: libc::sockaddr_ll = std::mem::zeroed();
+            let mut address_len = std::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t;
+            let len = libc::recvfrom(
+                socket.as_raw_fd(),
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+                0,
+                &mut address as *mut libc::sockaddr_ll as *mut libc::sockaddr,
+                &mut address_len,
+            );
+            (len, address)
+        };
+        if len < 0 {
+            // Timeouts and interrupted calls just loop back to the stop check
+            match std::io::Error::last_os_error().kind() {
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::Interrupted => continue,
+                _ => break,
+            }
+        }
+
+        let Some(linktype) = frame_linktype(address.sll_hatype, address.sll_pkttype) else {
+            continue;
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs_f64())
+            .unwrap_or_default();
+        if sender
+            .send((timestamp, linktype, buffer[..len as usize].to_vec()))
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ethernet + IPv4 + UDP frame carrying one single-chunk AC message
+    fn frame(src_port: u16, dst_port: u16, fragment_sequence: u32) -> Vec<u8> {
+        let mut message = Vec::new();
+        message.extend_from_slice(&0xF7B0u32.to_le_bytes());
+        message.extend_from_slice(&0x5000_0001u32.to_le_bytes()); // object id
+        message.extend_from_slice(&1u32.to_le_bytes()); // sequence
+        message.extend_from_slice(&0x00C9u32.to_le_bytes()); // event type
+
+        let size = 16 + message.len() as u16;
+        let mut packet = Vec::new();
+        packet.extend_from_slice(&fragment_sequence.to_le_bytes());
+        packet.extend_from_slice(
+            &common::PacketHeaderFlags::BLOB_FRAGMENTS
+                .bits()
+                .to_le_bytes(),
+        );
+        packet.extend_from_slice(&[0u8; 8]); // checksum, id, time
+        packet.extend_from_slice(&size.to_le_bytes());
+        packet.extend_from_slice(&0u16.to_le_bytes()); // iteration
+        packet.extend_from_slice(&fragment_sequence.to_le_bytes());
+        packet.extend_from_slice(&0x8000_0000u32.to_le_bytes()); // fragment id
+        packet.extend_from_slice(&1u16.to_le_bytes()); // count
+        packet.extend_from_slice(&size.to_le_bytes());
+        packet.extend_from_slice(&[0u8; 4]); // index, group
+        packet.extend_from_slice(&message);
+
+        let mut out = vec![0u8; 42];
+        out[12..14].copy_from_slice(&0x0800u16.to_be_bytes());
+        out[14] = 0x45;
+        out[23] = 17;
+        out[34..36].copy_from_slice(&src_port.to_be_bytes());
+        out[36..38].copy_from_slice(&dst_port.to_be_bytes());
+        out.extend_from_slice(&packet);
+        out
+    }
+
+    fn capture(max_messages: usize) -> (Sender<Frame>, LiveCapture) {
+        let (sender, frames) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let capture =
+            LiveCapture::from_frames("test", frames, stop, PacketParser::new(), max_messages);
+        (sender, capture)
+    }
+
+    #[test]
+    fn test_frame_linktype() {
+        assert_eq!(
+            frame_linktype(libc::ARPHRD_LOOPBACK, libc::PACKET_OUTGOING),
+            None
+        );
+        assert_eq!(
+            frame_linktype(libc::ARPHRD_LOOPBACK, libc::PACKET_HOST),
+            Some(Linktype::ETHERNET)
+        );
+        // On a real interface the outgoing copy is the client's only copy
+        assert_eq!(
+            frame_linktype(libc::ARPHRD_ETHER, libc::PACKET_OUTGOING),
+            Some(Linktype::ETHERNET)
+        );
+        assert_eq!(
+            frame_linktype(libc::ARPHRD_NONE, libc::PACKET_HOST),
+            Some(Linktype::RAW)
+        );
+        assert_eq!(
+            frame_linktype(libc::ARPHRD_IEEE80211, libc::PACKET_HOST),
+            None
+        );
+    }
+
+    #[test]
+    fn test_poll_parses_queued_frames() {
+        let (sender, mut capture) = capture(10);
+        sender
+            .send((1.0, Linktype::ETHERNET, frame(9000, 50000, 1)))
+            .unwrap();
+        sender
+            .send((2.0, Linktype::ETHERNET, frame(50000, 9000, 1)))
+            .unwrap();
+        // A raw IP frame is the same bytes without the Ethernet header
+        let raw = frame(9000, 50000, 2)[14..].to_vec();
+        sender.send((3.0, Linktype::RAW, raw)).unwrap();
+
+        let (packets, messages) = capture.poll();
+        assert_eq!(packets.len(), 3);
+        let directions: Vec<_> = messages.iter().map(|m| m.direction.as_str()).collect();
+        assert_eq!(directions, ["Recv", "Send", "Recv"]);
+        assert!(!capture.truncated());
+
+        // Nothing new, so nothing to refresh until the next frames arrive
+        assert!(capture.poll().0.is_empty());
+    }
+
+    #[test]
+    fn test_poll_stops_at_message_limit() {
+        let (sender, mut capture) = capture(2);
+        for sequence in 1..=3 {
+            sender
+                .send((
+                    f64::from(sequence),
+                    Linktype::ETHERNET,
+                    frame(9000, 50000, sequence),
+                ))
+                .unwrap();
+        }
+
+        let (_, messages) = capture.poll();
+        assert_eq!(messages.len(), 2);
+        assert!(capture.truncated());
+        assert!(capture.stop.load(Ordering::Relaxed));
+
+        sender
+            .send((4.0, Linktype::ETHERNET, frame(9000, 50000, 4)))
+            .unwrap();
+        assert!(capture.poll().1.is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+//! Shareable deep links for captures loaded from a URL or Discord
+//!
+//! The web build reads the same `?url=` and `?channel=&msg=` query parameters
+//! on startup, so a link built here reopens the capture. The `filter` and
+//! `select` parameters restore the search box and selected message once it
+//! has loaded.
+
+/// Where a capture was fetched from, in the terms of the web query parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShareSource {
+    Url(String),
+    Discord {
+        channel: String,
+        msg: String,
+        /// Attachment index or file name, for messages with several captures
+        attachment: Option<String>,
+    },
+}
+
+/// Longest search filter accepted from a link
+pub const MAX_FILTER_LEN: usize = 256;
+
+/// Link to `base` that reopens `source`, with `filter` as the search query
+/// and `select` as the selected message id
+///
+/// `base` is the viewer page without a query string. An empty `filter` is
+/// left out.
+pub fn share_link(base: &str, source: &ShareSource, filter: &str, select: Option<usize>) -> String {
+    let mut params = match source {
+        ShareSource::Url(url) => vec![("url", url.as_str())],
+        ShareSource::Discord {
+            channel,
+            msg,
+            attachment,
+        } => {
+            let mut params = vec![("channel", channel.as_str()), ("msg", msg.as_str())];
+            if let Some(attachment) = attachment {
+                params.push(("attachment", attachment.as_str()));
+            }
+            params
+        }
+    };
+    if !filter.is_empty() {
+        params.push(("filter", filter));
+    }
+    let select = select.map(|id| id.to_string());
+    if let Some(select) = &select {
+        params.push(("select", select));
+    }
+
+    let query: Vec<String> = params
+        .into_iter()
+        .map(|(key, value)| format!("{key}={}", encode_query_value(value)))
+        .collect();
+    format!("{base}?{}", query.join("&"))
+}
+
+/// Clean up a search filter taken from a link
+///
+/// Drops control characters, trims whitespace and caps the length at
+/// [`MAX_FILTER_LEN`] characters. `None` when nothing is left.
+pub fn sanitize_filter(raw: &str) -> Option<String> {
+    let filter: String = raw
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_FILTER_LEN)
+        .collect();
+    let filter = filter.trim();
+    (!filter.is_empty()).then(|| filter.to_string())
+}
+
+/// Message id from a link or the "Go to #" box, with or without a leading `#`
+pub fn parse_message_id(raw: &str) -> Option<usize> {
+    let raw = raw.trim();
+    raw.strip_prefix('#').unwrap_or(raw).parse().ok()
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+fn encode_query_value(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// The current page without its query string, which share links build on
+#[cfg(target_arch = "wasm32")]
+pub fn page_base_url() -> Option<String> {
+    let location = web_sys::window()?.location();
+    Some(format!(
+        "{}{}",
+        location.origin().ok()?,
+        location.pathname().ok()?
+    ))
+}
+
+/// Share links only make sense for the web build
+#[cfg(not(target_arch = "wasm32"))]
+pub fn page_base_url() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_link() {
+        let base = "https://pcap.treestats.net/";
+        let url = ShareSource::Url("https://example.com/a b.pcap?x=1".to_string());
+        assert_eq!(
+            share_link(base, &url, "", None),
+            "https://pcap.treestats.net/?url=https%3A%2F%2Fexample.com%2Fa%20b.pcap%3Fx%3D1"
+        );
+
+        let discord = ShareSource::Discord {
+            channel: "123".to_string(),
+            msg: "456".to_string(),
+            attachment: Some("1".to_string()),
+        };
+        assert_eq!(
+            share_link(base, &discord, "type:Magic & more", Some(42)),
+            "https://pcap.treestats.net/?channel=123&msg=456&attachment=1&filter=type%3AMagic%20%26%20more&select=42"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_filter() {
+        assert_eq!(
+            sanitize_filter("  type:Magic\n\u{7}").as_deref(),
+            Some("type:Magic")
+        );
+        assert_eq!(sanitize_filter(" \t "), None);
+        assert_eq!(
+            sanitize_filter(&"x".repeat(1000)).map(|f| f.len()),
+            Some(MAX_FILTER_LEN)
+        );
+    }
+
+    #[test]
+    fn test_parse_message_id() {
+        assert_eq!(parse_message_id("42"), Some(42));
+        assert_eq!(parse_message_id(" #42 "), Some(42));
+        assert_eq!(parse_message_id("-1"), None);
+        assert_eq!(parse_message_id("abc"), None);
+    }
+}
@@ -11,39 +11,72 @@ pub const TABLET_BREAKPOINT: f32 = 1024.0;
 // Mobile UI scaling factor
 pub const MOBILE_SCALE: f32 = 1.5;
 
+/// Storage key for persisted UI preferences
+pub const SETTINGS_KEY: &str = "ui_settings";
+
+/// UI preferences persisted across sessions (never loaded capture data)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct UiSettings {
+    pub dark_mode: bool,
+    pub sort_field: common::SortField,
+    pub sort_ascending: bool,
+    pub view_mode: common::ViewMode,
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            dark_mode: true,
+            sort_field: common::SortField::Id,
+            sort_ascending: true,
+            view_mode: common::ViewMode::Tree,
+        }
+    }
+}
+
+/// Storage key for the desktop recent-files list
+pub const RECENT_FILES_KEY: &str = "recent_files";
+
+/// Number of captures kept under File > Open Recent
+pub const MAX_RECENT_FILES: usize = 10;
+
+/// A capture opened from disk, for File > Open Recent
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RecentFile {
+    pub path: std::path::PathBuf,
+    /// When it was last opened, in seconds since the Unix epoch
+    pub opened: f64,
+}
+
+/// Move `path` to the front of `recent`, keeping at most [`MAX_RECENT_FILES`]
+pub fn remember_recent_file(recent: &mut Vec<RecentFile>, path: std::path::PathBuf, opened: f64) {
+    recent.retain(|file| file.path != path);
+    recent.insert(0, RecentFile { path, opened });
+    recent.truncate(MAX_RECENT_FILES);
+}
+
+/// Current time in seconds since the Unix epoch (not available on WASM)
+pub fn unix_time() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs_f64())
+        .unwrap_or_default()
+}
+
+/// Short description of how long ago `then` was, such as `5 min ago`
+pub fn format_age(then: f64, now: f64) -> String {
+    let seconds = (now - then).max(0.0) as u64;
+    match seconds {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{} min ago", seconds / 60),
+        3600..86400 => format!("{} h ago", seconds / 3600),
+        _ => format!("{} days ago", seconds / 86400),
+    }
+}
+
 // Shared state for async loading
 #[allow(dead_code)]
 pub type SharedData = Arc<Mutex<Option<Vec<u8>>>>;
 #[allow(dead_code)]
 pub type SharedError = Arc<Mutex<Option<String>>>;
-
-/// Recursively search for a string within a JSON value (case-insensitive)
-/// Searches in both field names and values, including numeric values
-pub fn json_contains_string(value: &serde_json::Value, search: &str) -> bool {
-    let search_lower = search.to_lowercase();
-
-    match value {
-        serde_json::Value::String(s) => s.to_lowercase().contains(&search_lower),
-        serde_json::Value::Number(n) => {
-            // Check if the search string matches the numeric value as a string
-            n.to_string().contains(&search_lower) ||
-            // Also check if search is a number and matches exactly
-            if let Ok(search_num) = search.parse::<i64>() {
-                n.as_i64() == Some(search_num)
-            } else if let Ok(search_num) = search.parse::<u64>() {
-                n.as_u64() == Some(search_num)
-            } else if let Ok(search_num) = search.parse::<f64>() {
-                n.as_f64() == Some(search_num)
-            } else {
-                false
-            }
-        }
-        serde_json::Value::Array(arr) => arr.iter().any(|v| json_contains_string(v, search)),
-        serde_json::Value::Object(obj) => {
-            // Search in both keys and values
-            obj.keys().any(|k| k.to_lowercase().contains(&search_lower))
-                || obj.values().any(|v| json_contains_string(v, search))
-        }
-        _ => false,
-    }
-}
@@ -1,6 +1,8 @@
 //! Time scrubber component for filtering packets by timestamp
 //!
 //! Provides a visual timeline with density visualization and interactive time range selection.
+//! The scroll wheel zooms around the pointer and a right/middle drag pans the zoomed view;
+//! all reported times stay in absolute capture time.
 
 use eframe::egui;
 
@@ -42,6 +44,10 @@ pub struct TimeScrubber {
     pub data_range: Option<TimeRange>,
     /// Density estimation data points (time, density)
     density_data: Vec<(f64, f32)>,
+    /// Throughput per density bin (time, bytes per second)
+    throughput_data: Vec<(f64, f32)>,
+    /// Plot bytes per second instead of item counts
+    pub show_throughput: bool,
     /// Dragging state
     drag_start: Option<f64>,
     /// Hover position
@@ -50,6 +56,31 @@ pub struct TimeScrubber {
     highlighted_timestamps: Vec<f64>,
     /// Marked timestamps (purple indicators)
     marked_timestamps: Vec<f64>,
+    /// Visible time window when zoomed in (None shows the full data range)
+    view_range: Option<TimeRange>,
+}
+
+/// Smallest visible window, in seconds, when zooming in
+const MIN_VIEW_SPAN: f64 = 0.01;
+
+/// Number of histogram bins across the data range
+const NUM_BINS: usize = 100;
+
+/// Histogram bin for a timestamp, clamped to the last bin
+fn bin_index(timestamp: f64, min_time: f64, bin_width: f64) -> usize {
+    let bin_index = ((timestamp - min_time) / bin_width).floor() as usize;
+    bin_index.min(NUM_BINS - 1)
+}
+
+/// Human-readable bytes per second
+fn format_rate(bytes_per_second: f32) -> String {
+    if bytes_per_second >= 1024.0 * 1024.0 {
+        format!("{:.1} MB/s", bytes_per_second / (1024.0 * 1024.0))
+    } else if bytes_per_second >= 1024.0 {
+        format!("{:.1} KB/s", bytes_per_second / 1024.0)
+    } else {
+        format!("{bytes_per_second:.0} B/s")
+    }
 }
 
 impl Default for TimeScrubber {
@@ -64,10 +95,13 @@ impl TimeScrubber {
             selected_range: None,
             data_range: None,
             density_data: Vec::new(),
+            throughput_data: Vec::new(),
+            show_throughput: false,
             drag_start: None,
             hover_time: None,
             highlighted_timestamps: Vec::new(),
             marked_timestamps: Vec::new(),
+            view_range: None,
         }
     }
 
@@ -75,26 +109,44 @@ impl TimeScrubber {
     pub fn update_density(&mut self, timestamps: &[f64]) {
         if timestamps.is_empty() {
             self.density_data.clear();
+            self.throughput_data.clear();
             self.data_range = None;
             self.selected_range = None;
+            self.view_range = None;
             return;
         }
 
         let min_time = timestamps.iter().copied().fold(f64::INFINITY, f64::min);
         let max_time = timestamps.iter().copied().fold(f64::NEG_INFINITY, f64::max);
 
+        // A selection covering all of the old data keeps covering all of it, so
+        // a growing live capture stays fully visible
+        let was_full_range = match (&self.selected_range, &self.data_range) {
+            (Some(selected), Some(data)) => selected.is_full_range(data.min, data.max),
+            _ => false,
+        };
+
+        // Likewise a zoomed view survives data that only extends the range, as
+        // when a live capture grows; any other change starts unzoomed
+        let grew = self
+            .data_range
+            .as_ref()
+            .is_some_and(|data| min_time <= data.min && max_time >= data.max);
+
         self.data_range = Some(TimeRange::new(min_time, max_time));
+        if let Some(view) = self.view_range.take()
+            && grew
+        {
+            self.set_view(view.min, view.max - view.min);
+        }
 
         // Calculate histogram bins
-        let num_bins = 100;
-        let bin_width = (max_time - min_time) / num_bins as f64;
+        let bin_width = (max_time - min_time) / NUM_BINS as f64;
 
         // Count packets in each bin
-        let mut bins = vec![0u32; num_bins];
+        let mut bins = vec![0u32; NUM_BINS];
         for &timestamp in timestamps {
-            let bin_index = ((timestamp - min_time) / bin_width).floor() as usize;
-            let bin_index = bin_index.min(num_bins - 1); // Clamp to last bin
-            bins[bin_index] += 1;
+            bins[bin_index(timestamp, min_time, bin_width)] += 1;
         }
 
         // Store bin data as (time, count)
@@ -105,11 +157,40 @@ impl TimeScrubber {
         }
 
         // Initialize selected range to full range
-        if self.selected_range.is_none() {
+        if self.selected_range.is_none() || was_full_range {
             self.selected_range = Some(TimeRange::new(min_time, max_time));
         }
     }
 
+    /// Sum byte sizes into the density bins as bytes per second
+    ///
+    /// Call after [`Self::update_density`] so the bins cover the same range.
+    /// Samples are `(timestamp, size in bytes)`.
+    pub fn update_throughput(&mut self, samples: &[(f64, usize)]) {
+        self.throughput_data.clear();
+        let Some(range) = self.data_range.clone() else {
+            return;
+        };
+
+        let bin_width = (range.max - range.min) / NUM_BINS as f64;
+        let mut bins = vec![0usize; NUM_BINS];
+        for &(timestamp, size) in samples {
+            bins[bin_index(timestamp, range.min, bin_width)] += size;
+        }
+        // Without raw bytes there is nothing to plot
+        if bins.iter().all(|&bytes| bytes == 0) {
+            return;
+        }
+
+        // A capture with a single timestamp has zero-width bins; report raw totals
+        let seconds = if bin_width > 0.0 { bin_width } else { 1.0 };
+        for (i, &bytes) in bins.iter().enumerate() {
+            let t = range.min + (i as f64 + 0.5) * bin_width;
+            self.throughput_data
+                .push((t, (bytes as f64 / seconds) as f32));
+        }
+    }
+
     /// Reset selection to show all data
     pub fn reset_selection(&mut self) {
         if let Some(ref range) = self.data_range {
@@ -117,6 +198,53 @@ impl TimeScrubber {
         }
     }
 
+    /// Time window currently shown, which is the full data range unless zoomed
+    pub fn visible_range(&self) -> Option<TimeRange> {
+        self.view_range.clone().or_else(|| self.data_range.clone())
+    }
+
+    /// Whether the view is zoomed in on part of the data range
+    pub fn is_zoomed(&self) -> bool {
+        self.view_range.is_some()
+    }
+
+    /// Zoom the view by `factor` (< 1 zooms in) keeping `anchor` at the same screen position
+    pub fn zoom(&mut self, anchor: f64, factor: f64) {
+        let (Some(data), Some(view)) = (self.data_range.clone(), self.visible_range()) else {
+            return;
+        };
+        let data_span = data.max - data.min;
+        let view_span = view.max - view.min;
+        if data_span <= 0.0 || view_span <= 0.0 {
+            return;
+        }
+
+        let new_span = (view_span * factor).clamp(MIN_VIEW_SPAN.min(data_span), data_span);
+        let anchor_ratio = ((anchor - view.min) / view_span).clamp(0.0, 1.0);
+        let min = anchor - anchor_ratio * new_span;
+        self.set_view(min, new_span);
+    }
+
+    /// Shift the zoomed view by `delta` seconds, staying within the data range
+    pub fn pan(&mut self, delta: f64) {
+        if let Some(view) = self.view_range.clone() {
+            self.set_view(view.min + delta, view.max - view.min);
+        }
+    }
+
+    /// Set the visible window, clamped to the data range
+    fn set_view(&mut self, min: f64, span: f64) {
+        let Some(data) = self.data_range.clone() else {
+            return;
+        };
+        if span >= data.max - data.min {
+            self.view_range = None;
+            return;
+        }
+        let min = min.clamp(data.min, data.max - span);
+        self.view_range = Some(TimeRange::new(min, min + span));
+    }
+
     /// Check if we have data
     pub fn has_data(&self) -> bool {
         self.data_range.is_some() && !self.density_data.is_empty()
@@ -153,6 +281,7 @@ impl TimeScrubber {
 
         let data_range = self.data_range.clone().unwrap();
         let selected_range = self.selected_range.clone().unwrap();
+        let view_range = self.visible_range().unwrap();
 
         let mut response = ScrubberResponse::default();
 
@@ -164,6 +293,7 @@ impl TimeScrubber {
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     if ui.button("Reset").clicked() {
                         self.reset_selection();
+                        self.view_range = None;
                     }
 
                     // Reset Marks button (only show if there are marked timestamps)
@@ -184,6 +314,17 @@ impl TimeScrubber {
                         )
                     };
                     ui.label(range_text);
+
+                    if !self.throughput_data.is_empty() {
+                        ui.checkbox(&mut self.show_throughput, "Bytes/s");
+                    }
+
+                    if self.is_zoomed() {
+                        ui.label(format!(
+                            "Zoom: {:.1}x",
+                            (data_range.max - data_range.min) / (view_range.max - view_range.min)
+                        ));
+                    }
                 });
             });
 
@@ -197,21 +338,29 @@ impl TimeScrubber {
             );
 
             let rect = egui_response.rect;
-            let time_range = data_range.max - data_range.min;
+            let time_range = view_range.max - view_range.min;
+            let x_for = |time: f64| {
+                rect.min.x + ((time - view_range.min) / time_range) as f32 * rect.width()
+            };
+            let in_view = |x: f32| x >= rect.min.x && x <= rect.max.x;
 
             // Background
             painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
 
+            // Plot throughput instead of item counts when toggled on
+            let bars = if self.show_throughput && !self.throughput_data.is_empty() {
+                &self.throughput_data
+            } else {
+                &self.density_data
+            };
+
             // Calculate max density for normalization
-            let max_density = self
-                .density_data
-                .iter()
-                .map(|(_, d)| *d)
-                .fold(0.0f32, f32::max);
+            let max_density = bars.iter().map(|(_, d)| *d).fold(0.0f32, f32::max);
 
             if max_density > 0.0 {
                 // Draw histogram bars
-                let bar_width = rect.width() / self.density_data.len() as f32;
+                let bar_width = rect.width() / bars.len() as f32
+                    * ((data_range.max - data_range.min) / time_range) as f32;
 
                 let fill_color = if ui.visuals().dark_mode {
                     egui::Color32::from_rgba_unmultiplied(100, 150, 255, 120)
@@ -225,9 +374,11 @@ impl TimeScrubber {
                     egui::Color32::from_rgba_unmultiplied(50, 100, 200, 200)
                 };
 
-                for (time, density) in &self.density_data {
-                    let x =
-                        rect.min.x + ((*time - data_range.min) / time_range) as f32 * rect.width();
+                for (time, density) in bars {
+                    let x = x_for(*time);
+                    if x + bar_width < rect.min.x || x - bar_width > rect.max.x {
+                        continue;
+                    }
                     let normalized_density = density / max_density;
                     let bar_height = normalized_density * height;
 
@@ -235,20 +386,32 @@ impl TimeScrubber {
                     let bar_rect = egui::Rect::from_min_size(
                         egui::pos2(x - bar_width * 0.4, rect.max.y - bar_height),
                         egui::vec2(bar_width * 0.8, bar_height),
-                    );
+                    )
+                    .intersect(rect);
 
                     painter.rect_filled(bar_rect, 1.0, fill_color);
                     painter.rect_stroke(bar_rect, 1.0, egui::Stroke::new(0.5, stroke_color));
                 }
+
+                if self.show_throughput && !self.throughput_data.is_empty() {
+                    painter.text(
+                        rect.left_top() + egui::vec2(4.0, 2.0),
+                        egui::Align2::LEFT_TOP,
+                        format!("Peak {}", format_rate(max_density)),
+                        egui::FontId::proportional(10.0),
+                        ui.visuals().weak_text_color(),
+                    );
+                }
             }
 
             // Draw highlighted timestamps (search results) as yellow vertical lines
             if !self.highlighted_timestamps.is_empty() {
                 let highlight_color = egui::Color32::from_rgb(255, 220, 0); // Bright yellow
                 for &timestamp in &self.highlighted_timestamps {
-                    let x = rect.min.x
-                        + ((timestamp - data_range.min) / time_range) as f32 * rect.width();
-                    painter.vline(x, rect.y_range(), egui::Stroke::new(2.0, highlight_color));
+                    let x = x_for(timestamp);
+                    if in_view(x) {
+                        painter.vline(x, rect.y_range(), egui::Stroke::new(2.0, highlight_color));
+                    }
                 }
             }
 
@@ -256,18 +419,17 @@ impl TimeScrubber {
             if !self.marked_timestamps.is_empty() {
                 let mark_color = egui::Color32::from_rgb(160, 80, 255); // Purple
                 for &timestamp in &self.marked_timestamps {
-                    let x = rect.min.x
-                        + ((timestamp - data_range.min) / time_range) as f32 * rect.width();
-                    painter.vline(x, rect.y_range(), egui::Stroke::new(2.0, mark_color));
+                    let x = x_for(timestamp);
+                    if in_view(x) {
+                        painter.vline(x, rect.y_range(), egui::Stroke::new(2.0, mark_color));
+                    }
                 }
             }
 
             // Draw selected range overlay
             if !selected_range.is_full_range(data_range.min, data_range.max) {
-                let sel_start_x = rect.min.x
-                    + ((selected_range.min - data_range.min) / time_range) as f32 * rect.width();
-                let sel_end_x = rect.min.x
-                    + ((selected_range.max - data_range.min) / time_range) as f32 * rect.width();
+                let sel_start_x = x_for(selected_range.min).clamp(rect.min.x, rect.max.x);
+                let sel_end_x = x_for(selected_range.max).clamp(rect.min.x, rect.max.x);
 
                 let selection_rect = egui::Rect::from_min_max(
                     egui::pos2(sel_start_x, rect.min.y),
@@ -281,18 +443,16 @@ impl TimeScrubber {
                 };
                 painter.rect_filled(selection_rect, 0.0, selection_color);
 
-                // Draw range borders
+                // Draw range borders that fall inside the view
                 let border_color = egui::Color32::from_rgb(255, 200, 100);
-                painter.vline(
-                    sel_start_x,
-                    rect.y_range(),
-                    egui::Stroke::new(2.0, border_color),
-                );
-                painter.vline(
-                    sel_end_x,
-                    rect.y_range(),
-                    egui::Stroke::new(2.0, border_color),
-                );
+                for (time, x) in [
+                    (selected_range.min, sel_start_x),
+                    (selected_range.max, sel_end_x),
+                ] {
+                    if in_view(x_for(time)) {
+                        painter.vline(x, rect.y_range(), egui::Stroke::new(2.0, border_color));
+                    }
+                }
             }
 
             // Handle interactions
@@ -302,7 +462,7 @@ impl TimeScrubber {
             if let Some(pointer_pos) = egui_response.interact_pointer_pos() {
                 if rect.contains(pointer_pos) {
                     let x_ratio = (pointer_pos.x - rect.min.x) / rect.width();
-                    let hover_time_val = data_range.min + x_ratio as f64 * time_range;
+                    let hover_time_val = view_range.min + x_ratio as f64 * time_range;
                     self.hover_time = Some(hover_time_val);
 
                     // Draw hover line
@@ -321,14 +481,37 @@ impl TimeScrubber {
                 self.hover_time = None;
             }
 
+            // Scroll wheel zooms around the pointer, horizontal scroll pans
+            if egui_response.hovered()
+                && let Some(pointer_pos) = egui_response.hover_pos()
+            {
+                let scroll = ui.input(|i| i.smooth_scroll_delta);
+                if scroll.y != 0.0 {
+                    let x_ratio = ((pointer_pos.x - rect.min.x) / rect.width()) as f64;
+                    let anchor = view_range.min + x_ratio * time_range;
+                    self.zoom(anchor, (-scroll.y as f64 * 0.005).exp());
+                }
+                if scroll.x != 0.0 {
+                    self.pan(-scroll.x as f64 / rect.width() as f64 * time_range);
+                }
+            }
+
+            // Right or middle drag pans the zoomed view
+            if egui_response.dragged_by(egui::PointerButton::Secondary)
+                || egui_response.dragged_by(egui::PointerButton::Middle)
+            {
+                let delta = egui_response.drag_delta().x as f64;
+                self.pan(-delta / rect.width() as f64 * time_range);
+            }
+
             // Handle dragging for range selection
-            if egui_response.drag_started()
+            if egui_response.drag_started_by(egui::PointerButton::Primary)
                 && let Some(hover) = self.hover_time
             {
                 self.drag_start = Some(hover);
             }
 
-            if egui_response.dragged()
+            if egui_response.dragged_by(egui::PointerButton::Primary)
                 && let (Some(start), Some(current)) = (self.drag_start, self.hover_time)
             {
                 let min = start.min(current);
@@ -432,6 +615,38 @@ mod tests {
         assert_eq!(scrubber.get_highlighted_timestamps(), &[5.0, 6.0, 7.0, 8.0]);
     }
 
+    #[test]
+    fn test_update_throughput_sums_bytes_per_bin() {
+        let mut scrubber = TimeScrubber::new();
+        scrubber.update_density(&[0.0, 0.5, 100.0]);
+        scrubber.update_throughput(&[(0.0, 100), (0.5, 50), (100.0, 10)]);
+
+        // 100 bins over 100 seconds, so one second per bin
+        assert!(!scrubber.show_throughput);
+        assert_eq!(scrubber.throughput_data.len(), 100);
+        assert_eq!(scrubber.throughput_data[0].1, 150.0);
+        assert_eq!(scrubber.throughput_data[99].1, 10.0);
+        assert_eq!(format_rate(2048.0), "2.0 KB/s");
+
+        scrubber.update_density(&[]);
+        assert!(scrubber.throughput_data.is_empty());
+    }
+
+    #[test]
+    fn test_full_selection_follows_new_data() {
+        let mut scrubber = TimeScrubber::new();
+        scrubber.update_density(&[0.0, 10.0]);
+        scrubber.update_density(&[0.0, 10.0, 20.0]);
+        let selected = scrubber.get_selected_range().unwrap();
+        assert_eq!((selected.min, selected.max), (0.0, 20.0));
+
+        // A narrowed selection is left alone
+        scrubber.selected_range = Some(TimeRange::new(5.0, 6.0));
+        scrubber.update_density(&[0.0, 30.0]);
+        let selected = scrubber.get_selected_range().unwrap();
+        assert_eq!((selected.min, selected.max), (5.0, 6.0));
+    }
+
     #[test]
     fn test_update_density_preserves_highlights() {
         let mut scrubber = TimeScrubber::new();
@@ -467,6 +682,68 @@ mod tests {
         assert_eq!(scrubber.get_highlighted_timestamps(), &highlights);
     }
 
+    #[test]
+    fn test_zoom_keeps_anchor_in_place() {
+        let mut scrubber = TimeScrubber::new();
+        scrubber.update_density(&[0.0, 100.0]);
+
+        scrubber.zoom(25.0, 0.5);
+        let view = scrubber.visible_range().unwrap();
+        assert_eq!((view.min, view.max), (12.5, 62.5));
+        assert!(scrubber.is_zoomed());
+
+        // Zooming back out past the full range returns to the unzoomed view
+        scrubber.zoom(25.0, 4.0);
+        assert!(!scrubber.is_zoomed());
+        let view = scrubber.visible_range().unwrap();
+        assert_eq!((view.min, view.max), (0.0, 100.0));
+    }
+
+    #[test]
+    fn test_pan_stays_within_data_range() {
+        let mut scrubber = TimeScrubber::new();
+        scrubber.update_density(&[0.0, 100.0]);
+
+        // Panning has no effect until zoomed in
+        scrubber.pan(10.0);
+        assert!(!scrubber.is_zoomed());
+
+        scrubber.zoom(0.0, 0.1);
+        scrubber.pan(50.0);
+        let view = scrubber.visible_range().unwrap();
+        assert_eq!((view.min, view.max), (50.0, 60.0));
+
+        scrubber.pan(1000.0);
+        let view = scrubber.visible_range().unwrap();
+        assert_eq!((view.min, view.max), (90.0, 100.0));
+    }
+
+    #[test]
+    fn test_zoom_survives_growing_data() {
+        let mut scrubber = TimeScrubber::new();
+        scrubber.update_density(&[0.0, 100.0]);
+        scrubber.zoom(0.0, 0.1);
+
+        scrubber.update_density(&[0.0, 100.0, 150.0]);
+        let view = scrubber.visible_range().unwrap();
+        assert_eq!((view.min, view.max), (0.0, 10.0));
+
+        // A different capture starts unzoomed
+        scrubber.update_density(&[500.0, 600.0]);
+        assert!(!scrubber.is_zoomed());
+    }
+
+    #[test]
+    fn test_zoom_does_not_change_selection() {
+        let mut scrubber = TimeScrubber::new();
+        scrubber.update_density(&[0.0, 100.0]);
+        scrubber.selected_range = Some(TimeRange::new(40.0, 45.0));
+
+        scrubber.zoom(42.0, 0.1);
+        let selected = scrubber.get_selected_range().unwrap();
+        assert_eq!((selected.min, selected.max), (40.0, 45.0));
+    }
+
     #[test]
     fn test_time_range_contains() {
         let range = TimeRange::new(10.0, 20.0);
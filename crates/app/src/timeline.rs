@@ -0,0 +1,227 @@
+//! Traffic timeline graph showing send vs recv message rates over time
+//!
+//! Messages are binned by timestamp (1-second buckets by default) and drawn as two
+//! lines. Clicking the graph reports the time under the pointer so the caller can
+//! scroll the list to the closest message.
+
+use common::messages::ParsedMessage;
+use eframe::egui;
+
+/// Default bucket width in seconds
+const DEFAULT_BIN_WIDTH: f64 = 1.0;
+
+/// Upper bound on the number of buckets; long captures get wider buckets instead
+const MAX_BINS: usize = 2000;
+
+/// Binned send/recv message counts for a capture
+#[derive(Default)]
+pub struct TrafficTimeline {
+    /// Timestamp of the start of the first bucket
+    start: f64,
+    /// Bucket width in seconds
+    bin_width: f64,
+    /// (send, recv) counts per bucket
+    bins: Vec<(u32, u32)>,
+}
+
+impl TrafficTimeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rebuild the buckets from the given messages
+    pub fn update(&mut self, messages: &[ParsedMessage]) {
+        self.bins.clear();
+        if messages.is_empty() {
+            return;
+        }
+
+        let min_time = messages
+            .iter()
+            .map(|m| m.timestamp)
+            .fold(f64::INFINITY, f64::min);
+        let max_time = messages
+            .iter()
+            .map(|m| m.timestamp)
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        let span = max_time - min_time;
+        let bin_width = DEFAULT_BIN_WIDTH.max(span / MAX_BINS as f64);
+        let num_bins = ((span / bin_width).floor() as usize + 1).min(MAX_BINS);
+
+        self.start = min_time;
+        self.bin_width = bin_width;
+        self.bins = vec![(0, 0); num_bins];
+
+        for message in messages {
+            let index = ((message.timestamp - min_time) / bin_width).floor() as usize;
+            let bin = &mut self.bins[index.min(num_bins - 1)];
+            if message.direction == "Send" {
+                bin.0 += 1;
+            } else {
+                bin.1 += 1;
+            }
+        }
+    }
+
+    /// Check if we have data
+    pub fn has_data(&self) -> bool {
+        !self.bins.is_empty()
+    }
+
+    /// Render the graph, returning the time that was clicked (if any)
+    pub fn show(&self, ui: &mut egui::Ui) -> Option<f64> {
+        if !self.has_data() {
+            ui.label("No data to display");
+            return None;
+        }
+
+        let dark_mode = ui.visuals().dark_mode;
+        let send_color = if dark_mode {
+            egui::Color32::from_rgb(100, 200, 255)
+        } else {
+            egui::Color32::from_rgb(30, 110, 200)
+        };
+        let recv_color = if dark_mode {
+            egui::Color32::from_rgb(100, 255, 150)
+        } else {
+            egui::Color32::from_rgb(20, 150, 60)
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Traffic");
+            ui.label(egui::RichText::new("— Send").color(send_color));
+            ui.label(egui::RichText::new("— Recv").color(recv_color));
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.label(format!("{:.0}s buckets", self.bin_width));
+            });
+        });
+
+        let height = 80.0;
+        let (response, painter) = ui.allocate_painter(
+            egui::vec2(ui.available_width(), height),
+            egui::Sense::click(),
+        );
+        let rect = response.rect;
+
+        // Background
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+        let max_count = self
+            .bins
+            .iter()
+            .map(|(send, recv)| (*send).max(*recv))
+            .max()
+            .unwrap_or(0)
+            .max(1) as f32;
+
+        let x_for = |index: usize| {
+            if self.bins.len() == 1 {
+                rect.center().x
+            } else {
+                rect.min.x + index as f32 / (self.bins.len() - 1) as f32 * rect.width()
+            }
+        };
+        let y_for = |count: u32| rect.max.y - count as f32 / max_count * (height - 4.0);
+
+        let send_points: Vec<egui::Pos2> = self
+            .bins
+            .iter()
+            .enumerate()
+            .map(|(i, (send, _))| egui::pos2(x_for(i), y_for(*send)))
+            .collect();
+        let recv_points: Vec<egui::Pos2> = self
+            .bins
+            .iter()
+            .enumerate()
+            .map(|(i, (_, recv))| egui::pos2(x_for(i), y_for(*recv)))
+            .collect();
+
+        painter.add(egui::Shape::line(
+            send_points,
+            egui::Stroke::new(1.5, send_color),
+        ));
+        painter.add(egui::Shape::line(
+            recv_points,
+            egui::Stroke::new(1.5, recv_color),
+        ));
+
+        let pointer_bin = response.hover_pos().map(|pos| {
+            let ratio = ((pos.x - rect.min.x) / rect.width()).clamp(0.0, 1.0);
+            (ratio * (self.bins.len() - 1) as f32).round() as usize
+        });
+
+        let mut clicked_time = None;
+        if let Some(index) = pointer_bin {
+            let hover_color = ui.visuals().text_color();
+            painter.vline(
+                x_for(index),
+                rect.y_range(),
+                egui::Stroke::new(1.0, hover_color.gamma_multiply(0.5)),
+            );
+
+            if response.clicked() {
+                clicked_time = Some(self.bin_center(index));
+            }
+
+            let (send, recv) = self.bins[index];
+            response.on_hover_text(format!(
+                "Time: {:.0}s\nSend: {send}\nRecv: {recv}",
+                self.bin_center(index) - self.start
+            ));
+        }
+
+        clicked_time
+    }
+
+    /// Timestamp at the center of a bucket
+    fn bin_center(&self, index: usize) -> f64 {
+        self.start + (index as f64 + 0.5) * self.bin_width
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(timestamp: f64, direction: &str) -> ParsedMessage {
+        ParsedMessage {
+            direction: direction.to_string(),
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_empty_timeline_has_no_data() {
+        let mut timeline = TrafficTimeline::new();
+        timeline.update(&[]);
+        assert!(!timeline.has_data());
+    }
+
+    #[test]
+    fn test_bins_send_and_recv_per_second() {
+        let mut timeline = TrafficTimeline::new();
+        timeline.update(&[
+            message(100.0, "Send"),
+            message(100.4, "Recv"),
+            message(100.9, "Recv"),
+            message(102.5, "Send"),
+        ]);
+
+        assert_eq!(timeline.bin_width, 1.0);
+        assert_eq!(timeline.bins, vec![(1, 2), (0, 0), (1, 0)]);
+        assert_eq!(timeline.bin_center(2), 102.5);
+    }
+
+    #[test]
+    fn test_long_captures_widen_buckets() {
+        let mut timeline = TrafficTimeline::new();
+        timeline.update(&[message(0.0, "Send"), message(10_000.0, "Recv")]);
+
+        assert!(timeline.bins.len() <= MAX_BINS);
+        assert_eq!(timeline.bin_width, 5.0);
+        let total: u32 = timeline.bins.iter().map(|(s, r)| s + r).sum();
+        assert_eq!(total, 2);
+    }
+}
@@ -0,0 +1,104 @@
+//! Damage dealt and taken, per attacker and target
+
+use crate::PcapViewerApp;
+use common::combat::DamageSummary;
+use eframe::egui;
+
+/// Damage types of a row, largest first, e.g. "Fire 120, Slash 30"
+fn damage_types_label(summary: &DamageSummary) -> String {
+    let mut types: Vec<(&String, &u64)> = summary.damage_types.iter().collect();
+    types.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    types
+        .iter()
+        .map(|(name, damage)| format!("{name} {damage}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Window summarizing combat within the messages scrubber's selected range
+///
+/// The report is cached until the capture or the selected range changes.
+pub fn show_combat_summary(app: &mut PcapViewerApp, ctx: &egui::Context) {
+    let window = app
+        .messages_scrubber
+        .get_selected_range()
+        .map(|range| (range.min, range.max));
+    if app
+        .combat_report
+        .as_ref()
+        .is_none_or(|(cached, _)| *cached != window)
+    {
+        let report =
+            common::combat::combat_summary(&app.messages, window.map(|(min, max)| min..=max));
+        app.combat_report = Some((window, report));
+    }
+    let Some((_, report)) = &app.combat_report else {
+        return;
+    };
+
+    let mut open = app.show_combat_summary;
+    egui::Window::new("Combat Summary")
+        .open(&mut open)
+        .default_size([560.0, 320.0])
+        .show(ctx, |ui| {
+            if report.is_empty() {
+                ui.label("No combat notifications in the selected time range");
+                return;
+            }
+
+            egui_extras::TableBuilder::new(ui)
+                .striped(true)
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                .column(egui_extras::Column::initial(120.0).resizable(true))
+                .column(egui_extras::Column::initial(120.0).resizable(true))
+                .column(egui_extras::Column::exact(60.0))
+                .column(egui_extras::Column::exact(40.0))
+                .column(egui_extras::Column::exact(40.0))
+                .column(egui_extras::Column::remainder())
+                .header(20.0, |mut header| {
+                    for title in ["Attacker", "Target", "Damage", "Hits", "Crits", "Types"] {
+                        header.col(|ui| {
+                            ui.strong(title);
+                        });
+                    }
+                })
+                .body(|body| {
+                    body.rows(18.0, report.len(), |mut row| {
+                        let summary = &report[row.index()];
+                        row.col(|ui| {
+                            ui.label(&summary.attacker);
+                        });
+                        row.col(|ui| {
+                            ui.label(&summary.target);
+                        });
+                        row.col(|ui| {
+                            ui.monospace(summary.total_damage.to_string());
+                        });
+                        row.col(|ui| {
+                            ui.monospace(summary.hits.to_string());
+                        });
+                        row.col(|ui| {
+                            ui.monospace(summary.crits.to_string());
+                        });
+                        row.col(|ui| {
+                            ui.label(damage_types_label(summary));
+                        });
+                    });
+                });
+        });
+    app.show_combat_summary = open;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_damage_types_label_is_largest_first() {
+        let summary = DamageSummary {
+            damage_types: [("Slash".to_string(), 30), ("Fire".to_string(), 120)].into(),
+            ..Default::default()
+        };
+        assert_eq!(damage_types_label(&summary), "Fire 120, Slash 30");
+    }
+}
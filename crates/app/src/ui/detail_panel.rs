@@ -2,8 +2,8 @@
 
 use crate::ui::hyper_tree::AcJsonTree;
 use crate::{PcapViewerApp, Tab, ViewMode};
-use common::messages::ParsedMessage;
 use eframe::egui;
+use std::borrow::Cow;
 
 /// Show detail content in the detail panel
 pub fn show_detail_content(app: &mut PcapViewerApp, ui: &mut egui::Ui) {
@@ -13,6 +13,12 @@ pub fn show_detail_content(app: &mut PcapViewerApp, ui: &mut egui::Ui) {
         return;
     }
 
+    if app.current_tab == Tab::Messages {
+        show_message_actions(app, ui);
+        show_message_origin(app, ui);
+        show_message_diff(app, ui);
+    }
+
     // View mode toggle buttons
     ui.horizontal(|ui| {
         ui.selectable_value(&mut app.view_mode, ViewMode::Tree, "Tree");
@@ -21,70 +27,253 @@ pub fn show_detail_content(app: &mut PcapViewerApp, ui: &mut egui::Ui) {
     });
     ui.separator();
 
+    let mut json_search = std::mem::take(&mut app.json_search);
+    let mut tree_open_state = std::mem::take(&mut app.tree_open_state);
+    let mut scan_offset = app.scan_offset;
+    let mut carver = std::mem::take(&mut app.carver);
+    let Some(item) = selected_item(app) else {
+        app.json_search = json_search;
+        app.tree_open_state = tree_open_state;
+        app.carver = carver;
+        ui.label(if app.current_tab == Tab::Packets {
+            "No packet selected"
+        } else {
+            "No message selected"
+        });
+        return;
+    };
+
+    // Messages that failed to decode keep their payload as a raw_data hex string
+    if let Some(raw_data) = item
+        .data
+        .get("raw_data")
+        .and_then(|v| v.as_str())
+        .and_then(|hex_str| hex::decode(hex_str).ok())
+    {
+        show_field_carver(ui, &raw_data, &mut carver);
+    }
+
     // Track filter clicks to update after the match block
     let mut filter_value: Option<String> = None;
 
     match app.view_mode {
-        ViewMode::JSON => {
-            if let Some(idx) = app.selected_message {
-                if idx < app.messages.len() {
-                    show_pretty_json(ui, &app.messages[idx].data);
-                } else {
-                    ui.label("No message selected");
-                }
-            } else {
-                ui.label("No message selected");
-            }
-        }
+        ViewMode::JSON => show_pretty_json(ui, &item.data, &mut json_search),
         ViewMode::Tree => {
-            if let Some(idx) = app.selected_message {
-                if idx < app.messages.len() {
-                    let tree_id = format!("message_tree_{idx}");
-                    let response = AcJsonTree::new(&tree_id).show(ui, &app.messages[idx].data);
-                    if let Some(value) = response.filter_clicked {
-                        filter_value = Some(value);
-                    }
-                } else {
-                    ui.label("No message selected");
-                }
-            } else {
-                ui.label("No message selected");
+            if ui
+                .small_button("Copy as text")
+                .on_hover_text("Copy the whole tree as indented text")
+                .clicked()
+            {
+                ui.ctx()
+                    .copy_text(common::tree::render_text_tree(&item.data));
+            }
+            // Collapse state is shared by all items of the same kind
+            let open_state = tree_open_state.entry(item.kind.to_string()).or_default();
+            let response = AcJsonTree::new(&item.tree_id, open_state).show(ui, &item.data);
+            if let Some(value) = response.filter_clicked {
+                filter_value = Some(value);
             }
         }
         ViewMode::Binary => {
-            if let Some(idx) = app.selected_message {
-                if idx < app.messages.len() {
-                    show_hex_dump(ui, &app.messages[idx]);
-                } else {
-                    ui.label("No message selected");
-                }
+            if item.raw_bytes.is_empty() {
+                ui.label("No binary data available for this item");
             } else {
-                ui.label("No message selected");
+                show_field_scan(ui, item.raw_bytes, &mut scan_offset);
+                render_hex_dump(ui, item.raw_bytes);
             }
         }
     }
 
+    app.json_search = json_search;
+    app.tree_open_state = tree_open_state;
+    app.scan_offset = scan_offset;
+    app.carver = carver;
+
     // Handle filter click - update search query
     if let Some(value) = filter_value {
         app.search_query = value;
     }
 }
 
-/// Extract binary data from a message
-fn extract_message_binary(message: &ParsedMessage) -> Option<Vec<u8>> {
-    // Use the raw_bytes field which contains the original message bytes
-    if !message.raw_bytes.is_empty() {
-        return Some(message.raw_bytes.clone());
+/// The message or packet selected on the current tab, ready for display
+struct SelectedItem<'a> {
+    /// Message type, or "Packet" for packets; tree layout is remembered per kind
+    kind: &'a str,
+    tree_id: String,
+    data: Cow<'a, serde_json::Value>,
+    raw_bytes: &'a [u8],
+}
+
+fn selected_item(app: &PcapViewerApp) -> Option<SelectedItem<'_>> {
+    if app.current_tab == Tab::Packets {
+        let idx = app.selected_packet?;
+        let packet = app.packets.get(idx)?;
+        Some(SelectedItem {
+            kind: "Packet",
+            tree_id: format!("packet_tree_{idx}"),
+            data: Cow::Owned(serde_json::to_value(packet).ok()?),
+            raw_bytes: &packet.raw_payload,
+        })
+    } else {
+        let idx = app.selected_message?;
+        let message = app.messages.get(idx)?;
+        Some(SelectedItem {
+            kind: &message.message_type,
+            tree_id: format!("message_tree_{idx}"),
+            data: Cow::Borrowed(&message.data),
+            raw_bytes: &message.raw_bytes,
+        })
+    }
+}
+
+/// Show quick actions for the selected message
+fn show_message_actions(app: &mut PcapViewerApp, ui: &mut egui::Ui) {
+    let Some(selected) = app.selected_message.filter(|&idx| idx < app.messages.len()) else {
+        return;
+    };
+
+    if ui
+        .button("Mark same type")
+        .on_hover_text(format!(
+            "Mark every {} message",
+            app.messages[selected].message_type
+        ))
+        .clicked()
+    {
+        app.mark_same_type(selected);
+    }
+    ui.separator();
+}
+
+/// Show the fragment sequence of the selected message with links to the packets that carried it
+fn show_message_origin(app: &mut PcapViewerApp, ui: &mut egui::Ui) {
+    let Some(message) = app.selected_message.and_then(|idx| app.messages.get(idx)) else {
+        return;
+    };
+    if message.packet_ids.is_empty() {
+        return;
+    }
+
+    let mut clicked_packet: Option<usize> = None;
+    ui.horizontal_wrapped(|ui| {
+        ui.label(format!("Fragment sequence: {}", message.fragment_sequence));
+        ui.separator();
+        ui.label(if message.packet_ids.len() == 1 {
+            "Packet:"
+        } else {
+            "Packets:"
+        });
+        for &packet_id in &message.packet_ids {
+            if ui.link(format!("#{packet_id}")).clicked() {
+                clicked_packet = Some(packet_id);
+            }
+        }
+    });
+    ui.separator();
+
+    if let Some(packet_id) = clicked_packet {
+        // Switch to Packets tab and select the packet that carried this message
+        app.current_tab = Tab::Packets;
+        app.selected_packet = app.packets.iter().position(|p| p.id == packet_id);
+    }
+}
+
+/// Show pin controls and, when another message is selected, its diff against the pinned one
+fn show_message_diff(app: &mut PcapViewerApp, ui: &mut egui::Ui) {
+    let Some(selected) = app.selected_message.filter(|&idx| idx < app.messages.len()) else {
+        return;
+    };
+    let pinned = app.pinned_message.filter(|&idx| idx < app.messages.len());
+
+    ui.horizontal(|ui| match pinned {
+        Some(pinned) if pinned == selected => {
+            if ui.button("Unpin").clicked() {
+                app.pinned_message = None;
+            }
+            ui.label("Select another message to compare");
+        }
+        Some(pinned) => {
+            ui.label(format!("Pinned #{}", app.messages[pinned].id));
+            if ui.button("Pin this instead").clicked() {
+                app.pinned_message = Some(selected);
+            }
+            if ui.button("Unpin").clicked() {
+                app.pinned_message = None;
+            }
+        }
+        None => {
+            if ui
+                .button("Pin for diff")
+                .on_hover_text("Pin this message, then select another to compare")
+                .clicked()
+            {
+                app.pinned_message = Some(selected);
+            }
+        }
+    });
+
+    if let Some(pinned) = pinned.filter(|&idx| idx != selected) {
+        let old = &app.messages[pinned];
+        let new = &app.messages[selected];
+        let entries = crate::diff::diff_values(&old.data, &new.data);
+
+        egui::CollapsingHeader::new(format!(
+            "Diff #{} → #{} ({} changes)",
+            old.id,
+            new.id,
+            entries.len()
+        ))
+        .id_salt("message_diff")
+        .default_open(true)
+        .show(ui, |ui| render_diff(ui, &entries));
     }
-    None
+    ui.separator();
 }
 
-/// Display hex dump for a message
-fn show_hex_dump(ui: &mut egui::Ui, message: &ParsedMessage) {
-    if let Some(data) = extract_message_binary(message) {
-        render_hex_dump(ui, &data);
+/// Render diff entries with additions green, removals red, and changes yellow
+fn render_diff(ui: &mut egui::Ui, entries: &[crate::diff::DiffEntry]) {
+    use crate::diff::DiffKind;
+    use egui::{Color32, RichText};
+
+    if entries.is_empty() {
+        ui.label("Messages are identical");
+        return;
+    }
+
+    let dark_mode = ui.visuals().dark_mode;
+    let (added, removed, changed) = if dark_mode {
+        (
+            Color32::from_rgb(128, 255, 128),
+            Color32::from_rgb(255, 128, 128),
+            Color32::from_rgb(255, 220, 100),
+        )
     } else {
-        ui.label("No binary data available for this message");
+        (
+            Color32::from_rgb(0, 128, 0),
+            Color32::from_rgb(180, 0, 0),
+            Color32::from_rgb(150, 110, 0),
+        )
+    };
+
+    let show = |value: &Option<serde_json::Value>| {
+        value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+    };
+
+    for entry in entries {
+        let (text, color) = match entry.kind {
+            DiffKind::Added => (format!("+ {}: {}", entry.path, show(&entry.new)), added),
+            DiffKind::Removed => (format!("- {}: {}", entry.path, show(&entry.old)), removed),
+            DiffKind::Changed => (
+                format!(
+                    "~ {}: {} → {}",
+                    entry.path,
+                    show(&entry.old),
+                    show(&entry.new)
+                ),
+                changed,
+            ),
+        };
+        ui.label(RichText::new(text).monospace().color(color));
     }
 }
 
@@ -216,8 +405,425 @@ fn render_hex_dump(ui: &mut egui::Ui, data: &[u8]) {
         });
 }
 
+/// Read a packed dword at `offset`, returning the value and bytes consumed
+///
+/// Values below 0x8000 are one u16. Larger ones are two u16s, high word first
+/// with its top bit set as the marker.
+fn read_packed_dword(data: &[u8], offset: usize) -> Option<(u32, usize)> {
+    let word = |at: usize| {
+        data.get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+    let high = word(offset)?;
+    if high & 0x8000 == 0 {
+        return Some((high as u32, 2));
+    }
+    let low = word(offset + 2)?;
+    Some((((high as u32 & 0x7FFF) << 16) | low as u32, 4))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// The bytes at an offset read as a packed dword and as a plain u32
+///
+/// Also reads the u32 that would follow under each framing. The framing the
+/// message really uses usually makes that next field look sensible, such as an
+/// object id or a small count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DwordFraming {
+    packed: Option<(u32, usize)>,
+    plain: Option<u32>,
+    after_packed: Option<u32>,
+    after_plain: Option<u32>,
+}
+
+fn dword_framing(data: &[u8], offset: usize) -> DwordFraming {
+    let packed = read_packed_dword(data, offset);
+    DwordFraming {
+        packed,
+        plain: read_u32(data, offset),
+        after_packed: packed.and_then(|(_, len)| read_u32(data, offset + len)),
+        after_plain: read_u32(data, offset + 4),
+    }
+}
+
+/// Tentative interpretations of the bytes starting at `offset`
+///
+/// Each entry is a field type and the value it would decode to; types that
+/// would run past the end of the data are left out.
+fn field_guesses(data: &[u8], offset: usize) -> Vec<(&'static str, String)> {
+    let Some(rest) = data.get(offset..).filter(|rest| !rest.is_empty()) else {
+        return Vec::new();
+    };
+    let bytes = |n: usize| rest.get(..n);
+
+    let mut guesses = vec![("u8", rest[0].to_string())];
+    if let Some(b) = bytes(2) {
+        let value = u16::from_le_bytes([b[0], b[1]]);
+        guesses.push(("u16", format!("{value} (0x{value:04X})")));
+    }
+    if let Some(b) = bytes(4) {
+        let raw = [b[0], b[1], b[2], b[3]];
+        let value = u32::from_le_bytes(raw);
+        guesses.push(("u32", format!("{value} (0x{value:08X})")));
+        guesses.push(("i32", i32::from_le_bytes(raw).to_string()));
+        guesses.push(("f32", f32::from_le_bytes(raw).to_string()));
+    }
+    if let Some(b) = bytes(8) {
+        let raw: [u8; 8] = b.try_into().unwrap_or_default();
+        guesses.push(("u64", format!("0x{:016X}", u64::from_le_bytes(raw))));
+        guesses.push(("f64", f64::from_le_bytes(raw).to_string()));
+    }
+    match read_packed_dword(data, offset) {
+        Some((value, 2)) => guesses.push(("packed dword", format!("{value} (2 bytes)"))),
+        Some((value, len)) => {
+            guesses.push(("packed dword", format!("0x{value:08X} ({len} bytes)")))
+        }
+        None => {}
+    }
+    if let Some(b) = bytes(2) {
+        // String16L: u16 length followed by that many characters
+        let len = u16::from_le_bytes([b[0], b[1]]) as usize;
+        if let Some(text) = rest.get(2..2 + len)
+            && len > 0
+            && text.iter().all(|c| c.is_ascii_graphic() || *c == b' ')
+        {
+            guesses.push(("string16l", format!("{:?}", String::from_utf8_lossy(text))));
+        }
+    }
+
+    let ascii_len = rest
+        .iter()
+        .take_while(|c| c.is_ascii_graphic() || **c == b' ')
+        .count();
+    if ascii_len >= 4 {
+        guesses.push((
+            "ascii",
+            format!("{:?}", String::from_utf8_lossy(&rest[..ascii_len])),
+        ));
+    }
+
+    guesses
+}
+
+/// Show the field scanner: possible decodings of the bytes at a chosen offset
+fn show_field_scan(ui: &mut egui::Ui, data: &[u8], offset: &mut usize) {
+    egui::CollapsingHeader::new("Scan")
+        .id_salt("field_scan")
+        .show(ui, |ui| {
+            let last = data.len().saturating_sub(1);
+            *offset = (*offset).min(last);
+
+            ui.horizontal(|ui| {
+                ui.label("Offset:");
+                ui.add(
+                    egui::DragValue::new(offset)
+                        .range(0..=last)
+                        .hexadecimal(4, false, true),
+                );
+                for step in [1, 2, 4, 8] {
+                    if ui.small_button(format!("+{step}")).clicked() {
+                        *offset = (*offset + step).min(last);
+                    }
+                }
+            });
+
+            egui::Grid::new("field_scan_grid")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (field_type, value) in field_guesses(data, *offset) {
+                        ui.label(egui::RichText::new(field_type).weak());
+                        ui.label(egui::RichText::new(value).monospace());
+                        ui.end_row();
+                    }
+                });
+
+            show_dword_framing(ui, dword_framing(data, *offset));
+        });
+    ui.separator();
+}
+
+/// Packed dword and plain u32 readings side by side
+fn show_dword_framing(ui: &mut egui::Ui, framing: DwordFraming) {
+    if framing.packed.is_none() && framing.plain.is_none() {
+        return;
+    }
+    let hex = |value: Option<u32>| value.map_or("-".to_string(), |v| format!("0x{v:08X}"));
+
+    ui.add_space(4.0);
+    ui.label(egui::RichText::new("Packed dword vs u32").strong());
+    egui::Grid::new("dword_framing_grid")
+        .num_columns(3)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("");
+            ui.label(egui::RichText::new("packed").weak());
+            ui.label(egui::RichText::new("u32").weak());
+            ui.end_row();
+
+            ui.label(egui::RichText::new("value").weak());
+            ui.monospace(hex(framing.packed.map(|(value, _)| value)));
+            ui.monospace(hex(framing.plain));
+            ui.end_row();
+
+            ui.label(egui::RichText::new("length").weak());
+            ui.monospace(
+                framing
+                    .packed
+                    .map_or("-".to_string(), |(_, len)| len.to_string()),
+            );
+            ui.monospace(if framing.plain.is_some() { "4" } else { "-" });
+            ui.end_row();
+
+            ui.label(egui::RichText::new("next u32").weak());
+            ui.monospace(hex(framing.after_packed));
+            ui.monospace(hex(framing.after_plain));
+            ui.end_row();
+        });
+}
+
+/// Field types that can be carved out of raw message data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CarveType {
+    U8,
+    U16,
+    U32,
+    F32,
+    /// u16 length followed by that many characters, padded to 4 bytes
+    String16L,
+}
+
+impl CarveType {
+    pub const ALL: [CarveType; 5] = [
+        CarveType::U8,
+        CarveType::U16,
+        CarveType::U32,
+        CarveType::F32,
+        CarveType::String16L,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CarveType::U8 => "u8",
+            CarveType::U16 => "u16",
+            CarveType::U32 => "u32",
+            CarveType::F32 => "f32",
+            CarveType::String16L => "String",
+        }
+    }
+
+    /// Decode a value at the start of `data`, returning it and the bytes consumed
+    fn read(self, data: &[u8]) -> Option<(String, usize)> {
+        let bytes = |n: usize| data.get(..n);
+        match self {
+            CarveType::U8 => Some((data.first()?.to_string(), 1)),
+            CarveType::U16 => {
+                let b = bytes(2)?;
+                let value = u16::from_le_bytes([b[0], b[1]]);
+                Some((format!("{value} (0x{value:04X})"), 2))
+            }
+            CarveType::U32 => {
+                let b = bytes(4)?;
+                let value = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+                Some((format!("{value} (0x{value:08X})"), 4))
+            }
+            CarveType::F32 => {
+                let b = bytes(4)?;
+                Some((f32::from_le_bytes([b[0], b[1], b[2], b[3]]).to_string(), 4))
+            }
+            CarveType::String16L => {
+                let b = bytes(2)?;
+                let len = u16::from_le_bytes([b[0], b[1]]) as usize;
+                let text = data.get(2..2 + len)?;
+                let consumed = (2 + len).next_multiple_of(4).min(data.len());
+                Some((format!("{:?}", String::from_utf8_lossy(text)), consumed))
+            }
+        }
+    }
+}
+
+/// A field decoded by the carver
+#[derive(Debug, Clone, PartialEq)]
+struct CarvedField {
+    offset: usize,
+    field_type: CarveType,
+    /// None when the field runs past the end of the data
+    value: Option<String>,
+}
+
+/// Manual field layout for data that failed to decode
+#[derive(Debug, Clone, Default)]
+pub struct FieldCarver {
+    pub offset: usize,
+    pub fields: Vec<CarveType>,
+}
+
+impl FieldCarver {
+    /// Decode the chosen fields in sequence from the start offset
+    fn carve(&self, data: &[u8]) -> Vec<CarvedField> {
+        let mut offset = self.offset;
+        let mut carved = Vec::with_capacity(self.fields.len());
+        for &field_type in &self.fields {
+            let read = data.get(offset..).and_then(|rest| field_type.read(rest));
+            carved.push(CarvedField {
+                offset,
+                field_type,
+                value: read.as_ref().map(|(value, _)| value.clone()),
+            });
+            match read {
+                Some((_, consumed)) => offset += consumed,
+                // Later fields can't be placed once one runs off the end
+                None => offset = data.len(),
+            }
+        }
+        carved
+    }
+}
+
+/// Rust-like struct listing the carved fields with their decoded values
+fn pseudo_struct(carved: &[CarvedField]) -> String {
+    let mut text = String::from("struct Unknown {\n");
+    for field in carved {
+        text.push_str(&format!(
+            "    field_{:02x}: {}, // {}\n",
+            field.offset,
+            field.field_type.name(),
+            field.value.as_deref().unwrap_or("<out of range>")
+        ));
+    }
+    text.push('}');
+    text
+}
+
+/// Show the field carver for data that failed to decode
+fn show_field_carver(ui: &mut egui::Ui, data: &[u8], carver: &mut FieldCarver) {
+    egui::CollapsingHeader::new("Interpret as")
+        .id_salt("field_carver")
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Start offset:");
+                ui.add(
+                    egui::DragValue::new(&mut carver.offset)
+                        .range(0..=data.len())
+                        .hexadecimal(4, false, true),
+                );
+            });
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Add:");
+                for field_type in CarveType::ALL {
+                    if ui.small_button(field_type.name()).clicked() {
+                        carver.fields.push(field_type);
+                    }
+                }
+            });
+
+            let carved = carver.carve(data);
+            let mut remove = None;
+            egui::Grid::new("field_carver_grid")
+                .num_columns(4)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (i, field) in carved.iter().enumerate() {
+                        ui.label(egui::RichText::new(format!("{:04X}", field.offset)).monospace());
+                        ui.label(egui::RichText::new(field.field_type.name()).weak());
+                        match &field.value {
+                            Some(value) => ui.label(egui::RichText::new(value).monospace()),
+                            None => ui.colored_label(ui.visuals().warn_fg_color, "out of range"),
+                        };
+                        if ui.small_button("x").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+            if let Some(i) = remove {
+                carver.fields.remove(i);
+            }
+
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!carved.is_empty(), egui::Button::new("Copy struct"))
+                    .clicked()
+                {
+                    ui.ctx().copy_text(pseudo_struct(&carved));
+                }
+                if ui
+                    .add_enabled(!carver.fields.is_empty(), egui::Button::new("Clear"))
+                    .clicked()
+                {
+                    carver.fields.clear();
+                }
+            });
+        });
+    ui.separator();
+}
+
+/// In-detail search state for the JSON view, independent of the list filter
+#[derive(Default)]
+pub struct JsonSearch {
+    pub query: String,
+    /// Index of the current match among all matches
+    pub current: usize,
+    /// Scroll the current match into view on the next frame
+    pub scroll_pending: bool,
+}
+
+/// Byte offsets of ASCII case-insensitive, non-overlapping matches of `query` in `text`
+fn find_matches(text: &str, query: &str) -> Vec<usize> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    // ASCII lowercasing keeps byte offsets aligned with the original text
+    let text = text.to_ascii_lowercase();
+    let query = query.to_ascii_lowercase();
+    text.match_indices(&query).map(|(start, _)| start).collect()
+}
+
+/// Show the JSON search box with match count and previous/next buttons
+fn show_json_search_bar(ui: &mut egui::Ui, search: &mut JsonSearch, match_count: usize) {
+    ui.horizontal(|ui| {
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut search.query)
+                .hint_text("Find in JSON...")
+                .desired_width(160.0),
+        );
+        if response.changed() {
+            search.current = 0;
+            search.scroll_pending = true;
+        }
+        let enter = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+        if search.query.is_empty() {
+            return;
+        }
+        if match_count == 0 {
+            ui.label("No matches");
+            return;
+        }
+
+        search.current = search.current.min(match_count - 1);
+        ui.label(format!("{}/{}", search.current + 1, match_count));
+
+        if ui.button("◀").on_hover_text("Previous match").clicked() {
+            search.current = (search.current + match_count - 1) % match_count;
+            search.scroll_pending = true;
+        }
+        if ui.button("▶").on_hover_text("Next match").clicked() || enter {
+            search.current = (search.current + 1) % match_count;
+            search.scroll_pending = true;
+            if enter {
+                response.request_focus();
+            }
+        }
+    });
+}
+
 /// Show pretty-printed JSON
-fn show_pretty_json(ui: &mut egui::Ui, value: &serde_json::Value) {
+fn show_pretty_json(ui: &mut egui::Ui, value: &serde_json::Value, search: &mut JsonSearch) {
     use egui::text::LayoutJob;
     use egui::{FontId, TextFormat};
 
@@ -229,12 +835,31 @@ fn show_pretty_json(ui: &mut egui::Ui, value: &serde_json::Value) {
         }
     };
 
+    let matches = find_matches(&json_str, &search.query);
+    show_json_search_bar(ui, search, matches.len());
+    let query_len = search.query.len();
+
     let mut job = LayoutJob::default();
     let font_id = FontId::monospace(12.0);
     let text_color = ui.visuals().text_color();
+    let (match_bg, current_bg) = if ui.visuals().dark_mode {
+        (
+            egui::Color32::from_rgb(90, 80, 20),
+            egui::Color32::from_rgb(170, 100, 0),
+        )
+    } else {
+        (
+            egui::Color32::from_rgb(255, 240, 150),
+            egui::Color32::from_rgb(255, 170, 60),
+        )
+    };
+
+    let mut next_match = 0;
+    let mut current_line = None;
+    let mut line_start = 0;
 
     // Simple syntax highlighting
-    for line in json_str.lines() {
+    for (line_index, line) in json_str.lines().enumerate() {
         let trimmed = line.trim_start();
 
         // Determine color based on line content
@@ -267,8 +892,42 @@ fn show_pretty_json(ui: &mut egui::Ui, value: &serde_json::Value) {
             text_color
         };
 
+        // Split the line into plain and highlighted segments
+        let line_end = line_start + line.len();
+        let mut pos = line_start;
+        while next_match < matches.len() && matches[next_match] + query_len <= line_end {
+            let start = matches[next_match];
+            let background = if next_match == search.current {
+                current_line = Some(line_index);
+                current_bg
+            } else {
+                match_bg
+            };
+            job.append(
+                &json_str[pos..start],
+                0.0,
+                TextFormat {
+                    font_id: font_id.clone(),
+                    color,
+                    ..Default::default()
+                },
+            );
+            job.append(
+                &json_str[start..start + query_len],
+                0.0,
+                TextFormat {
+                    font_id: font_id.clone(),
+                    color,
+                    background,
+                    ..Default::default()
+                },
+            );
+            pos = start + query_len;
+            next_match += 1;
+        }
+
         job.append(
-            line,
+            &json_str[pos..line_end],
             0.0,
             TextFormat {
                 font_id: font_id.clone(),
@@ -285,13 +944,119 @@ fn show_pretty_json(ui: &mut egui::Ui, value: &serde_json::Value) {
                 ..Default::default()
             },
         );
+        line_start = line_end + 1;
     }
 
+    let row_height = ui.fonts(|f| f.row_height(&font_id));
+    let scroll_pending = std::mem::take(&mut search.scroll_pending);
+
     egui::ScrollArea::both()
         .auto_shrink([false, false])
         .show(ui, |ui| {
             ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), |ui| {
-                ui.add(egui::Label::new(job).extend());
+                let response = ui.add(egui::Label::new(job).extend());
+
+                // Bring the current match into view after a search or jump
+                if scroll_pending && let Some(line) = current_line {
+                    let top = response.rect.top() + line as f32 * row_height;
+                    let rect = egui::Rect::from_min_size(
+                        egui::pos2(response.rect.left(), top),
+                        egui::vec2(1.0, row_height),
+                    );
+                    ui.scroll_to_rect(rect, Some(egui::Align::Center));
+                }
             });
         });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_guesses() {
+        let data = [0x05, 0x00, b'h', b'e', b'l', b'l', b'o', 0x00];
+        let guesses = field_guesses(&data, 0);
+        let guess = |name| {
+            guesses
+                .iter()
+                .find(|(field_type, _)| *field_type == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        assert_eq!(guess("u16"), Some("5 (0x0005)"));
+        assert_eq!(guess("packed dword"), Some("5 (2 bytes)"));
+        assert_eq!(guess("string16l"), Some("\"hello\""));
+        assert_eq!(guess("ascii"), None);
+
+        let packed = field_guesses(&[0x01, 0x80, 0x34, 0x12], 0);
+        assert!(packed.contains(&("packed dword", "0x00011234 (4 bytes)".to_string())));
+
+        assert!(field_guesses(&data, data.len()).is_empty());
+        assert_eq!(field_guesses(&data, 7), vec![("u8", "0".to_string())]);
+    }
+
+    #[test]
+    fn test_dword_framing() {
+        // Packed 0x0005 followed by an object id
+        let mut data = vec![0x05, 0x00];
+        data.extend_from_slice(&0x5000_0001u32.to_le_bytes());
+        let framing = dword_framing(&data, 0);
+        assert_eq!(framing.packed, Some((5, 2)));
+        assert_eq!(framing.plain, Some(0x0001_0005));
+        assert_eq!(framing.after_packed, Some(0x5000_0001));
+        assert_eq!(framing.after_plain, None);
+
+        let framing = dword_framing(&[0x01, 0x80, 0x34, 0x12], 0);
+        assert_eq!(framing.packed, Some((0x0001_1234, 4)));
+        assert_eq!(framing.plain, Some(0x1234_8001));
+
+        assert_eq!(read_packed_dword(&[0x01, 0x80, 0x34], 0), None);
+    }
+
+    #[test]
+    fn test_field_carver() {
+        let mut data = 0x5000_0001u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&[0x03, 0x00, b'a', b'b', b'c', 0x00, 0x00, 0x00]);
+        data.extend_from_slice(&1.5f32.to_le_bytes());
+
+        let carver = FieldCarver {
+            offset: 0,
+            fields: vec![
+                CarveType::U32,
+                CarveType::String16L,
+                CarveType::F32,
+                CarveType::U8,
+            ],
+        };
+        let carved = carver.carve(&data);
+        let offsets: Vec<usize> = carved.iter().map(|f| f.offset).collect();
+        assert_eq!(offsets, vec![0, 4, 12, 16]);
+        assert_eq!(carved[1].value.as_deref(), Some("\"abc\""));
+        assert_eq!(carved[2].value.as_deref(), Some("1.5"));
+        assert_eq!(carved[3].value, None);
+
+        assert_eq!(
+            pseudo_struct(&carved[..2]),
+            "struct Unknown {\n    field_00: u32, // 1342177281 (0x50000001)\n    field_04: String, // \"abc\"\n}"
+        );
+    }
+
+    #[test]
+    fn test_find_matches_is_case_insensitive() {
+        assert_eq!(
+            find_matches("ObjectId objectid OBJECTID", "objectid"),
+            vec![0, 9, 18]
+        );
+    }
+
+    #[test]
+    fn test_find_matches_empty_query() {
+        assert!(find_matches("anything", "").is_empty());
+    }
+
+    #[test]
+    fn test_find_matches_non_overlapping() {
+        assert_eq!(find_matches("aaaa", "aa"), vec![0, 2]);
+    }
+}
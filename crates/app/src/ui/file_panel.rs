@@ -1,51 +1,167 @@
 //! File loading and management UI components
 
 use crate::PcapViewerApp;
-use common::PacketParser;
 use eframe::egui;
+use serde::Deserialize;
 
 #[allow(dead_code)]
 static BOT_BASE_URL: &str = env!("BOT_BASE_URL");
 
+/// A capture attached to a Discord message, as listed by the bot
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordAttachment {
+    pub index: usize,
+    pub filename: String,
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+/// Attachment listing returned by the bot when no attachment is selected
+#[derive(Debug, Deserialize)]
+pub struct DiscordAttachmentList {
+    pub attachments: Vec<DiscordAttachment>,
+}
+
+/// Outcome of a Discord attachment request (WASM only)
+#[cfg(target_arch = "wasm32")]
+enum DiscordFetch {
+    Pcap(Vec<u8>),
+    Listing(Vec<DiscordAttachment>),
+}
+
+/// Build the query string that selects a Discord attachment
+///
+/// Numeric selectors are sent as `?index=N`, anything else as `?attachment=<filename>`.
+pub fn attachment_query(selector: Option<&str>) -> String {
+    match selector.map(str::trim) {
+        None | Some("") => String::new(),
+        Some(s) if s.parse::<usize>().is_ok() => format!("?index={s}"),
+        Some(s) => format!("?attachment={}", encode_query_value(s)),
+    }
+}
+
+/// Percent-encode a query parameter value
+fn encode_query_value(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// File name offered when saving a capture whose source name is unknown
+pub const DEFAULT_CAPTURE_NAME: &str = "capture.pcap";
+
+/// File name for a capture loaded from `url`, from the last path segment
+///
+/// Falls back to [`DEFAULT_CAPTURE_NAME`] when the URL has no usable name, and
+/// adds a `.pcap` extension when the name has none of the capture extensions.
+pub fn capture_file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let name = path.rsplit('/').next().unwrap_or_default();
+    if name.is_empty() || name == "." || name == ".." {
+        return DEFAULT_CAPTURE_NAME.to_string();
+    }
+
+    let lower = name.to_ascii_lowercase();
+    if lower.ends_with(".pcap") || lower.ends_with(".pcapng") {
+        name.to_string()
+    } else {
+        format!("{name}.pcap")
+    }
+}
+
 /// Parse PCAP data and update the app state
-pub fn parse_pcap_data(app: &mut PcapViewerApp, data: &[u8]) {
-    app.is_loading = true;
+///
+/// On native the parse runs on a worker thread, so the UI stays responsive and
+/// the spinner turns; its result is posted to `parsed_capture` and picked up by
+/// [`finish_parse`] on a later frame. Each parse is tagged with a new
+/// `parse_generation`, so when captures are opened in quick succession only the
+/// last one is loaded, whichever finishes first. The web build parses in place.
+pub fn parse_pcap_data(app: &mut PcapViewerApp, data: Vec<u8>, ctx: &egui::Context) {
     app.status_message = "Parsing PCAP file...".to_string();
+    start_parse(app, vec![data], ctx);
+}
+
+/// Parse several captures of one session and load them merged in time order
+///
+/// Runs like [`parse_pcap_data`]. The merged capture has no single file, so
+/// it can't be saved as a PCAP afterwards.
+pub fn merge_pcap_data(app: &mut PcapViewerApp, files: Vec<Vec<u8>>, ctx: &egui::Context) {
+    app.status_message = format!("Merging {} PCAP files...", files.len());
+    start_parse(app, files, ctx);
+}
+
+/// Parse `files`, merging them when there is more than one
+fn start_parse(app: &mut PcapViewerApp, mut files: Vec<Vec<u8>>, ctx: &egui::Context) {
+    app.is_loading = true;
+    app.parse_generation += 1;
+    let parser = app.packet_parser();
+    let parse = move || {
+        let result = match files.as_slice() {
+            [data] => crate::ParsedCapture::parse(parser, data),
+            files => crate::ParsedCapture::merge(parser, files),
+        };
+        let data = if files.len() == 1 { files.pop() } else { None };
+        (data, result)
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let generation = app.parse_generation;
+        let parsed_capture = app.parsed_capture.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let (data, result) = parse();
+            if let Ok(mut parsed) = parsed_capture.lock()
+                && parsed
+                    .as_ref()
+                    .is_none_or(|(posted, ..)| *posted < generation)
+            {
+                *parsed = Some((generation, data, result));
+            }
+            ctx.request_repaint();
+        });
+    }
 
-    let mut parser = PacketParser::new();
-    match parser.parse_pcap_bytes(data) {
-        Ok((packets, messages, weenie_db)) => {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = ctx;
+        let (data, result) = parse();
+        finish_parse(app, data, result);
+    }
+}
+
+/// Load a parsed capture into the app, or report why parsing failed
+pub fn finish_parse(
+    app: &mut PcapViewerApp,
+    data: Option<Vec<u8>>,
+    result: anyhow::Result<crate::ParsedCapture>,
+) {
+    let source = app.pending_share_source.take();
+
+    match result {
+        Ok(capture) => {
+            app.apply_capture(capture, data);
             app.status_message = format!(
                 "Loaded {} packets, {} messages, {} weenies",
-                packets.len(),
-                messages.len(),
-                weenie_db.count()
+                app.packets.len(),
+                app.messages.len(),
+                app.weenie_db.count()
             );
-            app.packets = packets;
-            app.messages = messages;
-            app.weenie_db = weenie_db;
-
+            if app.truncated {
+                app.status_message
+                    .push_str(" (stopped at the message limit)");
+            }
+            app.share_source = source;
+            app.apply_initial_view();
             // Clear any URL load errors on success
             app.url_load_error = None;
-            app.selected_message = if app.messages.is_empty() {
-                None
-            } else {
-                Some(0)
-            };
-            app.selected_packet = if app.packets.is_empty() {
-                None
-            } else {
-                Some(0)
-            };
-
-            // Update time scrubbers
-            // Messages scrubber uses message timestamps
-            let message_timestamps: Vec<f64> = app.messages.iter().map(|m| m.timestamp).collect();
-            app.messages_scrubber.update_density(&message_timestamps);
-
-            // Fragments scrubber uses packet timestamps
-            let packet_timestamps: Vec<f64> = app.packets.iter().map(|p| p.timestamp).collect();
-            app.fragments_scrubber.update_density(&packet_timestamps);
         }
         Err(e) => {
             app.show_error(format!("Error parsing PCAP: {e}"));
@@ -62,9 +178,9 @@ pub fn load_example(app: &mut PcapViewerApp, ctx: &egui::Context) {
 
 /// Load example PCAP file (native)
 #[cfg(not(target_arch = "wasm32"))]
-pub fn load_example(app: &mut PcapViewerApp, _ctx: &egui::Context) {
+pub fn load_example(app: &mut PcapViewerApp, ctx: &egui::Context) {
     match std::fs::read("static/example.pcap") {
-        Ok(data) => parse_pcap_data(app, &data),
+        Ok(data) => parse_pcap_data(app, data, ctx),
         Err(e) => app.show_error(format!("Failed to load example PCAP: {e}")),
     }
 }
@@ -78,6 +194,8 @@ pub fn load_from_url(app: &mut PcapViewerApp, url: String, ctx: &egui::Context)
 
     app.is_loading = true;
     app.status_message = format!("Loading PCAP from {}...", url);
+    app.capture_name = capture_file_name(&url);
+    app.pending_share_source = Some(crate::share::ShareSource::Url(url.clone()));
 
     // Clear any previous errors
     if let Ok(mut error) = app.fetched_error.lock() {
@@ -146,11 +264,23 @@ pub fn load_from_discord(
 
     let fetched_data = app.fetched_data.clone();
     let fetched_error = app.fetched_error.clone();
+    let attachments = app.discord_attachments.clone();
+    let selector = app.discord_attachment.clone();
     let ctx = ctx.clone();
+    app.pending_share_source = Some(crate::share::ShareSource::Discord {
+        channel: channel_id.clone(),
+        msg: message_id.clone(),
+        attachment: selector.clone(),
+    });
+
+    app.capture_name = match selector.as_deref().map(str::trim) {
+        Some(name) if !name.is_empty() && name.parse::<usize>().is_err() => capture_file_name(name),
+        _ => format!("discord-{message_id}.pcap"),
+    };
 
     wasm_bindgen_futures::spawn_local(async move {
-        match fetch_discord_pcap(&channel_id, &message_id).await {
-            Ok(bytes) => {
+        match fetch_discord_pcap(&channel_id, &message_id, selector.as_deref()).await {
+            Ok(DiscordFetch::Pcap(bytes)) => {
                 if let Ok(mut data) = fetched_data.lock() {
                     *data = Some(bytes);
                 }
@@ -160,6 +290,12 @@ pub fn load_from_discord(
                 }
                 ctx.request_repaint();
             }
+            Ok(DiscordFetch::Listing(listing)) => {
+                if let Ok(mut pending) = attachments.lock() {
+                    *pending = Some(listing);
+                }
+                ctx.request_repaint();
+            }
             Err(e) => {
                 log::error!("Failed to fetch PCAP from Discord: {}", e);
                 // Store error for display
@@ -186,14 +322,21 @@ pub fn load_from_discord(
 
 /// Fetch PCAP from Discord API (WASM only)
 #[cfg(target_arch = "wasm32")]
-async fn fetch_discord_pcap(channel_id: &str, message_id: &str) -> Result<Vec<u8>, String> {
+async fn fetch_discord_pcap(
+    channel_id: &str,
+    message_id: &str,
+    selector: Option<&str>,
+) -> Result<DiscordFetch, String> {
     use wasm_bindgen::JsCast;
     use wasm_bindgen_futures::JsFuture;
     use web_sys::{Request, RequestInit, Response};
 
     let url = format!(
-        "{}/api/discord/channels/{}/messages/{}/attachments",
-        BOT_BASE_URL, channel_id, message_id
+        "{}/api/discord/channels/{}/messages/{}/attachments{}",
+        BOT_BASE_URL,
+        channel_id,
+        message_id,
+        attachment_query(selector)
     );
 
     let opts = RequestInit::new();
@@ -216,12 +359,36 @@ async fn fetch_discord_pcap(channel_id: &str, message_id: &str) -> Result<Vec<u8
         return Err(format!("HTTP {}: {}", status, status_text));
     }
 
+    // Without a selector the bot answers with a JSON listing of attachments
+    let is_json = resp
+        .headers()
+        .get("content-type")
+        .ok()
+        .flatten()
+        .is_some_and(|ct| ct.starts_with("application/json"));
+
     let array_buffer = JsFuture::from(resp.array_buffer().unwrap())
         .await
         .map_err(|e| format!("Failed to get array buffer: {:?}", e))?;
 
     let uint8_array = js_sys::Uint8Array::new(&array_buffer);
-    Ok(uint8_array.to_vec())
+    let bytes = uint8_array.to_vec();
+
+    if is_json {
+        let list: DiscordAttachmentList = serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Invalid attachment listing: {e}"))?;
+        return match list.attachments.len() {
+            0 => Err("No PCAP attachments found on this message".to_string()),
+            1 => {
+                // Only one capture, load it directly
+                let index = list.attachments[0].index.to_string();
+                Box::pin(fetch_discord_pcap(channel_id, message_id, Some(&index))).await
+            }
+            _ => Ok(DiscordFetch::Listing(list.attachments)),
+        };
+    }
+
+    Ok(DiscordFetch::Pcap(bytes))
 }
 
 /// Fetch bytes from URL (WASM only)
@@ -258,18 +425,223 @@ async fn fetch_bytes(url: &str) -> Result<Vec<u8>, String> {
 }
 
 /// Open file dialog (desktop only)
+///
+/// Selecting several files merges them into one session.
 #[cfg(feature = "desktop")]
 pub fn open_file_dialog(app: &mut PcapViewerApp) {
     use rfd::FileDialog;
 
-    if let Some(path) = FileDialog::new()
+    let Some(mut paths) = FileDialog::new()
         .add_filter("PCAP files", &["pcap", "pcapng"])
-        .pick_file()
+        .pick_files()
+    else {
+        return;
+    };
+    if paths.len() == 1 {
+        app.pending_file_path = paths.pop();
+        return;
+    }
+
+    let files: Result<Vec<Vec<u8>>, _> = paths.iter().map(std::fs::read).collect();
+    match files {
+        Ok(files) => {
+            app.capture_name = DEFAULT_CAPTURE_NAME.to_string();
+            app.dropped_file_data = files;
+        }
+        Err(e) => app.show_error(format!("Error reading file: {e}")),
+    }
+}
+
+/// File > Open Recent submenu (desktop only)
+///
+/// Files that have since been deleted or moved are dropped from the list
+/// each time the submenu opens.
+#[cfg(feature = "desktop")]
+pub fn show_recent_files_menu(app: &mut PcapViewerApp, ui: &mut egui::Ui) {
+    ui.add_enabled_ui(!app.recent_files.is_empty(), |ui| {
+        ui.menu_button("Open Recent", |ui| {
+            let frame = ui.ctx().frame_nr();
+            if app.recent_menu_frame.is_none_or(|shown| shown + 1 < frame) {
+                app.recent_files.retain(|file| file.path.exists());
+            }
+            app.recent_menu_frame = Some(frame);
+            let now = crate::state::unix_time();
+
+            let mut chosen = None;
+            for file in &app.recent_files {
+                let name = file.path.file_name().map_or_else(
+                    || file.path.display().to_string(),
+                    |name| name.to_string_lossy().into_owned(),
+                );
+                let hover = format!(
+                    "{}\nOpened {}",
+                    file.path.display(),
+                    crate::state::format_age(file.opened, now)
+                );
+                if ui.button(name).on_hover_text(hover).clicked() {
+                    chosen = Some(file.path.clone());
+                }
+            }
+            if chosen.is_some() {
+                app.pending_file_path = chosen;
+                ui.close_menu();
+            }
+        });
+    });
+}
+
+/// Offer the loaded capture bytes as a browser download (WASM)
+#[cfg(target_arch = "wasm32")]
+pub fn save_capture(app: &mut PcapViewerApp) {
+    let Some(data) = app.loaded_data.as_deref() else {
+        return;
+    };
+    if let Err(e) = download_bytes(data, &app.capture_name) {
+        app.show_error(format!("Failed to download capture: {e}"));
+    }
+}
+
+/// Trigger a browser download of `data` named `file_name` (WASM only)
+#[cfg(target_arch = "wasm32")]
+fn download_bytes(data: &[u8], file_name: &str) -> Result<(), String> {
+    use wasm_bindgen::JsCast;
+    use web_sys::{Blob, HtmlAnchorElement, Url};
+
+    let parts = js_sys::Array::new();
+    parts.push(&js_sys::Uint8Array::from(data));
+    let blob = Blob::new_with_u8_array_sequence(&parts)
+        .map_err(|e| format!("Failed to create blob: {:?}", e))?;
+    let url = Url::create_object_url_with_blob(&blob)
+        .map_err(|e| format!("Failed to create object URL: {:?}", e))?;
+
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| "No document object".to_string())?;
+    let anchor: HtmlAnchorElement = document
+        .create_element("a")
+        .map_err(|e| format!("Failed to create link: {:?}", e))?
+        .dyn_into()
+        .map_err(|_| "Element is not an anchor".to_string())?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+
+    Url::revoke_object_url(&url).map_err(|e| format!("Failed to revoke object URL: {:?}", e))
+}
+
+/// Save the loaded capture bytes to a file (desktop)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_capture(app: &mut PcapViewerApp) {
+    #[cfg(feature = "desktop")]
+    {
+        use rfd::FileDialog;
+
+        let Some(data) = app.loaded_data.as_deref() else {
+            return;
+        };
+        let Some(path) = FileDialog::new()
+            .add_filter("PCAP files", &["pcap", "pcapng"])
+            .set_file_name(&app.capture_name)
+            .save_file()
+        else {
+            return;
+        };
+
+        match std::fs::write(&path, data) {
+            Ok(()) => app.status_message = format!("Saved {}", path.display()),
+            Err(e) => app.show_error(format!("Failed to save {}: {e}", path.display())),
+        }
+    }
+    #[cfg(not(feature = "desktop"))]
     {
-        app.pending_file_path = Some(path);
+        app.status_message = "Saving captures is not supported in this build".to_string();
     }
 }
 
+/// Ask where to save an export and write it there with `write` (desktop only)
+///
+/// The dialog offers `kind` files named like `file_name` and a gzipped
+/// variant; everything goes through [`OutputFile`](common::export::OutputFile),
+/// so output is buffered and compressed when the chosen name ends in `.gz`.
+#[cfg(feature = "desktop")]
+fn export_dialog(
+    app: &mut PcapViewerApp,
+    kind: &str,
+    file_name: &str,
+    write: impl FnOnce(&PcapViewerApp, &mut common::export::OutputFile) -> anyhow::Result<()>,
+) {
+    use rfd::FileDialog;
+
+    let extension = file_name.rsplit('.').next().unwrap_or_default();
+    let Some(path) = FileDialog::new()
+        .add_filter(kind, &[extension])
+        .add_filter(format!("Gzipped {kind}"), &["gz"])
+        .set_file_name(file_name)
+        .save_file()
+    else {
+        return;
+    };
+
+    let result = common::export::OutputFile::create(&path).and_then(|mut file| {
+        write(app, &mut file)?;
+        file.finish()
+    });
+    match result {
+        Ok(()) => app.status_message = format!("Exported to {}", path.display()),
+        Err(e) => app.show_error(format!("Failed to export {}: {e}", path.display())),
+    }
+}
+
+/// Save the parsed capture as one JSON document (desktop only)
+#[cfg(feature = "desktop")]
+pub fn export_json_dialog(app: &mut PcapViewerApp) {
+    export_dialog(app, "JSON files", "capture.json", |app, file| {
+        common::export::write_capture_json(file, &app.messages, &app.packets, &app.weenie_db)
+    });
+}
+
+/// Save the messages as JSONL (desktop only)
+#[cfg(feature = "desktop")]
+pub fn export_jsonl_dialog(app: &mut PcapViewerApp) {
+    export_dialog(app, "JSON Lines", "messages.jsonl", |app, file| {
+        common::export::write_messages_jsonl(file, &app.messages)
+    });
+}
+
+/// Save the capture summary as JSON (desktop only)
+#[cfg(feature = "desktop")]
+pub fn export_summary_dialog(app: &mut PcapViewerApp) {
+    export_dialog(app, "JSON files", "summary.json", |app, file| {
+        let summary = common::summary::capture_summary(
+            &app.packets,
+            &app.messages,
+            &app.weenie_db,
+            common::summary::DEFAULT_TOP_TYPES,
+        );
+        Ok(serde_json::to_writer_pretty(file, &summary)?)
+    });
+}
+
+/// Save the raw opcode histogram as JSON (desktop only)
+#[cfg(feature = "desktop")]
+pub fn export_opcode_histogram_dialog(app: &mut PcapViewerApp) {
+    export_dialog(app, "JSON files", "opcodes.json", |app, file| {
+        let histogram = common::coverage::opcode_histogram(&app.messages);
+        Ok(serde_json::to_writer_pretty(file, &histogram)?)
+    });
+}
+
+/// Save the JSON Schema of the capture export, for tools that read it (desktop only)
+#[cfg(feature = "desktop")]
+pub fn export_schema_dialog(app: &mut PcapViewerApp) {
+    export_dialog(app, "JSON Schema", "capture.schema.json", |_, file| {
+        Ok(serde_json::to_writer_pretty(
+            file,
+            &common::schema::capture_schema(),
+        )?)
+    });
+}
+
 /// Preview files being dropped
 pub fn preview_files_being_dropped(ctx: &egui::Context) {
     use egui::*;
@@ -338,6 +710,54 @@ pub fn show_url_dialog(app: &mut PcapViewerApp, ctx: &egui::Context) {
     }
 }
 
+/// Show picker for messages that carry several PCAP attachments
+pub fn show_attachment_picker(app: &mut PcapViewerApp, ctx: &egui::Context) {
+    let mut selected: Option<usize> = None;
+    let mut close_dialog = false;
+
+    egui::Window::new("Select Attachment")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label("This message has several captures. Choose one to load:");
+            ui.add_space(5.0);
+
+            for attachment in &app.attachment_choices {
+                let label = match attachment.size {
+                    Some(size) => format!("{} ({} KB)", attachment.filename, size / 1024),
+                    None => attachment.filename.clone(),
+                };
+                if ui.button(label).clicked() {
+                    selected = Some(attachment.index);
+                }
+            }
+
+            ui.add_space(10.0);
+
+            ui.horizontal(|ui| {
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("Cancel").clicked() {
+                        close_dialog = true;
+                    }
+                });
+            });
+        });
+
+    if let Some(index) = selected {
+        app.discord_attachment = Some(index.to_string());
+        let channel = app.discord_channel_id.clone();
+        let msg = app.discord_message_id.clone();
+        load_from_discord(app, channel, msg, ctx);
+        close_dialog = true;
+    }
+
+    if close_dialog {
+        app.show_attachment_picker = false;
+        app.attachment_choices.clear();
+    }
+}
+
 /// Show settings dialog
 pub fn show_settings_dialog(app: &mut PcapViewerApp, ctx: &egui::Context) {
     let mut close_settings = false;
@@ -386,6 +806,13 @@ pub fn show_settings_dialog(app: &mut PcapViewerApp, ctx: &egui::Context) {
             ui.add_space(20.0);
 
             ui.horizontal(|ui| {
+                if ui
+                    .button("Reset to defaults")
+                    .on_hover_text("Restore theme, sort order, and view mode defaults")
+                    .clicked()
+                {
+                    app.apply_settings(crate::state::UiSettings::default());
+                }
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     if ui.button("Close").clicked() {
                         close_settings = true;
@@ -460,3 +887,51 @@ pub fn show_about_dialog(app: &mut PcapViewerApp, ctx: &egui::Context) {
         app.show_about = false;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_file_name() {
+        assert_eq!(
+            capture_file_name("https://example.com/files/fight.pcap?dl=1"),
+            "fight.pcap"
+        );
+        assert_eq!(capture_file_name("./session.PCAPNG"), "session.PCAPNG");
+        assert_eq!(capture_file_name("https://example.com/get/42"), "42.pcap");
+        assert_eq!(
+            capture_file_name("https://example.com/"),
+            DEFAULT_CAPTURE_NAME
+        );
+    }
+
+    #[test]
+    fn test_attachment_query_none() {
+        assert_eq!(attachment_query(None), "");
+        assert_eq!(attachment_query(Some("  ")), "");
+    }
+
+    #[test]
+    fn test_attachment_query_index() {
+        assert_eq!(attachment_query(Some("2")), "?index=2");
+    }
+
+    #[test]
+    fn test_attachment_query_filename() {
+        assert_eq!(
+            attachment_query(Some("my capture.pcap")),
+            "?attachment=my%20capture.pcap"
+        );
+    }
+
+    #[test]
+    fn test_parse_attachment_listing() {
+        let json = r#"{"attachments":[{"index":0,"filename":"a.pcap","size":2048},{"index":1,"filename":"b.pcapng"}]}"#;
+        let list: DiscordAttachmentList = serde_json::from_str(json).unwrap();
+        assert_eq!(list.attachments.len(), 2);
+        assert_eq!(list.attachments[0].size, Some(2048));
+        assert_eq!(list.attachments[1].filename, "b.pcapng");
+        assert_eq!(list.attachments[1].size, None);
+    }
+}
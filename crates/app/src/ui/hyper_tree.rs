@@ -1,8 +1,9 @@
 //! AC Protocol-aware JSON tree viewer with hex display and click-to-filter
 
+use crate::filter::field_filter_term;
 use eframe::egui;
 use serde_json::Value;
-use std::collections::HashSet;
+use std::collections::HashMap;
 
 /// Field types that determine display format
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -18,22 +19,51 @@ pub struct TreeResponse {
     pub filter_clicked: Option<String>,
 }
 
+/// Open/closed state of tree nodes keyed by dotted JSON path (e.g. `S2C.Items[0]`)
+pub type TreeOpenState = HashMap<String, bool>;
+
 /// AC Protocol-aware JSON tree viewer
-pub struct AcJsonTree {
+pub struct AcJsonTree<'a> {
     id: String,
-    expanded_paths: HashSet<String>,
+    open_state: &'a mut TreeOpenState,
     response: TreeResponse,
 }
 
-impl AcJsonTree {
-    pub fn new(id: impl Into<String>) -> Self {
+impl<'a> AcJsonTree<'a> {
+    /// Create a tree whose collapse state is read from and written to `open_state`
+    ///
+    /// Nodes without a remembered state use the defaults: first-level objects open,
+    /// everything else closed.
+    pub fn new(id: impl Into<String>, open_state: &'a mut TreeOpenState) -> Self {
         Self {
             id: id.into(),
-            expanded_paths: HashSet::new(),
+            open_state,
             response: TreeResponse::default(),
         }
     }
 
+    /// Show a collapsible node, remembering its state when the header is clicked
+    fn show_node(
+        &mut self,
+        ui: &mut egui::Ui,
+        label: String,
+        item_path: String,
+        default_open: bool,
+        add_contents: impl FnOnce(&mut Self, &mut egui::Ui),
+    ) {
+        let header_id = egui::Id::new(format!("{}_{}", self.id, item_path));
+        let is_open = *self.open_state.get(&item_path).unwrap_or(&default_open);
+
+        let header_response = egui::CollapsingHeader::new(label)
+            .id_salt(header_id)
+            .open(Some(is_open))
+            .show(ui, |ui| add_contents(self, ui));
+
+        if header_response.header_response.clicked() {
+            self.open_state.insert(item_path, !is_open);
+        }
+    }
+
     /// Show the tree and return interaction result
     pub fn show(mut self, ui: &mut egui::Ui, value: &Value) -> TreeResponse {
         self.show_value(ui, value, "", 0);
@@ -53,24 +83,11 @@ impl AcJsonTree {
 
                     match val {
                         Value::Object(_) | Value::Array(_) => {
-                            // Collapsible header for nested structures
-                            let header_id = egui::Id::new(format!("{}_{}", self.id, item_path));
-                            let is_expanded = self.expanded_paths.contains(&item_path) || depth < 1; // Auto-expand first level
-
-                            let header_response = egui::CollapsingHeader::new(key)
-                                .id_salt(header_id)
-                                .default_open(is_expanded)
-                                .show(ui, |ui| {
-                                    self.show_value(ui, val, &item_path, depth + 1);
-                                });
-
-                            if header_response.header_response.clicked() {
-                                if self.expanded_paths.contains(&item_path) {
-                                    self.expanded_paths.remove(&item_path);
-                                } else {
-                                    self.expanded_paths.insert(item_path.clone());
-                                }
-                            }
+                            // Collapsible header for nested structures, first level open by default
+                            let child_path = item_path.clone();
+                            self.show_node(ui, key.clone(), item_path, depth < 1, |tree, ui| {
+                                tree.show_value(ui, val, &child_path, depth + 1);
+                            });
                         }
                         _ => {
                             // Leaf value - show key and clickable value
@@ -88,23 +105,10 @@ impl AcJsonTree {
 
                     match val {
                         Value::Object(_) | Value::Array(_) => {
-                            let header_id = egui::Id::new(format!("{}_{}", self.id, item_path));
-                            let is_expanded = self.expanded_paths.contains(&item_path);
-
-                            let header_response = egui::CollapsingHeader::new(format!("[{idx}]"))
-                                .id_salt(header_id)
-                                .default_open(is_expanded)
-                                .show(ui, |ui| {
-                                    self.show_value(ui, val, &item_path, depth + 1);
-                                });
-
-                            if header_response.header_response.clicked() {
-                                if self.expanded_paths.contains(&item_path) {
-                                    self.expanded_paths.remove(&item_path);
-                                } else {
-                                    self.expanded_paths.insert(item_path.clone());
-                                }
-                            }
+                            let child_path = item_path.clone();
+                            self.show_node(ui, format!("[{idx}]"), item_path, false, |tree, ui| {
+                                tree.show_value(ui, val, &child_path, depth + 1);
+                            });
                         }
                         _ => {
                             ui.horizontal(|ui| {
@@ -129,9 +133,9 @@ impl AcJsonTree {
         match value {
             Value::Number(num) => {
                 if let Some(u) = num.as_u64() {
-                    self.show_number_value(ui, u, format);
+                    self.show_number_value(ui, key, u, format);
                 } else if let Some(i) = num.as_i64() {
-                    self.show_signed_number_value(ui, i, format);
+                    self.show_signed_number_value(ui, key, i, format);
                 } else if let Some(f) = num.as_f64() {
                     self.show_float_value(ui, f);
                 }
@@ -141,50 +145,34 @@ impl AcJsonTree {
                 if Self::is_hex_string(s) {
                     if let Ok(num) = u64::from_str_radix(s, 16) {
                         ui.horizontal(|ui| {
-                            let response = ui
-                                .selectable_label(
-                                    false,
-                                    egui::RichText::new(format!("0x{s}"))
-                                        .color(ui.visuals().hyperlink_color),
-                                )
-                                .on_hover_text("Click to filter")
-                                .on_hover_cursor(egui::CursorIcon::PointingHand);
-
-                            if response.clicked() {
-                                self.response.filter_clicked = Some(format!("0x{s}"));
-                            }
+                            self.filter_link(
+                                ui,
+                                key,
+                                egui::RichText::new(format!("0x{s}"))
+                                    .color(ui.visuals().hyperlink_color),
+                                format!("0x{s}"),
+                            );
 
                             ui.label(egui::RichText::new(format!("({num})")).weak().small());
                         });
                     } else {
                         // Just a regular string that happens to look hex-like
-                        let response = ui
-                            .selectable_label(
-                                false,
-                                egui::RichText::new(format!("\"{s}\""))
-                                    .color(ui.visuals().hyperlink_color),
-                            )
-                            .on_hover_text("Click to filter")
-                            .on_hover_cursor(egui::CursorIcon::PointingHand);
-
-                        if response.clicked() {
-                            self.response.filter_clicked = Some(s.clone());
-                        }
-                    }
-                } else {
-                    // Regular string
-                    let response = ui
-                        .selectable_label(
-                            false,
+                        self.filter_link(
+                            ui,
+                            key,
                             egui::RichText::new(format!("\"{s}\""))
                                 .color(ui.visuals().hyperlink_color),
-                        )
-                        .on_hover_text("Click to filter")
-                        .on_hover_cursor(egui::CursorIcon::PointingHand);
-
-                    if response.clicked() {
-                        self.response.filter_clicked = Some(s.clone());
+                            s.clone(),
+                        );
                     }
+                } else {
+                    // Regular string
+                    self.filter_link(
+                        ui,
+                        key,
+                        egui::RichText::new(format!("\"{s}\"")).color(ui.visuals().hyperlink_color),
+                        s.clone(),
+                    );
                 }
             }
             Value::Bool(b) => {
@@ -205,113 +193,128 @@ impl AcJsonTree {
     }
 
     /// Show a numeric value with hex/decimal display
-    fn show_number_value(&mut self, ui: &mut egui::Ui, num: u64, format: DisplayFormat) {
+    fn show_number_value(&mut self, ui: &mut egui::Ui, key: &str, num: u64, format: DisplayFormat) {
         match format {
             DisplayFormat::Hex => {
                 ui.horizontal(|ui| {
-                    let response = ui
-                        .selectable_label(
-                            false,
-                            egui::RichText::new(format!("0x{num:X}"))
-                                .color(ui.visuals().hyperlink_color),
-                        )
-                        .on_hover_text("Click to filter")
-                        .on_hover_cursor(egui::CursorIcon::PointingHand);
-
-                    if response.clicked() {
-                        self.response.filter_clicked = Some(format!("0x{num:X}"));
-                    }
+                    self.filter_link(
+                        ui,
+                        key,
+                        egui::RichText::new(format!("0x{num:X}"))
+                            .color(ui.visuals().hyperlink_color),
+                        format!("0x{num:X}"),
+                    );
 
                     ui.label(egui::RichText::new(format!("({num})")).weak().small());
                 });
             }
             DisplayFormat::Decimal => {
-                let response = ui
-                    .selectable_label(
-                        false,
-                        egui::RichText::new(num.to_string()).color(ui.visuals().hyperlink_color),
-                    )
-                    .on_hover_text("Click to filter")
-                    .on_hover_cursor(egui::CursorIcon::PointingHand);
-
-                if response.clicked() {
-                    self.response.filter_clicked = Some(num.to_string());
-                }
+                self.filter_link(
+                    ui,
+                    key,
+                    egui::RichText::new(num.to_string()).color(ui.visuals().hyperlink_color),
+                    num.to_string(),
+                );
             }
             DisplayFormat::Auto => {
                 // Show both if the number is large enough to be interesting in hex
                 if num > 255 {
                     ui.horizontal(|ui| {
-                        let response = ui
-                            .selectable_label(
-                                false,
-                                egui::RichText::new(format!("0x{num:X}"))
-                                    .color(ui.visuals().hyperlink_color),
-                            )
-                            .on_hover_text("Click to filter")
-                            .on_hover_cursor(egui::CursorIcon::PointingHand);
-
-                        if response.clicked() {
-                            self.response.filter_clicked = Some(format!("0x{num:X}"));
-                        }
+                        self.filter_link(
+                            ui,
+                            key,
+                            egui::RichText::new(format!("0x{num:X}"))
+                                .color(ui.visuals().hyperlink_color),
+                            format!("0x{num:X}"),
+                        );
 
                         ui.label(egui::RichText::new(format!("({num})")).weak().small());
                     });
                 } else {
-                    let response = ui
-                        .selectable_label(
-                            false,
-                            egui::RichText::new(num.to_string())
-                                .color(ui.visuals().hyperlink_color),
-                        )
-                        .on_hover_text("Click to filter")
-                        .on_hover_cursor(egui::CursorIcon::PointingHand);
-
-                    if response.clicked() {
-                        self.response.filter_clicked = Some(num.to_string());
-                    }
+                    self.filter_link(
+                        ui,
+                        key,
+                        egui::RichText::new(num.to_string()).color(ui.visuals().hyperlink_color),
+                        num.to_string(),
+                    );
                 }
             }
         }
     }
 
     /// Show a signed number value
-    fn show_signed_number_value(&mut self, ui: &mut egui::Ui, num: i64, format: DisplayFormat) {
+    fn show_signed_number_value(
+        &mut self,
+        ui: &mut egui::Ui,
+        key: &str,
+        num: i64,
+        format: DisplayFormat,
+    ) {
         match format {
             DisplayFormat::Hex if num >= 0 => {
                 ui.horizontal(|ui| {
-                    let response = ui
-                        .selectable_label(
-                            false,
-                            egui::RichText::new(format!("0x{num:X}"))
-                                .color(ui.visuals().hyperlink_color),
-                        )
-                        .on_hover_text("Click to filter")
-                        .on_hover_cursor(egui::CursorIcon::PointingHand);
-
-                    if response.clicked() {
-                        self.response.filter_clicked = Some(format!("0x{num:X}"));
-                    }
+                    self.filter_link(
+                        ui,
+                        key,
+                        egui::RichText::new(format!("0x{num:X}"))
+                            .color(ui.visuals().hyperlink_color),
+                        format!("0x{num:X}"),
+                    );
 
                     ui.label(egui::RichText::new(format!("({num})")).weak().small());
                 });
             }
             _ => {
-                let response = ui
-                    .selectable_label(
-                        false,
-                        egui::RichText::new(num.to_string()).color(ui.visuals().hyperlink_color),
-                    )
-                    .on_hover_text("Click to filter")
-                    .on_hover_cursor(egui::CursorIcon::PointingHand);
-
-                if response.clicked() {
-                    self.response.filter_clicked = Some(num.to_string());
-                }
+                self.filter_link(
+                    ui,
+                    key,
+                    egui::RichText::new(num.to_string()).color(ui.visuals().hyperlink_color),
+                    num.to_string(),
+                );
             }
         }
     }
 
+    /// Clickable value that filters on click
+    ///
+    /// The context menu also offers a `Key:value` filter scoped to this field,
+    /// like Wireshark's "Apply as Filter".
+    fn filter_link(
+        &mut self,
+        ui: &mut egui::Ui,
+        key: &str,
+        text: impl Into<egui::WidgetText>,
+        filter: String,
+    ) {
+        let link = ui
+            .selectable_label(false, text)
+            .on_hover_text("Click to filter, right-click for more")
+            .on_hover_cursor(egui::CursorIcon::PointingHand);
+
+        if link.clicked() {
+            self.response.filter_clicked = Some(filter.clone());
+        }
+
+        // Array indices and the top-level value have no field name to scope to
+        let is_field = !key.is_empty() && !key.chars().all(|c| c.is_ascii_digit());
+        link.context_menu(|ui| {
+            if ui
+                .add_enabled(
+                    is_field,
+                    egui::Button::new(format!("Filter to {key} = this")),
+                )
+                .clicked()
+            {
+                self.response.filter_clicked = Some(field_filter_term(key, &filter));
+                ui.close_menu();
+            }
+            if ui.button("Filter to this value anywhere").clicked() {
+                self.response.filter_clicked = Some(filter.clone());
+                ui.close_menu();
+            }
+        });
+    }
+
     /// Show a float value
     fn show_float_value(&mut self, ui: &mut egui::Ui, num: f64) {
         ui.label(egui::RichText::new(format!("{num}")).weak());
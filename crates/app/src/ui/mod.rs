@@ -2,10 +2,12 @@
 //!
 //! This module contains all UI-related components and panels.
 
+pub mod combat_panel;
 pub mod detail_panel;
 pub mod error_dialog;
 pub mod file_panel;
 pub mod hyper_tree;
+pub mod opcode_reference;
 pub mod packet_list;
 pub mod weenie_panel;
 
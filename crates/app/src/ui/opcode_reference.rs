@@ -0,0 +1,116 @@
+//! Searchable reference of the message types the decoder knows
+
+use crate::PcapViewerApp;
+use common::messages::{KnownMessageType, known_message_types};
+use eframe::egui;
+
+/// Whether a reference entry matches the (lowercased) filter text
+fn reference_matches(entry: &KnownMessageType, filter: &str) -> bool {
+    filter.is_empty()
+        || entry.name.to_lowercase().contains(filter)
+        || entry.code.to_lowercase().contains(filter)
+        || entry.family.to_lowercase() == filter
+}
+
+/// Search box query that finds messages of this type
+///
+/// Top-level messages are matched by opcode; game actions and events share
+/// their wrapper's opcode, so those are matched by type name.
+fn search_query(entry: &KnownMessageType) -> String {
+    match entry.family {
+        "C2S" | "S2C" => format!("OpCode:{}", entry.code),
+        _ => format!("Type:{}", entry.name),
+    }
+}
+
+/// Window listing known message types; clicking one filters the messages list
+pub fn show_opcode_reference(app: &mut PcapViewerApp, ctx: &egui::Context) {
+    let mut open = app.show_opcode_reference;
+    let mut chosen = None;
+
+    egui::Window::new("Message Types")
+        .open(&mut open)
+        .default_size([420.0, 480.0])
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Filter:");
+                ui.text_edit_singleline(&mut app.opcode_reference_filter);
+            });
+            ui.separator();
+
+            let filter = app.opcode_reference_filter.to_lowercase();
+            let entries: Vec<&KnownMessageType> = known_message_types()
+                .iter()
+                .filter(|entry| reference_matches(entry, &filter))
+                .collect();
+
+            egui_extras::TableBuilder::new(ui)
+                .striped(true)
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                .column(egui_extras::Column::exact(90.0))
+                .column(egui_extras::Column::exact(80.0))
+                .column(egui_extras::Column::remainder())
+                .header(20.0, |mut header| {
+                    for title in ["Family", "Code", "Name"] {
+                        header.col(|ui| {
+                            ui.strong(title);
+                        });
+                    }
+                })
+                .body(|body| {
+                    body.rows(18.0, entries.len(), |mut row| {
+                        let entry = entries[row.index()];
+                        row.col(|ui| {
+                            ui.label(egui::RichText::new(entry.family).weak());
+                        });
+                        row.col(|ui| {
+                            ui.monospace(&entry.code);
+                        });
+                        row.col(|ui| {
+                            if ui
+                                .selectable_label(false, &entry.name)
+                                .on_hover_text("Show these messages")
+                                .clicked()
+                            {
+                                chosen = Some(search_query(entry));
+                            }
+                        });
+                    });
+                });
+        });
+
+    if let Some(query) = chosen {
+        app.search_query = query;
+        app.current_tab = common::Tab::Messages;
+    }
+    app.show_opcode_reference = open;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::messages::MessageKind;
+
+    fn entry(family: &'static str, code: &str, name: &str) -> KnownMessageType {
+        KnownMessageType {
+            kind: MessageKind::Unknown(0),
+            family,
+            code: code.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_reference_filter_and_query() {
+        let appraise = entry("GameEvent", "F7B0/00C9", "Item_SetAppraiseInfo");
+        assert!(reference_matches(&appraise, ""));
+        assert!(reference_matches(&appraise, "appraise"));
+        assert!(reference_matches(&appraise, "00c9"));
+        assert!(reference_matches(&appraise, "gameevent"));
+        assert!(!reference_matches(&appraise, "s2c"));
+        assert_eq!(search_query(&appraise), "Type:Item_SetAppraiseInfo");
+
+        let movement = entry("S2C", "F74C", "Movement_SetObjectMovement");
+        assert_eq!(search_query(&movement), "OpCode:F74C");
+    }
+}
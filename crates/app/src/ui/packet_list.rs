@@ -1,12 +1,13 @@
 //! Packet and message list UI components
 
-use crate::filter::{matches_any_filter, parse_filter_string};
-use crate::state::json_contains_string;
+use crate::filter::{message_matches_search, type_highlights};
 use crate::{PcapViewerApp, SortField};
-// TODO: Re-enable this import when needed
-// use ac_parser::messages::ParsedMessage;
+use common::FragmentInfo;
+use common::messages::{MessageCategory, ParsedMessage};
 use eframe::egui;
 use egui_extras::Column;
+use std::collections::HashSet;
+use std::ops::Range;
 
 /// Draw sort button
 pub fn draw_sort_button(app: &mut PcapViewerApp, ui: &mut egui::Ui) -> bool {
@@ -53,6 +54,58 @@ pub fn draw_sort_button(app: &mut PcapViewerApp, ui: &mut egui::Ui) -> bool {
     false
 }
 
+/// Draw the toolbar "Go to #" box, which jumps to a message by id
+pub fn show_goto_box(app: &mut PcapViewerApp, ui: &mut egui::Ui) {
+    ui.label("Go to #");
+    let response = ui.add(
+        egui::TextEdit::singleline(&mut app.goto_message_input)
+            .hint_text("id")
+            .desired_width(60.0),
+    );
+    let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+    let target = crate::share::parse_message_id(&app.goto_message_input);
+
+    let clicked = ui
+        .add_enabled(target.is_some(), egui::Button::new("Go"))
+        .clicked();
+    if let Some(id) = target
+        && (clicked || submitted)
+    {
+        app.go_to_message(id);
+    }
+}
+
+/// Ask whether to clear the filter hiding the message picked in the "Go to #" box
+pub fn show_hidden_goto_prompt(app: &mut PcapViewerApp, ctx: &egui::Context) {
+    let Some(id) = app
+        .hidden_goto_message
+        .and_then(|i| app.messages.get(i))
+        .map(|m| m.id)
+    else {
+        app.hidden_goto_message = None;
+        return;
+    };
+
+    egui::Window::new("Message Hidden")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label(format!("Message #{id} is hidden by the current filter."));
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                if ui.button("Clear Filter").clicked() {
+                    app.hidden_goto_message = None;
+                    app.clear_message_filter();
+                    app.go_to_message(id);
+                }
+                if ui.button("Cancel").clicked() {
+                    app.hidden_goto_message = None;
+                }
+            });
+        });
+}
+
 /// Draw theme toggle button
 pub fn draw_theme_toggle(app: &mut PcapViewerApp, ui: &mut egui::Ui) {
     let (rect, response) = ui.allocate_exact_size(egui::vec2(20.0, 20.0), egui::Sense::click());
@@ -220,50 +273,14 @@ pub fn show_messages_list(app: &mut PcapViewerApp, ui: &mut egui::Ui, is_mobile:
     let sort_field = app.sort_field;
     let sort_ascending = app.sort_ascending;
     let total = app.messages.len();
-    let time_filter = app.messages_scrubber.get_selected_range().cloned();
+    let filter = app.message_filter();
 
     // Collect timestamps of messages matching search (for highlighting on scrubber)
     if !search.is_empty() {
-        let filters = parse_filter_string(&search);
         let search_matched_timestamps: Vec<f64> = app
             .messages
             .iter()
-            .filter(|m| {
-                // Use the same matching logic as the table filtering
-                let mut matches = false;
-
-                // Search in message ID
-                if matches_any_filter(&filters, &m.id.to_string()) {
-                    matches = true;
-                }
-
-                // Check opcode match
-                if !matches && matches_any_filter(&filters, &m.opcode) {
-                    matches = true;
-                }
-
-                // Check direction match
-                if !matches && matches_any_filter(&filters, &m.direction) {
-                    matches = true;
-                }
-
-                // Check if filter matches in data fields
-                if !matches {
-                    let data_str = serde_json::to_string(&m.data).unwrap_or_default();
-                    if matches_any_filter(&filters, &data_str) {
-                        matches = true;
-                    }
-                }
-
-                // Always also do text search (type and data)
-                if !matches {
-                    let type_matches = m.message_type.to_lowercase().contains(&search);
-                    let data_matches = json_contains_string(&m.data, &search);
-                    matches = type_matches || data_matches;
-                }
-
-                matches
-            })
+            .filter(|m| message_matches_search(m, &search))
             .map(|m| m.timestamp)
             .collect();
         app.messages_scrubber
@@ -276,59 +293,7 @@ pub fn show_messages_list(app: &mut PcapViewerApp, ui: &mut egui::Ui, is_mobile:
         .messages
         .iter()
         .enumerate()
-        .filter(|(_, m)| {
-            // Apply search filter with rich filter support
-            let matches_search = if search.is_empty() {
-                true
-            } else {
-                // Parse search string into rich filters (supports hex, decimal, and text)
-                let filters = parse_filter_string(&search);
-
-                // Check if any filter matches
-                let mut matches = false;
-
-                // Search in message ID
-                if matches_any_filter(&filters, &m.id.to_string()) {
-                    matches = true;
-                }
-
-                // Check opcode match
-                if !matches && matches_any_filter(&filters, &m.opcode) {
-                    matches = true;
-                }
-
-                // Check direction match
-                if !matches && matches_any_filter(&filters, &m.direction) {
-                    matches = true;
-                }
-
-                // Check if filter matches in data fields
-                if !matches {
-                    let data_str = serde_json::to_string(&m.data).unwrap_or_default();
-                    if matches_any_filter(&filters, &data_str) {
-                        matches = true;
-                    }
-                }
-
-                // Always also do text search (type and data)
-                if !matches {
-                    let type_matches = m.message_type.to_lowercase().contains(&search);
-                    let data_matches = json_contains_string(&m.data, &search);
-                    matches = type_matches || data_matches;
-                }
-
-                matches
-            };
-
-            // Apply time filter
-            let matches_time = if let Some(ref range) = time_filter {
-                range.contains(m.timestamp)
-            } else {
-                true
-            };
-
-            matches_search && matches_time
-        })
+        .filter(|(_, m)| filter.matches(m))
         .map(|(idx, m)| {
             (
                 idx,
@@ -346,16 +311,378 @@ pub fn show_messages_list(app: &mut PcapViewerApp, ui: &mut egui::Ui, is_mobile:
             SortField::Type => a.2.cmp(&b.2),
             SortField::Direction => a.3.cmp(&b.3),
             SortField::OpCode => a.4.cmp(&b.4),
+            SortField::Time => app.messages[a.0]
+                .timestamp
+                .total_cmp(&app.messages[b.0].timestamp)
+                .then(a.1.cmp(&b.1)),
         };
         if sort_ascending { cmp } else { cmp.reverse() }
     });
 
     ui.horizontal(|ui| {
         ui.label(format!("{}/{} messages", filtered.len(), total));
+        egui::ComboBox::from_id_salt("message_category_filter")
+            .selected_text(
+                app.category_filter
+                    .map_or("All categories", MessageCategory::name),
+            )
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut app.category_filter, None, "All categories");
+                for category in MessageCategory::ALL {
+                    ui.selectable_value(&mut app.category_filter, Some(category), category.name());
+                }
+            });
+        show_unhandled_summary(ui, &app.unhandled_report);
+    });
+    show_category_legend(ui, &filtered);
+    ui.separator();
+
+    // The split view lists the same rows in time order
+    let split = (app.split_view && !is_mobile).then(|| split_rows(&app.messages, &filtered));
+    let order: Vec<usize> = match &split {
+        Some(rows) => rows.iter().map(|row| row.0).collect(),
+        None => filtered.iter().map(|f| f.0).collect(),
+    };
+
+    // Keyboard navigation through the listed rows, ignored while typing in the search box
+    let mut scroll_to = app
+        .scroll_to_message
+        .take()
+        .and_then(|target| order.iter().position(|&idx| idx == target));
+    if !ui.ctx().wants_keyboard_input() {
+        let page = ((ui.available_height() / 20.0) as usize).max(1);
+        let current = app
+            .selected_message
+            .and_then(|selected| order.iter().position(|&idx| idx == selected));
+        let key = ui.input(pressed_list_key);
+
+        if let Some(key) = key
+            && let Some(position) = navigate_list(current, order.len(), key, page)
+        {
+            app.selected_message = Some(order[position]);
+            scroll_to = Some(position);
+        }
+
+        if is_mobile
+            && app.selected_message.is_some()
+            && ui.input(|i| i.key_pressed(egui::Key::Enter))
+        {
+            app.show_detail_panel = true;
+        }
+    }
+
+    if let Some(rows) = split {
+        show_split_table(app, ui, &rows, scroll_to);
+    } else {
+        show_messages_table(
+            app,
+            ui,
+            is_mobile,
+            &filtered,
+            sort_field,
+            sort_ascending,
+            scroll_to,
+        );
+    }
+}
+
+/// `text` in `color` with the byte ranges in `highlights` on a highlight background
+///
+/// Shows why a row matched the search box, fuzzy-finder style.
+fn highlighted_text(
+    ui: &egui::Ui,
+    text: &str,
+    highlights: &[Range<usize>],
+    color: egui::Color32,
+) -> egui::WidgetText {
+    if highlights.is_empty() {
+        return egui::RichText::new(text).color(color).into();
+    }
+
+    let plain = egui::TextFormat::simple(egui::TextStyle::Body.resolve(ui.style()), color);
+    let highlight = egui::TextFormat {
+        background: egui::Color32::from_rgba_unmultiplied(255, 200, 0, 90),
+        ..plain.clone()
+    };
+    let mut job = egui::text::LayoutJob::default();
+    let mut position = 0;
+    for range in highlights {
+        if range.start < position || range.end > text.len() {
+            continue;
+        }
+        job.append(&text[position..range.start], 0.0, plain.clone());
+        job.append(&text[range.clone()], 0.0, highlight.clone());
+        position = range.end;
+    }
+    job.append(&text[position..], 0.0, plain);
+    job.into()
+}
+
+/// Timestamp of the earliest message, the start of the scrubber's axis
+///
+/// Both the Time column and the split view measure times from here, so a
+/// message shows the same time whatever is filtered out.
+fn capture_start(messages: &[ParsedMessage]) -> f64 {
+    messages
+        .iter()
+        .map(|m| m.timestamp)
+        .min_by(f64::total_cmp)
+        .unwrap_or_default()
+}
+
+/// Time since the start of the capture, as shown in the Time column
+fn format_relative_time(timestamp: f64, start: f64) -> String {
+    format!("+{:.3}s", timestamp - start)
+}
+
+/// Color for a message direction, matching the Dir column
+fn direction_color(direction: &str) -> egui::Color32 {
+    if direction == "Send" {
+        egui::Color32::from_rgb(100, 200, 255)
+    } else {
+        egui::Color32::from_rgb(100, 255, 150)
+    }
+}
+
+/// Filtered rows in capture order as (index into messages, is send, timestamp)
+fn split_rows(
+    messages: &[ParsedMessage],
+    filtered: &[(usize, usize, String, String, String)],
+) -> Vec<(usize, bool, f64)> {
+    let mut rows: Vec<(usize, bool, f64)> = filtered
+        .iter()
+        .map(|(idx, _, _, direction, _)| (*idx, direction == "Send", messages[*idx].timestamp))
+        .collect();
+    rows.sort_by(|a, b| a.2.total_cmp(&b.2).then(a.0.cmp(&b.0)));
+    rows
+}
+
+/// Sequence-diagram layout: sent messages on the left, received on the right
+///
+/// `rows` come from [`split_rows`], always in timestamp order, so a request and
+/// its response sit next to each other on opposite sides. `scroll_to` is a
+/// position in `rows`.
+fn show_split_table(
+    app: &mut PcapViewerApp,
+    ui: &mut egui::Ui,
+    rows: &[(usize, bool, f64)],
+    scroll_to: Option<usize>,
+) {
+    let start = capture_start(&app.messages);
+    let half = ((ui.available_width() - 80.0) / 2.0).max(100.0);
+    let dark_mode = ui.visuals().dark_mode;
+
+    ui.horizontal(|ui| {
+        ui.add_sized(
+            [half, 16.0],
+            egui::Label::new(
+                egui::RichText::new("Send")
+                    .strong()
+                    .color(direction_color("Send")),
+            ),
+        );
+        ui.add_sized(
+            [70.0, 16.0],
+            egui::Label::new(egui::RichText::new("Time").strong()),
+        );
+        ui.add_sized(
+            [half, 16.0],
+            egui::Label::new(
+                egui::RichText::new("Recv")
+                    .strong()
+                    .color(direction_color("Recv")),
+            ),
+        );
     });
     ui.separator();
 
-    show_messages_table(app, ui, is_mobile, &filtered, sort_field, sort_ascending);
+    let table = egui_extras::TableBuilder::new(ui)
+        .striped(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .column(Column::exact(half))
+        .column(Column::exact(70.0))
+        .column(Column::remainder())
+        .min_scrolled_height(0.0);
+    let table = match scroll_to {
+        Some(row) => table.scroll_to_row(row, None),
+        None => table,
+    };
+
+    table.body(|body| {
+        body.rows(20.0, rows.len(), |mut row| {
+            let (original_idx, is_send, timestamp) = rows[row.index()];
+            let message = &app.messages[original_idx];
+            let is_selected = app.selected_message == Some(original_idx);
+            let is_marked = app.marked_messages.contains(&original_idx);
+
+            let mut text = egui::text::LayoutJob::default();
+            let type_format = egui::TextFormat {
+                color: category_color(&message.message_type, dark_mode),
+                ..Default::default()
+            };
+            let arrow_format = egui::TextFormat {
+                color: direction_color(&message.direction),
+                ..Default::default()
+            };
+            let label = format!("#{} {}", message.id, message.message_type);
+            if is_send {
+                text.append(&label, 0.0, type_format);
+                text.append(" →", 0.0, arrow_format);
+            } else {
+                text.append("← ", 0.0, arrow_format);
+                text.append(&label, 0.0, type_format);
+            }
+
+            let mut clicked = false;
+            row.col(|ui| {
+                if is_send {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        clicked |=
+                            desktop_marked_cell(ui, is_selected, is_marked, text.clone()).clicked();
+                    });
+                }
+            });
+            row.col(|ui| {
+                ui.label(
+                    egui::RichText::new(format_relative_time(timestamp, start))
+                        .monospace()
+                        .weak(),
+                );
+            });
+            row.col(|ui| {
+                if !is_send {
+                    clicked |= desktop_marked_cell(ui, is_selected, is_marked, text).clicked();
+                }
+            });
+            if clicked {
+                app.selected_message = Some(original_idx);
+            }
+        });
+    });
+}
+
+/// Stable color for a message category, tuned for contrast in the current theme
+///
+/// The hue comes from an FNV-1a hash of the category name so the same category
+/// gets the same color across runs and captures.
+pub fn category_color(message_type: &str, dark_mode: bool) -> egui::Color32 {
+    let hash = MessageCategory::from_type_name(message_type)
+        .name()
+        .bytes()
+        .fold(0x811c_9dc5u32, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+        });
+    let hue = (hash % 360) as f32 / 360.0;
+
+    let (saturation, value) = if dark_mode {
+        (0.45, 0.95)
+    } else {
+        (0.85, 0.55)
+    };
+    egui::ecolor::Hsva::new(hue, saturation, value, 1.0).into()
+}
+
+/// Collapsible legend of the message categories in the current list
+fn show_category_legend(ui: &mut egui::Ui, filtered: &[(usize, usize, String, String, String)]) {
+    let categories: std::collections::BTreeSet<MessageCategory> = filtered
+        .iter()
+        .map(|(_, _, msg_type, _, _)| MessageCategory::from_type_name(msg_type))
+        .collect();
+    if categories.is_empty() {
+        return;
+    }
+
+    let dark_mode = ui.visuals().dark_mode;
+    egui::CollapsingHeader::new("Legend")
+        .id_salt("message_category_legend")
+        .default_open(false)
+        .show(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for category in categories {
+                    ui.label(
+                        egui::RichText::new(category.name())
+                            .color(category_color(category.name(), dark_mode)),
+                    );
+                }
+            });
+        });
+}
+
+/// Count of messages the decoder doesn't fully handle, with the top opcodes on hover
+fn show_unhandled_summary(ui: &mut egui::Ui, report: &[common::coverage::UnhandledOpcode]) {
+    if report.is_empty() {
+        return;
+    }
+
+    let total: usize = report.iter().map(|entry| entry.count).sum();
+    let mut details = String::from("Unhandled messages by opcode:");
+    for entry in report.iter().take(10) {
+        let opcode = match &entry.sub_type {
+            Some(sub_type) => format!("{}/{}", entry.opcode, sub_type),
+            None => entry.opcode.clone(),
+        };
+        details.push_str(&format!(
+            "\n{opcode} ({}) x{}\n  {}",
+            entry.message_type, entry.count, entry.sample
+        ));
+    }
+    if report.len() > 10 {
+        details.push_str(&format!("\n…and {} more", report.len() - 10));
+    }
+
+    ui.label(egui::RichText::new(format!("· {total} unhandled ({} opcodes)", report.len())).weak())
+        .on_hover_text(details);
+}
+
+/// A key that moves the selection in a list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListKey {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+}
+
+/// Read the list navigation key pressed this frame, if any
+fn pressed_list_key(input: &egui::InputState) -> Option<ListKey> {
+    [
+        (egui::Key::ArrowUp, ListKey::Up),
+        (egui::Key::ArrowDown, ListKey::Down),
+        (egui::Key::PageUp, ListKey::PageUp),
+        (egui::Key::PageDown, ListKey::PageDown),
+        (egui::Key::Home, ListKey::Home),
+        (egui::Key::End, ListKey::End),
+    ]
+    .into_iter()
+    .find(|(key, _)| input.key_pressed(*key))
+    .map(|(_, list_key)| list_key)
+}
+
+/// Compute the new row position after a navigation key, clamped to the list
+fn navigate_list(current: Option<usize>, len: usize, key: ListKey, page: usize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let last = len - 1;
+
+    let Some(current) = current else {
+        // Nothing selected in the filtered set yet: start from the matching end
+        return Some(match key {
+            ListKey::End => last,
+            _ => 0,
+        });
+    };
+
+    Some(match key {
+        ListKey::Up => current.saturating_sub(1),
+        ListKey::Down => (current + 1).min(last),
+        ListKey::PageUp => current.saturating_sub(page),
+        ListKey::PageDown => (current + page).min(last),
+        ListKey::Home => 0,
+        ListKey::End => last,
+    })
 }
 
 fn show_messages_table(
@@ -365,6 +692,7 @@ fn show_messages_table(
     filtered: &[(usize, usize, String, String, String)],
     sort_field: SortField,
     sort_ascending: bool,
+    scroll_to: Option<usize>,
 ) {
     let available_width = ui.available_width();
 
@@ -445,6 +773,10 @@ fn show_messages_table(
             .column(Column::auto())
             .column(Column::auto())
             .min_scrolled_height(0.0);
+        let table = match scroll_to {
+            Some(row) => table.scroll_to_row(row, None),
+            None => table,
+        };
 
         table.body(|body| {
             body.rows(20.0, filtered.len(), |mut row| {
@@ -468,20 +800,17 @@ fn show_messages_table(
                     } else {
                         msg_type.clone()
                     };
-                    if mobile_cell(ui, widths[1], false, is_selected, is_marked, display_type)
-                        .clicked()
-                    {
+                    let type_color = category_color(msg_type, ui.visuals().dark_mode);
+                    let highlights = type_highlights(&display_type, &app.search_query);
+                    let text = highlighted_text(ui, &display_type, &highlights, type_color);
+                    if mobile_cell(ui, widths[1], false, is_selected, is_marked, text).clicked() {
                         app.selected_message = Some(*original_idx);
                         app.show_detail_panel = true;
                     }
                 });
 
                 row.col(|ui| {
-                    let dir_color = if direction == "Send" {
-                        egui::Color32::from_rgb(100, 200, 255)
-                    } else {
-                        egui::Color32::from_rgb(100, 255, 150)
-                    };
+                    let dir_color = direction_color(direction);
                     let dir_text = if direction == "Send" { "S" } else { "R" };
                     if mobile_cell(
                         ui,
@@ -510,6 +839,16 @@ fn show_messages_table(
                     app.sort_ascending = true;
                 }
             }
+            if desktop_header_cell(ui, "Time", SortField::Time, sort_field, sort_ascending)
+                .clicked()
+            {
+                if sort_field == SortField::Time {
+                    app.sort_ascending = !app.sort_ascending;
+                } else {
+                    app.sort_field = SortField::Time;
+                    app.sort_ascending = true;
+                }
+            }
             if desktop_header_cell(ui, "Type", SortField::Type, sort_field, sort_ascending)
                 .clicked()
             {
@@ -543,16 +882,23 @@ fn show_messages_table(
         });
         ui.separator();
 
+        let start = capture_start(&app.messages);
+
         // Desktop table
         let table = egui_extras::TableBuilder::new(ui)
             .striped(true)
             .resizable(true)
             .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
             .column(Column::initial(60.0).range(40.0..=100.0))
-            .column(Column::initial(200.0).range(100.0..=(available_width - 200.0).max(100.0)))
+            .column(Column::initial(80.0).range(60.0..=120.0))
+            .column(Column::initial(200.0).range(100.0..=(available_width - 280.0).max(100.0)))
             .column(Column::initial(60.0).range(40.0..=100.0))
             .column(Column::initial(100.0).range(60.0..=150.0))
             .min_scrolled_height(0.0);
+        let table = match scroll_to {
+            Some(row) => table.scroll_to_row(row, None),
+            None => table,
+        };
 
         table.body(|body| {
             body.rows(20.0, filtered.len(), |mut row| {
@@ -568,19 +914,31 @@ fn show_messages_table(
                 });
 
                 row.col(|ui| {
-                    if desktop_marked_cell(ui, is_selected, is_marked, msg_type.to_string())
-                        .clicked()
+                    let timestamp = app.messages[*original_idx].timestamp;
+                    if desktop_marked_cell(
+                        ui,
+                        is_selected,
+                        is_marked,
+                        egui::RichText::new(format_relative_time(timestamp, start)).monospace(),
+                    )
+                    .on_hover_text(format!("{timestamp:.6}"))
+                    .clicked()
                     {
                         app.selected_message = Some(*original_idx);
                     }
                 });
 
                 row.col(|ui| {
-                    let dir_color = if direction == "Send" {
-                        egui::Color32::from_rgb(100, 200, 255)
-                    } else {
-                        egui::Color32::from_rgb(100, 255, 150)
-                    };
+                    let type_color = category_color(msg_type, ui.visuals().dark_mode);
+                    let highlights = type_highlights(msg_type, &app.search_query);
+                    let text = highlighted_text(ui, msg_type, &highlights, type_color);
+                    if desktop_marked_cell(ui, is_selected, is_marked, text).clicked() {
+                        app.selected_message = Some(*original_idx);
+                    }
+                });
+
+                row.col(|ui| {
+                    let dir_color = direction_color(direction);
                     if desktop_marked_cell(
                         ui,
                         is_selected,
@@ -629,7 +987,11 @@ pub fn show_packets_list(app: &mut PcapViewerApp, ui: &mut egui::Ui, is_mobile:
                 idx,
                 p.id,
                 p.header.sequence,
-                format!("{:?}", p.direction),
+                if p.ambiguous_direction {
+                    format!("{}?", p.direction)
+                } else {
+                    p.direction.clone()
+                },
                 p.header.flags.bits(),
                 p.header.size,
             )
@@ -643,6 +1005,10 @@ pub fn show_packets_list(app: &mut PcapViewerApp, ui: &mut egui::Ui, is_mobile:
             SortField::Direction => a.3.cmp(&b.3),
             // Packets don't have OpCode, fall back to Id
             SortField::OpCode => a.1.cmp(&b.1),
+            SortField::Time => app.packets[a.0]
+                .timestamp
+                .total_cmp(&app.packets[b.0].timestamp)
+                .then(a.1.cmp(&b.1)),
         };
         if sort_ascending { cmp } else { cmp.reverse() }
     });
@@ -652,7 +1018,64 @@ pub fn show_packets_list(app: &mut PcapViewerApp, ui: &mut egui::Ui, is_mobile:
     });
     ui.separator();
 
-    show_packets_table(app, ui, is_mobile, &filtered, sort_field, sort_ascending);
+    let completed = completed_fragments(
+        app.packets
+            .iter()
+            .filter_map(|p| Some((p.direction == "Send", p.fragment.as_ref()?))),
+    );
+    show_packets_table(
+        app,
+        ui,
+        is_mobile,
+        &filtered,
+        &completed,
+        sort_field,
+        sort_ascending,
+    );
+}
+
+/// Fragments, as (is send, sequence), that some packet completed
+fn completed_fragments<'a>(
+    fragments: impl IntoIterator<Item = (bool, &'a FragmentInfo)>,
+) -> HashSet<(bool, u32)> {
+    fragments
+        .into_iter()
+        .filter(|(_, f)| f.received >= f.count as usize)
+        .map(|(is_send, f)| (is_send, f.sequence))
+        .collect()
+}
+
+/// How far a packet's fragment got towards a complete message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FragmentProgress {
+    /// This packet carried the last missing chunk
+    Complete,
+    /// Later packets complete the fragment
+    Partial,
+    /// The fragment never completed, so its message is missing
+    Stalled,
+}
+
+fn fragment_progress(fragment: &FragmentInfo, completed_later: bool) -> FragmentProgress {
+    if fragment.received >= fragment.count as usize {
+        FragmentProgress::Complete
+    } else if completed_later {
+        FragmentProgress::Partial
+    } else {
+        FragmentProgress::Stalled
+    }
+}
+
+/// Frag column cell: received/count chunks, checked when complete and red when stalled
+fn fragment_cell(fragment: &FragmentInfo, progress: FragmentProgress) -> egui::RichText {
+    let text = format!("{}/{}", fragment.received, fragment.count);
+    match progress {
+        FragmentProgress::Complete => egui::RichText::new(format!("{text} \u{2714}")),
+        FragmentProgress::Partial => egui::RichText::new(text),
+        FragmentProgress::Stalled => {
+            egui::RichText::new(text).color(egui::Color32::from_rgb(230, 80, 80))
+        }
+    }
 }
 
 fn show_packets_table(
@@ -660,6 +1083,7 @@ fn show_packets_table(
     ui: &mut egui::Ui,
     is_mobile: bool,
     filtered: &[(usize, usize, u32, String, u32, u16)],
+    completed: &HashSet<(bool, u32)>,
     sort_field: SortField,
     sort_ascending: bool,
 ) {
@@ -799,15 +1223,16 @@ fn show_packets_table(
                 });
 
                 row.col(|ui| {
-                    let dir_color = if direction == "ClientToServer" {
+                    let dir_color = if direction.starts_with("Send") {
                         egui::Color32::from_rgb(100, 200, 255)
                     } else {
                         egui::Color32::from_rgb(100, 255, 150)
                     };
-                    let dir_text = if direction == "ClientToServer" {
-                        "C→S"
-                    } else {
-                        "S→C"
+                    let dir_text = match direction.as_str() {
+                        "Send" => "C→S",
+                        "Recv" => "S→C",
+                        "Send?" => "C→S?",
+                        _ => "S→C?",
                     };
                     if mobile_cell(
                         ui,
@@ -894,9 +1319,11 @@ fn show_packets_table(
                     app.sort_ascending = true;
                 }
             }
-            // Flags, Size are not sortable
+            // Flags, Size and Frag are not sortable
             ui.strong("Flags");
             ui.strong("Size");
+            ui.strong("Frag")
+                .on_hover_text("Fragment chunks received so far out of the total");
         });
         ui.separator();
 
@@ -910,6 +1337,7 @@ fn show_packets_table(
             .column(Column::initial(100.0).range(60.0..=150.0))
             .column(Column::initial(80.0).range(50.0..=120.0))
             .column(Column::initial(60.0).range(40.0..=100.0))
+            .column(Column::initial(70.0).range(50.0..=120.0))
             .min_scrolled_height(0.0);
 
         table.body(|body| {
@@ -932,7 +1360,7 @@ fn show_packets_table(
                 });
 
                 row.col(|ui| {
-                    let dir_color = if direction == "ClientToServer" {
+                    let dir_color = if direction.starts_with("Send") {
                         egui::Color32::from_rgb(100, 200, 255)
                     } else {
                         egui::Color32::from_rgb(100, 255, 150)
@@ -951,6 +1379,7 @@ fn show_packets_table(
 
                 row.col(|ui| {
                     if desktop_marked_cell(ui, is_selected, is_marked, format!("{flags:08X}"))
+                        .on_hover_text(app.packets[*original_idx].flag_names.join(" | "))
                         .clicked()
                     {
                         app.selected_packet = Some(*original_idx);
@@ -962,7 +1391,143 @@ fn show_packets_table(
                         app.selected_packet = Some(*original_idx);
                     }
                 });
+
+                row.col(|ui| {
+                    let Some(fragment) = &app.packets[*original_idx].fragment else {
+                        return;
+                    };
+                    let progress = fragment_progress(
+                        fragment,
+                        completed.contains(&(direction.starts_with("Send"), fragment.sequence)),
+                    );
+                    let response = desktop_marked_cell(
+                        ui,
+                        is_selected,
+                        is_marked,
+                        fragment_cell(fragment, progress),
+                    )
+                    .on_hover_text(format!(
+                        "Fragment {}: {} of {} chunks, {} bytes{}",
+                        fragment.sequence,
+                        fragment.received,
+                        fragment.count,
+                        fragment.length,
+                        if progress == FragmentProgress::Stalled {
+                            " (never completed)"
+                        } else {
+                            ""
+                        }
+                    ));
+                    if response.clicked() {
+                        app.selected_packet = Some(*original_idx);
+                    }
+                });
             });
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_rows_are_chronological() {
+        let message =
+            |id: usize, direction: &str, timestamp: f64| common::messages::ParsedMessage {
+                id,
+                direction: direction.to_string(),
+                timestamp,
+                ..Default::default()
+            };
+        let messages = vec![
+            message(0, "Send", 2.0),
+            message(1, "Recv", 1.0),
+            message(2, "Recv", 3.0),
+        ];
+        // Filtered rows sorted by something other than time
+        let filtered: Vec<_> = [2, 0, 1]
+            .into_iter()
+            .map(|idx: usize| {
+                let m = &messages[idx];
+                (
+                    idx,
+                    m.id,
+                    m.message_type.clone(),
+                    m.direction.clone(),
+                    m.opcode.clone(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            split_rows(&messages, &filtered),
+            vec![(1, false, 1.0), (0, true, 2.0), (2, false, 3.0)]
+        );
+    }
+
+    #[test]
+    fn test_fragment_progress() {
+        let fragment = |sequence: u32, received: usize| FragmentInfo {
+            data: String::new(),
+            count: 2,
+            received,
+            length: 0,
+            sequence,
+        };
+        let (first, last, stalled) = (fragment(5, 1), fragment(5, 2), fragment(6, 1));
+        let completed = completed_fragments([(false, &first), (false, &last), (false, &stalled)]);
+        assert_eq!(completed, [(false, 5)].into_iter().collect());
+
+        let progress =
+            |f: &FragmentInfo| fragment_progress(f, completed.contains(&(false, f.sequence)));
+        assert_eq!(progress(&first), FragmentProgress::Partial);
+        assert_eq!(progress(&last), FragmentProgress::Complete);
+        assert_eq!(progress(&stalled), FragmentProgress::Stalled);
+    }
+
+    #[test]
+    fn test_format_relative_time() {
+        assert_eq!(format_relative_time(1012.3454, 1000.0), "+12.345s");
+        assert_eq!(format_relative_time(5.0, 5.0), "+0.000s");
+    }
+
+    #[test]
+    fn test_category_color_is_stable_per_category() {
+        for dark_mode in [true, false] {
+            assert_eq!(
+                category_color("Magic_UpdateEnchantment", dark_mode),
+                category_color("Magic_DispelEnchantment", dark_mode)
+            );
+            assert_ne!(
+                category_color("Magic_UpdateEnchantment", dark_mode),
+                category_color("Combat_HandleAttackDoneEvent", dark_mode)
+            );
+        }
+    }
+
+    #[test]
+    fn test_navigate_empty_list() {
+        assert_eq!(navigate_list(None, 0, ListKey::Down, 10), None);
+        assert_eq!(navigate_list(Some(3), 0, ListKey::Up, 10), None);
+    }
+
+    #[test]
+    fn test_navigate_without_selection() {
+        assert_eq!(navigate_list(None, 5, ListKey::Down, 10), Some(0));
+        assert_eq!(navigate_list(None, 5, ListKey::Up, 10), Some(0));
+        assert_eq!(navigate_list(None, 5, ListKey::End, 10), Some(4));
+    }
+
+    #[test]
+    fn test_navigate_steps_and_clamps() {
+        assert_eq!(navigate_list(Some(2), 5, ListKey::Down, 10), Some(3));
+        assert_eq!(navigate_list(Some(4), 5, ListKey::Down, 10), Some(4));
+        assert_eq!(navigate_list(Some(0), 5, ListKey::Up, 10), Some(0));
+        assert_eq!(navigate_list(Some(2), 50, ListKey::PageDown, 10), Some(12));
+        assert_eq!(navigate_list(Some(45), 50, ListKey::PageDown, 10), Some(49));
+        assert_eq!(navigate_list(Some(5), 50, ListKey::PageUp, 10), Some(0));
+        assert_eq!(navigate_list(Some(25), 50, ListKey::Home, 10), Some(0));
+        assert_eq!(navigate_list(Some(25), 50, ListKey::End, 10), Some(49));
+    }
+}
@@ -23,7 +23,11 @@ pub fn show_weenie_panel(app: &mut PcapViewerApp, ui: &mut egui::Ui, is_mobile:
     // Filter input
     ui.horizontal(|ui| {
         ui.label("Filter:");
-        ui.text_edit_singleline(&mut app.search_query);
+        ui.text_edit_singleline(&mut app.search_query)
+            .on_hover_text(
+                "Object id or name, and/or property terms like Value>10000 or ItemType=MeleeWeapon; \
+                 all terms must match",
+            );
         if ui.button("Clear").clicked() {
             app.search_query.clear();
         }
@@ -32,25 +36,33 @@ pub fn show_weenie_panel(app: &mut PcapViewerApp, ui: &mut egui::Ui, is_mobile:
     ui.separator();
 
     // Filter weenies
-    let filter_lower = app.search_query.to_lowercase();
     let filtered_weenies: Vec<&common::weenie::Weenie> = weenies
         .iter()
-        .filter(|w| {
-            if filter_lower.is_empty() {
-                true
-            } else {
-                w.object_id.to_string().contains(&filter_lower)
-                    || w.name
-                        .as_ref()
-                        .map(|n| n.to_lowercase().contains(&filter_lower))
-                        .unwrap_or(false)
-            }
-        })
+        .filter(|w| weenie_matches_filter(w, &app.search_query))
         .collect();
 
     show_weenie_table(app, ui, is_mobile, &filtered_weenies);
 }
 
+/// Check a weenie against the filter box text
+///
+/// Each whitespace-separated term must match: property terms (`Name=Value`,
+/// `Name>Value`, `Name<Value`) are checked against the property tables, other
+/// terms against the object id and name.
+fn weenie_matches_filter(weenie: &common::weenie::Weenie, filter: &str) -> bool {
+    filter.split_whitespace().all(|term| {
+        if let Some(property) = common::weenie::PropertyFilter::parse(term) {
+            return property.matches(weenie);
+        }
+        let term = term.to_lowercase();
+        weenie.object_id.to_string().contains(&term)
+            || weenie
+                .name
+                .as_ref()
+                .is_some_and(|n| n.to_lowercase().contains(&term))
+    })
+}
+
 fn show_weenie_table(
     app: &mut PcapViewerApp,
     ui: &mut egui::Ui,
@@ -179,22 +191,14 @@ pub fn show_weenie_detail(app: &mut PcapViewerApp, ui: &mut egui::Ui) {
         .collect();
 
     // Filter weenies
-    let filter_lower = app.search_query.to_lowercase();
     let filtered_weenies: Vec<&common::weenie::Weenie> = weenies
         .iter()
-        .filter(|w| {
-            if filter_lower.is_empty() {
-                true
-            } else {
-                w.object_id.to_string().contains(&filter_lower)
-                    || w.name
-                        .as_ref()
-                        .map(|n| n.to_lowercase().contains(&filter_lower))
-                        .unwrap_or(false)
-            }
-        })
+        .filter(|w| weenie_matches_filter(w, &app.search_query))
         .collect();
 
+    // Object clicked in the container tree, selected once the detail view is drawn
+    let mut clicked_object: Option<u32> = None;
+
     if let Some(idx) = app.selected_weenie {
         if let Some(weenie) = filtered_weenies.get(idx) {
             ScrollArea::vertical().show(ui, |ui| {
@@ -230,10 +234,34 @@ pub fn show_weenie_detail(app: &mut PcapViewerApp, ui: &mut egui::Ui) {
                         ui.label("Message Count:");
                         ui.label(format!("{}", weenie.message_count));
                         ui.end_row();
+
+                        if let Some(parent_id) = weenie.parent_id() {
+                            ui.label("Held By:");
+                            weenie_link(
+                                ui,
+                                app.weenie_db.get(parent_id),
+                                parent_id,
+                                &mut clicked_object,
+                            );
+                            ui.end_row();
+                        }
                     });
 
                 ui.separator();
 
+                // Contained and wielded items
+                if app.weenie_db.has_children(weenie.object_id) {
+                    ui.heading("Contents:");
+                    show_contents_tree(
+                        ui,
+                        &app.weenie_db,
+                        weenie.object_id,
+                        0,
+                        &mut clicked_object,
+                    );
+                    ui.separator();
+                }
+
                 // Message IDs section with clickable links
                 if !weenie.message_ids.is_empty() {
                     ui.heading("Referenced in Messages:");
@@ -255,8 +283,12 @@ pub fn show_weenie_detail(app: &mut PcapViewerApp, ui: &mut egui::Ui) {
                 show_property_section(ui, "Bool Properties", &weenie.bool_properties);
                 show_property_section(ui, "Float Properties", &weenie.float_properties);
                 show_property_section(ui, "String Properties", &weenie.string_properties);
-                show_property_section(ui, "DataId Properties", &weenie.data_id_properties);
-                show_property_section(ui, "InstanceId Properties", &weenie.instance_id_properties);
+                show_id_property_section(ui, "DataId Properties", &weenie.data_id_properties);
+                show_id_property_section(
+                    ui,
+                    "InstanceId Properties",
+                    &weenie.instance_id_properties,
+                );
             });
         }
     } else {
@@ -264,6 +296,88 @@ pub fn show_weenie_detail(app: &mut PcapViewerApp, ui: &mut egui::Ui) {
             ui.label("Select a weenie to view details");
         });
     }
+
+    if let Some(object_id) = clicked_object {
+        select_weenie(app, object_id);
+    }
+}
+
+/// Deepest container nesting shown, guarding against cyclic Container ids
+const MAX_CONTENTS_DEPTH: usize = 16;
+
+/// Show the items contained in or wielded by an object as an expandable tree
+fn show_contents_tree(
+    ui: &mut egui::Ui,
+    db: &common::weenie::WeenieDatabase,
+    object_id: u32,
+    depth: usize,
+    clicked_object: &mut Option<u32>,
+) {
+    if depth >= MAX_CONTENTS_DEPTH {
+        return;
+    }
+
+    for child in db.children_of(object_id) {
+        let wielded = child.instance_id_properties.get("Wielder").copied() == Some(object_id);
+        let has_children = db.has_children(child.object_id);
+
+        if has_children {
+            let id = ui.make_persistent_id(("weenie_contents", child.object_id));
+            egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
+                .show_header(ui, |ui| {
+                    weenie_link(ui, Some(child), child.object_id, clicked_object);
+                    if wielded {
+                        ui.label(egui::RichText::new("(wielded)").weak());
+                    }
+                })
+                .body(|ui| {
+                    show_contents_tree(ui, db, child.object_id, depth + 1, clicked_object);
+                });
+        } else {
+            ui.horizontal(|ui| {
+                weenie_link(ui, Some(child), child.object_id, clicked_object);
+                if wielded {
+                    ui.label(egui::RichText::new("(wielded)").weak());
+                }
+            });
+        }
+    }
+}
+
+/// Link to a weenie by name, or a placeholder for an object that wasn't captured
+fn weenie_link(
+    ui: &mut egui::Ui,
+    weenie: Option<&common::weenie::Weenie>,
+    object_id: u32,
+    clicked_object: &mut Option<u32>,
+) {
+    let id = common::weenie::format_id(object_id);
+    match weenie {
+        Some(weenie) => {
+            let name = weenie.name.as_deref().unwrap_or("<unknown>");
+            if ui.link(format!("{name} ({id})")).clicked() {
+                *clicked_object = Some(object_id);
+            }
+        }
+        None => {
+            ui.label(egui::RichText::new(format!("{id} (not captured)")).weak());
+        }
+    }
+}
+
+/// Select a weenie by object ID, clearing the filter if it hides that weenie
+fn select_weenie(app: &mut PcapViewerApp, object_id: u32) {
+    let weenies = app.weenie_db.sorted_weenies();
+    let position = weenies
+        .iter()
+        .filter(|w| weenie_matches_filter(w, &app.search_query))
+        .position(|w| w.object_id == object_id);
+
+    if position.is_none() {
+        app.search_query.clear();
+    }
+    app.selected_weenie =
+        position.or_else(|| weenies.iter().position(|w| w.object_id == object_id));
 }
 
 fn show_property_section<K, V>(
@@ -295,3 +409,16 @@ fn show_property_section<K, V>(
         ui.separator();
     }
 }
+
+/// Show DataId/InstanceId properties with their values in hex
+fn show_id_property_section(
+    ui: &mut egui::Ui,
+    title: &str,
+    properties: &std::collections::HashMap<String, u32>,
+) {
+    let formatted: std::collections::HashMap<&String, String> = properties
+        .iter()
+        .map(|(key, value)| (key, common::weenie::format_id(*value)))
+        .collect();
+    show_property_section(ui, title, &formatted);
+}
@@ -0,0 +1,108 @@
+//! Appraisal profile flags sent with Item_SetAppraiseInfo
+//!
+//! The flags decide which property tables and profiles follow in the event, so
+//! listing them tells a reader what was sent rather than merely absent.
+
+use crate::messages::OrderedEventHeader;
+
+pub const INT_STATS: u32 = 0x0001;
+pub const BOOL_STATS: u32 = 0x0002;
+pub const FLOAT_STATS: u32 = 0x0004;
+pub const STRING_STATS: u32 = 0x0008;
+pub const SPELL_BOOK: u32 = 0x0010;
+pub const WEAPON_PROFILE: u32 = 0x0020;
+pub const HOOK_PROFILE: u32 = 0x0040;
+pub const ARMOR_PROFILE: u32 = 0x0080;
+pub const CREATURE_PROFILE: u32 = 0x0100;
+pub const ARMOR_ENCHANTMENT_BITFIELD: u32 = 0x0200;
+pub const RESIST_ENCHANTMENT_BITFIELD: u32 = 0x0400;
+pub const WEAPON_ENCHANTMENT_BITFIELD: u32 = 0x0800;
+pub const DID_STATS: u32 = 0x1000;
+pub const INT64_STATS: u32 = 0x2000;
+pub const ARMOR_LEVELS: u32 = 0x4000;
+
+/// Flag bits paired with their display names, in bit order
+const FLAG_NAMES: [(u32, &str); 15] = [
+    (INT_STATS, "Int"),
+    (BOOL_STATS, "Bool"),
+    (FLOAT_STATS, "Float"),
+    (STRING_STATS, "String"),
+    (SPELL_BOOK, "SpellBook"),
+    (WEAPON_PROFILE, "WeaponProfile"),
+    (HOOK_PROFILE, "HookProfile"),
+    (ARMOR_PROFILE, "ArmorProfile"),
+    (CREATURE_PROFILE, "CreatureProfile"),
+    (ARMOR_ENCHANTMENT_BITFIELD, "ArmorEnchantmentBitfield"),
+    (RESIST_ENCHANTMENT_BITFIELD, "ResistEnchantmentBitfield"),
+    (WEAPON_ENCHANTMENT_BITFIELD, "WeaponEnchantmentBitfield"),
+    (DID_STATS, "DataId"),
+    (INT64_STATS, "Int64"),
+    (ARMOR_LEVELS, "ArmorLevels"),
+];
+
+/// Names of the set flags; bits without a name are listed as hex
+pub fn appraisal_flag_names(flags: u32) -> Vec<String> {
+    let mut names: Vec<String> = FLAG_NAMES
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| name.to_string())
+        .collect();
+
+    let known = FLAG_NAMES.iter().fold(0, |acc, (bit, _)| acc | bit);
+    if flags & !known != 0 {
+        names.push(format!("0x{:08X}", flags & !known));
+    }
+    names
+}
+
+/// Add a `Flags` list to a decoded Item_SetAppraiseInfo event
+///
+/// The flags follow the appraised object id in the event payload.
+pub fn add_appraisal_flags(parsed: &mut serde_json::Value, raw_bytes: &[u8]) {
+    let offset = OrderedEventHeader::SIZE + 4;
+    let Some(bytes) = raw_bytes.get(offset..offset + 4) else {
+        return;
+    };
+    let flags = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+    if let Some(appraise_info) = parsed
+        .pointer_mut("/S2C/OrderedGameEvent/event/ItemSetAppraiseInfo")
+        .and_then(|v| v.as_object_mut())
+    {
+        appraise_info.insert("Flags".to_string(), appraisal_flag_names(flags).into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_flag_names() {
+        assert!(appraisal_flag_names(0).is_empty());
+        assert_eq!(
+            appraisal_flag_names(INT_STATS | WEAPON_PROFILE | 0x0001_0000),
+            vec!["Int", "WeaponProfile", "0x00010000"]
+        );
+    }
+
+    #[test]
+    fn test_adds_flags_to_appraise_info() {
+        let mut raw = Vec::new();
+        for value in [0xF7B0u32, 0x5000_0001, 7, 0x00C9, 0x8000_1234] {
+            raw.extend_from_slice(&value.to_le_bytes());
+        }
+        raw.extend_from_slice(&(INT_STATS | ARMOR_PROFILE).to_le_bytes());
+
+        let mut parsed = json!({"S2C": {"OrderedGameEvent": {"event": {
+            "ItemSetAppraiseInfo": {"ObjectId": 0x8000_1234u32}
+        }}}});
+        add_appraisal_flags(&mut parsed, &raw);
+
+        assert_eq!(
+            parsed["S2C"]["OrderedGameEvent"]["event"]["ItemSetAppraiseInfo"]["Flags"],
+            json!(["Int", "ArmorProfile"])
+        );
+    }
+}
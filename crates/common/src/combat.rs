@@ -0,0 +1,205 @@
+//! Aggregate combat notification events into a damage report
+//!
+//! The client only sees its own fights: attacker notifications are hits the
+//! player landed, defender notifications are hits the player took. Both are
+//! recorded with the player as "You".
+
+use crate::messages::ParsedMessage;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+/// Name used for the player on whichever side of the hit they were on
+pub const PLAYER: &str = "You";
+
+/// Damage dealt by one attacker to one target
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DamageSummary {
+    #[serde(rename = "Attacker")]
+    pub attacker: String,
+    #[serde(rename = "Target")]
+    pub target: String,
+    #[serde(rename = "TotalDamage")]
+    pub total_damage: u64,
+    #[serde(rename = "Hits")]
+    pub hits: usize,
+    #[serde(rename = "Crits")]
+    pub crits: usize,
+    /// Damage per damage type
+    #[serde(rename = "DamageTypes")]
+    pub damage_types: BTreeMap<String, u64>,
+}
+
+/// A single hit read from a combat notification
+struct Hit<'a> {
+    attacker: &'a str,
+    target: &'a str,
+    damage: u64,
+    critical: bool,
+    damage_type: String,
+}
+
+/// Summarize damage per attacker/target pair, optionally within a time window
+///
+/// Rows are ordered by total damage, highest first.
+pub fn combat_summary(
+    messages: &[ParsedMessage],
+    window: Option<RangeInclusive<f64>>,
+) -> Vec<DamageSummary> {
+    let mut summaries: BTreeMap<(String, String), DamageSummary> = BTreeMap::new();
+
+    for message in messages {
+        if let Some(window) = &window
+            && !window.contains(&message.timestamp)
+        {
+            continue;
+        }
+        let Some(hit) = read_hit(message) else {
+            continue;
+        };
+
+        let summary = summaries
+            .entry((hit.attacker.to_string(), hit.target.to_string()))
+            .or_insert_with(|| DamageSummary {
+                attacker: hit.attacker.to_string(),
+                target: hit.target.to_string(),
+                ..Default::default()
+            });
+        summary.total_damage += hit.damage;
+        summary.hits += 1;
+        if hit.critical {
+            summary.crits += 1;
+        }
+        *summary.damage_types.entry(hit.damage_type).or_default() += hit.damage;
+    }
+
+    let mut report: Vec<DamageSummary> = summaries.into_values().collect();
+    report.sort_by_key(|summary| std::cmp::Reverse(summary.total_damage));
+    report
+}
+
+/// Read a hit from an attacker or defender notification event
+fn read_hit(message: &ParsedMessage) -> Option<Hit<'_>> {
+    let (event_key, other_key, player_attacked) = match message.message_type.as_str() {
+        "Combat_HandleAttackerNotificationEvent" => (
+            "CombatHandleAttackerNotificationEvent",
+            "DefenderName",
+            true,
+        ),
+        "Combat_HandleDefenderNotificationEvent" => (
+            "CombatHandleDefenderNotificationEvent",
+            "AttackerName",
+            false,
+        ),
+        _ => return None,
+    };
+
+    let event_data = message
+        .data
+        .get("S2C")?
+        .get("OrderedGameEvent")?
+        .get("event")?
+        .get(event_key)?;
+
+    let other = event_data.get(other_key)?.as_str()?;
+    let (attacker, target) = if player_attacked {
+        (PLAYER, other)
+    } else {
+        (other, PLAYER)
+    };
+
+    let damage_type = match event_data.get("DamageType") {
+        Some(serde_json::Value::String(name)) => name.clone(),
+        Some(value) => value.to_string(),
+        None => "Unknown".to_string(),
+    };
+
+    Some(Hit {
+        attacker,
+        target,
+        damage: event_data.get("Damage")?.as_u64()?,
+        critical: event_data
+            .get("Critical")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        damage_type,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn notification(
+        timestamp: f64,
+        attacker: bool,
+        other: &str,
+        damage: u64,
+        critical: bool,
+    ) -> ParsedMessage {
+        let (message_type, event_key, name_key) = if attacker {
+            (
+                "Combat_HandleAttackerNotificationEvent",
+                "CombatHandleAttackerNotificationEvent",
+                "DefenderName",
+            )
+        } else {
+            (
+                "Combat_HandleDefenderNotificationEvent",
+                "CombatHandleDefenderNotificationEvent",
+                "AttackerName",
+            )
+        };
+        ParsedMessage {
+            message_type: message_type.to_string(),
+            data: json!({"S2C": {"OrderedGameEvent": {"event": {event_key: {
+                name_key: other,
+                "DamageType": if critical { "Fire" } else { "Slash" },
+                "Damage": damage,
+                "Critical": critical,
+            }}}}}),
+            direction: "Recv".to_string(),
+            opcode: "F7B0".to_string(),
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_summarizes_hits_per_pair() {
+        let messages = vec![
+            notification(1.0, true, "Drudge Skulker", 10, false),
+            notification(2.0, true, "Drudge Skulker", 25, true),
+            notification(3.0, false, "Drudge Skulker", 4, false),
+        ];
+
+        let report = combat_summary(&messages, None);
+        assert_eq!(report.len(), 2);
+
+        assert_eq!(report[0].attacker, PLAYER);
+        assert_eq!(report[0].target, "Drudge Skulker");
+        assert_eq!(report[0].total_damage, 35);
+        assert_eq!(report[0].hits, 2);
+        assert_eq!(report[0].crits, 1);
+        assert_eq!(report[0].damage_types.get("Slash"), Some(&10));
+        assert_eq!(report[0].damage_types.get("Fire"), Some(&25));
+
+        assert_eq!(report[1].attacker, "Drudge Skulker");
+        assert_eq!(report[1].target, PLAYER);
+        assert_eq!(report[1].total_damage, 4);
+    }
+
+    #[test]
+    fn test_time_window_excludes_other_hits() {
+        let messages = vec![
+            notification(1.0, true, "Drudge Skulker", 10, false),
+            notification(5.0, true, "Drudge Skulker", 20, false),
+        ];
+
+        let report = combat_summary(&messages, Some(4.0..=6.0));
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].total_damage, 20);
+        assert_eq!(report[0].hits, 1);
+    }
+}
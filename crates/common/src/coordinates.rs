@@ -0,0 +1,159 @@
+//! Convert landcell positions to in-game map coordinates
+//!
+//! Positions are a 32-bit landcell plus a local offset. The top byte of the
+//! landcell is the landblock column, the next byte its row, and the low 16 bits
+//! the cell inside the landblock. Each landblock is 192 meters and one map unit
+//! is 240 meters, with the map centered on 0,0.
+
+use serde_json::Value;
+
+/// Size of a landblock in meters
+const LANDBLOCK_SIZE: f64 = 192.0;
+
+/// Meters per map coordinate unit
+const METERS_PER_UNIT: f64 = 240.0;
+
+/// Map coordinate of the world's south-west corner
+const MAP_ORIGIN: f64 = -101.95;
+
+/// First cell index used for building and dungeon interiors
+const FIRST_INDOOR_CELL: u32 = 0x100;
+
+/// Field added to decoded positions
+pub const LOCATION_KEY: &str = "Location";
+
+/// Message types whose payload can hold a position
+///
+/// Only these are searched, so most messages skip the walk over their JSON.
+const POSITION_TYPES: &[&str] = &[
+    "Item_CreateObject",
+    "Item_UpdateObject",
+    "Movement_AutonomousPosition",
+    "Movement_Jump",
+    "Movement_MoveToState",
+    "Movement_PositionAndMovementEvent",
+    "Movement_PositionEvent",
+    "Movement_SetObjectMovement",
+    "Qualities_PrivateUpdatePosition",
+    "Qualities_UpdatePosition",
+];
+
+/// Whether messages of this type can carry a position to add a `Location` to
+pub fn carries_position(message_type: &str) -> bool {
+    POSITION_TYPES.contains(&message_type)
+}
+
+/// Map coordinates (north/south, east/west) for a landcell and local offset
+///
+/// Returns None for interior cells, whose offsets are not relative to the
+/// outdoor landblock.
+pub fn map_coordinates(landcell: u32, x: f64, y: f64) -> Option<(f64, f64)> {
+    if landcell & 0xFFFF >= FIRST_INDOOR_CELL {
+        return None;
+    }
+    let block_x = (landcell >> 24) as f64;
+    let block_y = ((landcell >> 16) & 0xFF) as f64;
+
+    let east = (block_x * LANDBLOCK_SIZE + x) / METERS_PER_UNIT + MAP_ORIGIN;
+    let north = (block_y * LANDBLOCK_SIZE + y) / METERS_PER_UNIT + MAP_ORIGIN;
+    Some((north, east))
+}
+
+/// In-game location string such as `42.1N, 33.6E`
+pub fn format_location(landcell: u32, x: f64, y: f64) -> Option<String> {
+    let (north, east) = map_coordinates(landcell, x, y)?;
+    let ns = if north < 0.0 { 'S' } else { 'N' };
+    let ew = if east < 0.0 { 'W' } else { 'E' };
+    Some(format!("{:.1}{ns}, {:.1}{ew}", north.abs(), east.abs()))
+}
+
+/// Add a `Location` string to every decoded position in a message
+///
+/// Callers check [`carries_position`] first; this walks the whole value.
+///
+/// Positions are objects with an `ObjCellId` or `Landcell` and an offset under
+/// `Frame.Origin` or `Origin`. Objects that already have a `Location` field are
+/// left alone.
+pub fn add_locations(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            if !map.contains_key(LOCATION_KEY)
+                && let Some(location) = position_location(map)
+            {
+                map.insert(LOCATION_KEY.to_string(), location.into());
+            }
+            for child in map.values_mut() {
+                add_locations(child);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(add_locations),
+        _ => {}
+    }
+}
+
+fn position_location(map: &serde_json::Map<String, Value>) -> Option<String> {
+    let landcell = map
+        .get("ObjCellId")
+        .or_else(|| map.get("Landcell"))?
+        .as_u64()? as u32;
+    let origin = map
+        .get("Frame")
+        .and_then(|frame| frame.get("Origin"))
+        .or_else(|| map.get("Origin"))?;
+
+    format_location(
+        landcell,
+        origin.get("X")?.as_f64()?,
+        origin.get("Y")?.as_f64()?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_location() {
+        // Holtburg
+        assert_eq!(
+            format_location(0xA9B4_001F, 84.0, 12.0).as_deref(),
+            Some("42.1N, 33.6E")
+        );
+        // South-west corner of the world
+        assert_eq!(
+            format_location(0x0000_0001, 0.0, 0.0).as_deref(),
+            Some("102.0S, 102.0W")
+        );
+        assert_eq!(format_location(0xA9B4_0150, 84.0, 12.0), None);
+    }
+
+    #[test]
+    fn test_add_locations_to_nested_positions() {
+        let mut data = json!({"S2C": {"MovementPositionEvent": {
+            "ObjectId": 0x5000_0001u32,
+            "Position": {
+                "ObjCellId": 0xA9B4_001Fu32,
+                "Frame": {"Origin": {"X": 84.0, "Y": 12.0, "Z": 94.0}},
+            },
+        }}});
+        add_locations(&mut data);
+
+        assert_eq!(
+            data["S2C"]["MovementPositionEvent"]["Position"]["Location"],
+            "42.1N, 33.6E"
+        );
+        assert!(
+            data["S2C"]["MovementPositionEvent"]
+                .get("Location")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_carries_position() {
+        assert!(carries_position("Movement_PositionEvent"));
+        assert!(carries_position("Item_CreateObject"));
+        assert!(!carries_position("Communication_Talk"));
+    }
+}
@@ -0,0 +1,216 @@
+//! Report on messages the decoder doesn't fully handle
+//!
+//! Helps prioritize which messages to implement next by grouping everything that
+//! fell through to a generic type or failed to decode by opcode.
+//! The opcode histogram counts all messages by raw opcode and sub-type for
+//! protocol research.
+//!
+//! Sub-types and samples come from the raw bytes. Messages parsed with
+//! `with_raw_bytes(false)` fall back on the payload hex kept in a decode
+//! error; messages that decoded have no such copy, so they are reported
+//! without a sub-type or sample.
+
+use crate::messages::{OrderedEventHeader, ParsedMessage};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+/// Maximum number of bytes kept in a sample payload
+const SAMPLE_LEN: usize = 64;
+
+/// An opcode (and game event/action type, where relevant) that isn't fully decoded
+#[derive(Debug, Clone, Serialize)]
+pub struct UnhandledOpcode {
+    #[serde(rename = "OpCode")]
+    pub opcode: String,
+    /// Game event or action type for OrderedGameEvent/OrderedGameAction messages
+    #[serde(rename = "SubType", skip_serializing_if = "Option::is_none")]
+    pub sub_type: Option<String>,
+    #[serde(rename = "Type")]
+    pub message_type: String,
+    #[serde(rename = "Count")]
+    pub count: usize,
+    /// Hex of the first message's raw bytes, truncated to 64 bytes
+    #[serde(rename = "Sample")]
+    pub sample: String,
+}
+
+/// Whether a message fell through to a generic type or failed to decode
+pub fn is_unhandled(message: &ParsedMessage) -> bool {
+    matches!(
+        message.message_type.as_str(),
+        "Unknown" | "OrderedGameEvent" | "OrderedGameAction"
+    ) || message.data.get("error").is_some()
+}
+
+/// Group unhandled messages by opcode and sub-type, most frequent first
+pub fn unhandled_report(messages: &[ParsedMessage]) -> Vec<UnhandledOpcode> {
+    let mut groups: HashMap<(String, Option<String>), UnhandledOpcode> = HashMap::new();
+
+    for message in messages.iter().filter(|m| is_unhandled(m)) {
+        let bytes = message_bytes(message);
+        let sub_type = sub_type(message, &bytes);
+        groups
+            .entry((message.opcode.clone(), sub_type.clone()))
+            .and_modify(|entry| entry.count += 1)
+            .or_insert_with(|| UnhandledOpcode {
+                opcode: message.opcode.clone(),
+                sub_type,
+                message_type: message.message_type.clone(),
+                count: 1,
+                sample: hex::encode(&bytes[..bytes.len().min(SAMPLE_LEN)]),
+            });
+    }
+
+    let mut report: Vec<UnhandledOpcode> = groups.into_values().collect();
+    report.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.opcode.cmp(&b.opcode))
+            .then_with(|| a.sub_type.cmp(&b.sub_type))
+    });
+    report
+}
+
+/// Count every message by raw opcode, splitting ordered messages by sub-type
+///
+/// Keys are `0xF745`, or `0xF7B0/0x00C9` for game events and actions, whether
+/// or not the message decoded.
+pub fn opcode_histogram(messages: &[ParsedMessage]) -> BTreeMap<String, usize> {
+    let mut histogram = BTreeMap::new();
+    for message in messages {
+        let key = match sub_type(message, &message_bytes(message)) {
+            Some(sub_type) => format!("0x{}/0x{sub_type}", message.opcode),
+            None => format!("0x{}", message.opcode),
+        };
+        *histogram.entry(key).or_insert(0) += 1;
+    }
+    histogram
+}
+
+/// Raw bytes of a message, rebuilt from its decode error when they were dropped
+///
+/// Empty when the message decoded and was parsed without raw bytes.
+fn message_bytes(message: &ParsedMessage) -> Cow<'_, [u8]> {
+    if !message.raw_bytes.is_empty() {
+        return Cow::Borrowed(&message.raw_bytes);
+    }
+    let rebuilt = || {
+        let opcode = u32::from_str_radix(&message.opcode, 16).ok()?;
+        let payload = hex::decode(message.data.get("raw_data")?.as_str()?).ok()?;
+        Some([opcode.to_le_bytes().as_slice(), &payload].concat())
+    };
+    Cow::Owned(rebuilt().unwrap_or_default())
+}
+
+/// Read the game event/action type that follows the opcode in ordered messages
+fn sub_type(message: &ParsedMessage, bytes: &[u8]) -> Option<String> {
+    let value = match message.opcode.as_str() {
+        "F7B0" => OrderedEventHeader::parse(bytes)?.event_type,
+        // OrderedGameAction: opcode, sequence, action type
+        "F7B1" => {
+            let bytes = bytes.get(8..12)?;
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        }
+        _ => return None,
+    };
+    Some(format!("{value:04X}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(message_type: &str, opcode: &str, raw_bytes: Vec<u8>) -> ParsedMessage {
+        ParsedMessage {
+            message_type: message_type.to_string(),
+            data: json!({}),
+            direction: "Recv".to_string(),
+            opcode: opcode.to_string(),
+            size: raw_bytes.len(),
+            raw_bytes,
+            ..Default::default()
+        }
+    }
+
+    fn ordered_event(event_type: u32) -> Vec<u8> {
+        [0xF7B0u32, 0x5000_0001, 1, event_type]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_groups_by_opcode_and_sub_type() {
+        let messages = vec![
+            message("OrderedGameEvent", "F7B0", ordered_event(0x0001)),
+            message("OrderedGameEvent", "F7B0", ordered_event(0x0002)),
+            message("OrderedGameEvent", "F7B0", ordered_event(0x0002)),
+            message("Item_SetAppraiseInfo", "F7B0", ordered_event(0x00C9)),
+        ];
+
+        let report = unhandled_report(&messages);
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].sub_type.as_deref(), Some("0002"));
+        assert_eq!(report[0].count, 2);
+        assert_eq!(report[1].sub_type.as_deref(), Some("0001"));
+        assert_eq!(report[1].count, 1);
+        assert_eq!(report[1].sample, hex::encode(ordered_event(0x0001)));
+    }
+
+    #[test]
+    fn test_opcode_histogram_splits_ordered_messages() {
+        let messages = vec![
+            message("Item_SetAppraiseInfo", "F7B0", ordered_event(0x00C9)),
+            message("Item_SetAppraiseInfo", "F7B0", ordered_event(0x00C9)),
+            message("OrderedGameEvent", "F7B0", ordered_event(0x0001)),
+            message("Movement_SetObjectMovement", "F74C", Vec::new()),
+        ];
+
+        let histogram = opcode_histogram(&messages);
+        assert_eq!(
+            serde_json::to_value(&histogram).unwrap(),
+            json!({"0xF7B0/0x00C9": 2, "0xF7B0/0x0001": 1, "0xF74C": 1})
+        );
+    }
+
+    #[test]
+    fn test_decode_errors_are_unhandled() {
+        let mut failed = message("Movement_SetObjectMovement", "F74C", vec![0x4C, 0xF7, 0, 0]);
+        failed.data = json!({"error": "unexpected end of input"});
+
+        let report = unhandled_report(&[failed]);
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].opcode, "F74C");
+        assert_eq!(report[0].sub_type, None);
+    }
+
+    #[test]
+    fn test_without_raw_bytes_falls_back_on_error_payload() {
+        let bytes = ordered_event(0x0001);
+        let mut failed = message("OrderedGameEvent", "F7B0", Vec::new());
+        failed.data = json!({
+            "error": "unknown event",
+            "opcode": "0xF7B0",
+            "raw_data": hex::encode(&bytes[4..]),
+        });
+        let decoded = message("Item_SetAppraiseInfo", "F7B0", Vec::new());
+
+        let report = unhandled_report(std::slice::from_ref(&failed));
+        assert_eq!(report[0].sub_type.as_deref(), Some("0001"));
+        assert_eq!(report[0].sample, hex::encode(&bytes));
+
+        // A decoded message keeps no copy of its bytes to read the sub-type from
+        assert_eq!(
+            serde_json::to_value(opcode_histogram(&[failed, decoded])).unwrap(),
+            json!({"0xF7B0/0x0001": 1, "0xF7B0": 1})
+        );
+    }
+
+    #[test]
+    fn test_sample_is_truncated() {
+        let report = unhandled_report(&[message("Unknown", "1234", vec![0xAB; 200])]);
+        assert_eq!(report[0].sample.len(), SAMPLE_LEN * 2);
+    }
+}
@@ -0,0 +1,233 @@
+//! Echo request/response header sections and round-trip latency
+//!
+//! The client stamps an EchoRequest with its local time; the server answers
+//! with an EchoResponse carrying that time back plus how long it held the
+//! request. Pairing the two packets gives the round-trip time seen at the
+//! capture point.
+
+use crate::ParsedPacket;
+use acprotocol::network::packet::PacketHeaderFlags;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Server reply to an echo request
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct EchoResponse {
+    /// Client time copied from the matching EchoRequest
+    #[serde(rename = "ClientTime")]
+    pub client_time: f32,
+    /// Seconds the server held the request before replying
+    #[serde(rename = "HoldingTime")]
+    pub holding_time: f32,
+}
+
+/// Echo sections read from a packet's optional header
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EchoHeaders {
+    pub request: Option<f32>,
+    pub response: Option<EchoResponse>,
+}
+
+/// Read the echo sections from the optional header following the base header
+///
+/// Optional sections appear in flag bit order, so every section before the
+/// echo ones is skipped by its size. Returns the default when the packet has
+/// no echo sections, is truncated, or carries a login request (which runs to
+/// the end of the packet).
+pub fn read_echo_headers(flags: PacketHeaderFlags, optional: &[u8]) -> EchoHeaders {
+    if !flags.intersects(PacketHeaderFlags::ECHO_REQUEST | PacketHeaderFlags::ECHO_RESPONSE) {
+        return EchoHeaders::default();
+    }
+    read_echo_sections(flags, optional).unwrap_or_default()
+}
+
+fn read_echo_sections(flags: PacketHeaderFlags, optional: &[u8]) -> Option<EchoHeaders> {
+    let read_u32 = |offset: usize| -> Option<u32> {
+        let bytes = optional.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+
+    if flags.contains(PacketHeaderFlags::LOGIN_REQUEST) {
+        return None;
+    }
+
+    let mut offset = 0;
+    if flags.contains(PacketHeaderFlags::SERVER_SWITCH) {
+        offset += 8;
+    }
+    for list in [
+        PacketHeaderFlags::REQUEST_RETRANSMIT,
+        PacketHeaderFlags::REJECT_RETRANSMIT,
+    ] {
+        if flags.contains(list) {
+            let count = read_u32(offset)? as usize;
+            offset += 4 + count.checked_mul(4)?;
+        }
+    }
+    for (flag, size) in [
+        (PacketHeaderFlags::ACK_SEQUENCE, 4),
+        (PacketHeaderFlags::WORLD_LOGIN_REQUEST, 8),
+        (PacketHeaderFlags::CONNECT_REQUEST, 32),
+        (PacketHeaderFlags::CONNECT_RESPONSE, 8),
+        (PacketHeaderFlags::NET_ERROR, 8),
+        (PacketHeaderFlags::NET_ERROR_DISCONNECT, 8),
+        (PacketHeaderFlags::CICMD_COMMAND, 8),
+        (PacketHeaderFlags::TIME_SYNC, 8),
+    ] {
+        if flags.contains(flag) {
+            offset += size;
+        }
+    }
+
+    let mut headers = EchoHeaders::default();
+    if flags.contains(PacketHeaderFlags::ECHO_REQUEST) {
+        headers.request = Some(f32::from_bits(read_u32(offset)?));
+        offset += 4;
+    }
+    if flags.contains(PacketHeaderFlags::ECHO_RESPONSE) {
+        headers.response = Some(EchoResponse {
+            client_time: f32::from_bits(read_u32(offset)?),
+            holding_time: f32::from_bits(read_u32(offset + 4)?),
+        });
+    }
+    Some(headers)
+}
+
+/// An echo request paired with its response
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoundTrip {
+    #[serde(rename = "RequestPacket")]
+    pub request_packet: usize,
+    #[serde(rename = "ResponsePacket")]
+    pub response_packet: usize,
+    /// Capture time between the request and the response, in seconds
+    #[serde(rename = "Rtt")]
+    pub rtt: f64,
+    /// Round-trip time minus the time the server held the request
+    #[serde(rename = "NetworkRtt")]
+    pub network_rtt: f64,
+}
+
+/// Pair echo requests with the responses that echo their client time
+///
+/// Responses without a preceding request in the capture are ignored.
+pub fn round_trips(packets: &[ParsedPacket]) -> Vec<RoundTrip> {
+    // Keyed by the bits of the client time, which the server echoes unchanged
+    let mut pending: HashMap<u32, &ParsedPacket> = HashMap::new();
+    let mut trips = Vec::new();
+
+    for packet in packets {
+        if let Some(client_time) = packet.echo_request {
+            pending.insert(client_time.to_bits(), packet);
+        }
+        if let Some(response) = packet.echo_response
+            && let Some(request) = pending.remove(&response.client_time.to_bits())
+        {
+            let rtt = packet.timestamp - request.timestamp;
+            trips.push(RoundTrip {
+                request_packet: request.id,
+                response_packet: packet.id,
+                rtt,
+                network_rtt: rtt - response.holding_time as f64,
+            });
+        }
+    }
+
+    trips
+}
+
+/// Distribution of round-trip times, in seconds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencyStats {
+    #[serde(rename = "Count")]
+    pub count: usize,
+    #[serde(rename = "Min")]
+    pub min: f64,
+    #[serde(rename = "Median")]
+    pub median: f64,
+    #[serde(rename = "P95")]
+    pub p95: f64,
+    #[serde(rename = "Max")]
+    pub max: f64,
+    #[serde(rename = "Mean")]
+    pub mean: f64,
+}
+
+impl LatencyStats {
+    /// Summarize the round-trip times, or None if there are none
+    pub fn from_round_trips(trips: &[RoundTrip]) -> Option<Self> {
+        let mut rtts: Vec<f64> = trips.iter().map(|trip| trip.rtt).collect();
+        if rtts.is_empty() {
+            return None;
+        }
+        rtts.sort_by(f64::total_cmp);
+
+        let percentile = |p: f64| rtts[((rtts.len() - 1) as f64 * p).round() as usize];
+        Some(Self {
+            count: rtts.len(),
+            min: rtts[0],
+            median: percentile(0.5),
+            p95: percentile(0.95),
+            max: rtts[rtts.len() - 1],
+            mean: rtts.iter().sum::<f64>() / rtts.len() as f64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn optional(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_reads_echo_request_after_other_sections() {
+        let flags = PacketHeaderFlags::ACK_SEQUENCE
+            | PacketHeaderFlags::TIME_SYNC
+            | PacketHeaderFlags::ECHO_REQUEST;
+        let bytes = optional(&[7, 0, 0, 12.5f32.to_bits()]);
+
+        let headers = read_echo_headers(flags, &bytes);
+        assert_eq!(headers.request, Some(12.5));
+        assert_eq!(headers.response, None);
+    }
+
+    #[test]
+    fn test_reads_echo_response_after_retransmit_list() {
+        let flags = PacketHeaderFlags::REQUEST_RETRANSMIT | PacketHeaderFlags::ECHO_RESPONSE;
+        let bytes = optional(&[2, 100, 101, 12.5f32.to_bits(), 0.25f32.to_bits()]);
+
+        let headers = read_echo_headers(flags, &bytes);
+        assert_eq!(
+            headers.response,
+            Some(EchoResponse {
+                client_time: 12.5,
+                holding_time: 0.25
+            })
+        );
+    }
+
+    #[test]
+    fn test_truncated_echo_is_ignored() {
+        let headers = read_echo_headers(PacketHeaderFlags::ECHO_RESPONSE, &[0, 0, 0x48, 0x41]);
+        assert_eq!(headers, EchoHeaders::default());
+    }
+
+    #[test]
+    fn test_latency_stats() {
+        let trip = |rtt| RoundTrip {
+            request_packet: 0,
+            response_packet: 1,
+            rtt,
+            network_rtt: rtt,
+        };
+        let stats = LatencyStats::from_round_trips(&[trip(0.3), trip(0.1), trip(0.2)]).unwrap();
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.min, 0.1);
+        assert_eq!(stats.median, 0.2);
+        assert_eq!(stats.max, 0.3);
+        assert!(LatencyStats::from_round_trips(&[]).is_none());
+    }
+}
@@ -0,0 +1,161 @@
+//! Write a parsed capture as JSON
+//!
+//! The whole capture goes out as `{"messages": [...], "packets": [...],
+//! "weenies": [...]}`, which web tools can load in one go, and messages alone as
+//! JSONL for line-oriented tools. Entries are serialized straight into the
+//! writer, so large captures are never held in memory as one string.
+//! [`OutputFile`] gzips the output when the path ends in `.gz`.
+
+use crate::ParsedPacket;
+use crate::messages::ParsedMessage;
+use crate::weenie::WeenieDatabase;
+use anyhow::{Context, Result};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::Serializer;
+use serde::ser::SerializeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// An export file, gzip-compressed when its name ends in `.gz`
+///
+/// Call [`finish`](Self::finish) when done so the gzip trailer is written and
+/// any error doing so is reported.
+pub enum OutputFile {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl OutputFile {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = BufWriter::new(
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?,
+        );
+        let gzip = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("gz"));
+        Ok(if gzip {
+            OutputFile::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            OutputFile::Plain(file)
+        })
+    }
+
+    /// Flush everything, finishing the gzip stream if there is one
+    pub fn finish(self) -> Result<()> {
+        match self {
+            OutputFile::Plain(mut file) => file.flush()?,
+            OutputFile::Gzip(encoder) => encoder.finish()?.flush()?,
+        }
+        Ok(())
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            OutputFile::Plain(file) => file.write(buf),
+            OutputFile::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            OutputFile::Plain(file) => file.flush(),
+            OutputFile::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Write each message to `writer` as one line of JSON
+pub fn write_messages_jsonl<W: Write>(writer: W, messages: &[ParsedMessage]) -> Result<()> {
+    let mut writer = BufWriter::new(writer);
+    for message in messages {
+        serde_json::to_writer(&mut writer, message)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write messages, packets and weenies to `writer` as one JSON object
+pub fn write_capture_json<W: Write>(
+    writer: W,
+    messages: &[ParsedMessage],
+    packets: &[ParsedPacket],
+    weenies: &WeenieDatabase,
+) -> Result<()> {
+    let mut writer = BufWriter::new(writer);
+    let mut serializer = serde_json::Serializer::new(&mut writer);
+
+    let mut map = serializer.serialize_map(Some(3))?;
+    map.serialize_entry("messages", messages)?;
+    map.serialize_entry("packets", packets)?;
+    map.serialize_entry("weenies", &weenies.sorted_weenies())?;
+    map.end()?;
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(id: usize) -> ParsedMessage {
+        ParsedMessage {
+            id,
+            message_type: "Movement_SetObjectMovement".to_string(),
+            data: json!({}),
+            direction: "Recv".to_string(),
+            opcode: "F74C".to_string(),
+            timestamp: 1.5,
+            packet_ids: vec![1],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_writes_single_object() {
+        let messages = vec![message(3)];
+
+        let mut out = Vec::new();
+        write_capture_json(&mut out, &messages, &[], &WeenieDatabase::new()).unwrap();
+
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(value["messages"][0]["Id"], 3);
+        assert_eq!(value["packets"], json!([]));
+        assert_eq!(value["weenies"], json!([]));
+    }
+
+    #[test]
+    fn test_gz_output_is_compressed_jsonl() {
+        let path = std::env::temp_dir().join(format!("export-{}.jsonl.gz", std::process::id()));
+        let mut output = OutputFile::create(&path).unwrap();
+        assert!(matches!(output, OutputFile::Gzip(_)));
+        write_messages_jsonl(&mut output, &[message(1), message(2)]).unwrap();
+        output.finish().unwrap();
+
+        let mut text = String::new();
+        std::io::Read::read_to_string(
+            &mut flate2::read::GzDecoder::new(File::open(&path).unwrap()),
+            &mut text,
+        )
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let ids: Vec<u64> = text
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<serde_json::Value>(line).unwrap()["Id"]
+                    .as_u64()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+}
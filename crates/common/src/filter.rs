@@ -1,4 +1,12 @@
-//! Rich filter types for flexible searching
+//! Message search and filtering shared by every front end
+//!
+//! The search box syntax (rich hex/decimal/text filters and `Key:value` field
+//! terms) and [`MessageFilter`], which combines it with type, opcode,
+//! direction, category, time and regex criteria.
+
+use crate::messages::{MessageCategory, ParsedMessage};
+use std::collections::BTreeSet;
+use std::ops::{Range, RangeInclusive};
 
 /// A single parsed filter that can match multiple representations
 #[derive(Debug, Clone, PartialEq)]
@@ -82,10 +90,307 @@ fn matches_filter(filter: &Filter, value: &str) -> bool {
     }
 }
 
+/// A filter scoped to one field, written `Key:value` (e.g. `ObjectId:0x50000123`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldFilter {
+    /// Field name, compared case-insensitively
+    pub key: std::string::String,
+    /// Filters the field's value must match exactly
+    pub filters: Vec<Filter>,
+    /// The value as a whole number outside the `u32` range of `filters`, such
+    /// as a negative amount or a 64-bit id; matched instead of `filters`
+    pub integer: Option<i128>,
+}
+
+/// Parse a `Key:value` search term, if the input has that shape
+///
+/// The key must be a plain identifier so free text containing a colon still
+/// falls back to an ordinary search.
+pub fn parse_field_filter(s: &str) -> Option<FieldFilter> {
+    let (key, value) = s.trim().split_once(':')?;
+    let value = value.trim();
+    if key.is_empty()
+        || value.is_empty()
+        || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return None;
+    }
+
+    Some(FieldFilter {
+        key: key.to_string(),
+        filters: parse_filter_string(value),
+        integer: parse_wide_integer(value),
+    })
+}
+
+/// Parse a decimal or `0x` hex whole number that doesn't fit in a `u32`
+fn parse_wide_integer(s: &str) -> Option<i128> {
+    let integer = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => i128::from(u64::from_str_radix(hex, 16).ok()?),
+        None => s
+            .parse::<i64>()
+            .map(i128::from)
+            .or_else(|_| s.parse::<u64>().map(i128::from))
+            .ok()?,
+    };
+    u32::try_from(integer).is_err().then_some(integer)
+}
+
+/// Build the `Key:value` term that `parse_field_filter` reads back
+pub fn field_filter_term(key: &str, value: &str) -> std::string::String {
+    format!("{key}:{value}")
+}
+
+/// Check whether any field named `field.key` anywhere in `value` matches exactly
+pub fn json_field_matches(value: &serde_json::Value, field: &FieldFilter) -> bool {
+    match value {
+        serde_json::Value::Object(map) => map.iter().any(|(key, v)| {
+            (key.eq_ignore_ascii_case(&field.key) && leaf_matches(field, v))
+                || json_field_matches(v, field)
+        }),
+        serde_json::Value::Array(items) => items.iter().any(|v| json_field_matches(v, field)),
+        _ => false,
+    }
+}
+
+/// Exact (not substring) match of a scalar JSON value against a field filter's value
+fn leaf_matches(field: &FieldFilter, value: &serde_json::Value) -> bool {
+    if let Some(integer) = field.integer {
+        return match value {
+            serde_json::Value::Number(n) => {
+                n.as_i64()
+                    .map(i128::from)
+                    .or_else(|| n.as_u64().map(i128::from))
+                    == Some(integer)
+            }
+            serde_json::Value::String(s) => s.parse::<i128>().ok() == Some(integer),
+            _ => false,
+        };
+    }
+
+    field.filters.iter().any(|filter| match (filter, value) {
+        (Filter::HexValue(num) | Filter::DecimalValue(num), serde_json::Value::Number(n)) => {
+            n.as_u64() == Some(*num as u64)
+        }
+        (Filter::HexValue(num), serde_json::Value::String(s)) => {
+            u32::from_str_radix(s, 16).ok() == Some(*num)
+        }
+        (Filter::DecimalValue(num), serde_json::Value::String(s)) => {
+            s.parse::<u32>().ok() == Some(*num)
+        }
+        (Filter::StringValue(text), serde_json::Value::String(s)) => s.eq_ignore_ascii_case(text),
+        (Filter::StringValue(text), serde_json::Value::Bool(b)) => b.to_string() == *text,
+        _ => false,
+    })
+}
+
+/// Check whether a message matches the (lowercased) search box text
+///
+/// A `Key:value` term matches that field in the message data or one of the
+/// top-level Id/Type/OpCode/Direction columns; anything else is matched as
+/// rich filters against the columns and a text search of the data.
+pub fn message_matches_search(m: &ParsedMessage, search: &str) -> bool {
+    if let Some(field) = parse_field_filter(search) {
+        let column = match field.key.to_ascii_lowercase().as_str() {
+            "id" => Some(serde_json::Value::from(m.id)),
+            "type" => Some(serde_json::Value::from(m.message_type.as_str())),
+            "opcode" => Some(serde_json::Value::from(m.opcode.as_str())),
+            "direction" => Some(serde_json::Value::from(m.direction.as_str())),
+            _ => None,
+        };
+        return column.is_some_and(|v| leaf_matches(&field, &v))
+            || json_field_matches(&m.data, &field);
+    }
+
+    // Parse search string into rich filters (supports hex, decimal, and text)
+    let filters = parse_filter_string(search);
+
+    matches_any_filter(&filters, &m.id.to_string())
+        || matches_any_filter(&filters, &m.opcode)
+        || matches_any_filter(&filters, &m.direction)
+        || matches_any_filter(
+            &filters,
+            &serde_json::to_string(&m.data).unwrap_or_default(),
+        )
+        // Always also do text search (type and data)
+        || m.message_type.to_lowercase().contains(search)
+        || json_contains_string(&m.data, search)
+}
+
+/// Byte ranges of a message type that the search box text matched
+///
+/// A plain term highlights every case-insensitive occurrence, and a
+/// `Type:value` term the whole type when it names it. Other field terms and
+/// matches found only in the data highlight nothing.
+pub fn type_highlights(message_type: &str, search: &str) -> Vec<Range<usize>> {
+    if let Some(field) = parse_field_filter(search) {
+        let names_type = field.key.eq_ignore_ascii_case("type")
+            && leaf_matches(&field, &serde_json::Value::from(message_type));
+        return names_type
+            .then_some(0..message_type.len())
+            .into_iter()
+            .collect();
+    }
+
+    let needle = search.to_ascii_lowercase();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    // ASCII lowercasing keeps byte offsets valid in the original string
+    let haystack = message_type.to_ascii_lowercase();
+    haystack
+        .match_indices(&needle)
+        .map(|(start, matched)| start..start + matched.len())
+        .collect()
+}
+
+/// Recursively search for a string within a JSON value (case-insensitive)
+/// Searches in both field names and values, including numeric values
+pub fn json_contains_string(value: &serde_json::Value, search: &str) -> bool {
+    let search_lower = search.to_lowercase();
+
+    match value {
+        serde_json::Value::String(s) => s.to_lowercase().contains(&search_lower),
+        serde_json::Value::Number(n) => {
+            // Check if the search string matches the numeric value as a string
+            n.to_string().contains(&search_lower) ||
+            // Also check if search is a number and matches exactly
+            if let Ok(search_num) = search.parse::<i64>() {
+                n.as_i64() == Some(search_num)
+            } else if let Ok(search_num) = search.parse::<u64>() {
+                n.as_u64() == Some(search_num)
+            } else if let Ok(search_num) = search.parse::<f64>() {
+                n.as_f64() == Some(search_num)
+            } else {
+                false
+            }
+        }
+        serde_json::Value::Array(arr) => arr.iter().any(|v| json_contains_string(v, search)),
+        serde_json::Value::Object(obj) => {
+            // Search in both keys and values
+            obj.keys().any(|k| k.to_lowercase().contains(&search_lower))
+                || obj.values().any(|v| json_contains_string(v, search))
+        }
+        _ => false,
+    }
+}
+
+/// Criteria a message must meet to be listed
+///
+/// Every criterion that is set must hold; unset criteria match anything, so
+/// the default filter matches every message.
+#[derive(Debug, Clone, Default)]
+pub struct MessageFilter {
+    /// Lowercased search box text
+    search: String,
+    /// Lowercased substring of the message type
+    type_contains: Option<String>,
+    /// Uppercased opcodes; empty matches any opcode
+    opcodes: BTreeSet<String>,
+    direction: Option<String>,
+    category: Option<MessageCategory>,
+    time_range: Option<RangeInclusive<f64>>,
+    data_regex: Option<regex::Regex>,
+}
+
+impl MessageFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match the search box syntax, as [`message_matches_search`] does
+    pub fn with_search(mut self, search: &str) -> Self {
+        self.search = search.to_lowercase();
+        self
+    }
+
+    /// Require the message type to contain `text` (case-insensitive)
+    pub fn with_type(mut self, text: &str) -> Self {
+        self.type_contains = Some(text.to_lowercase());
+        self
+    }
+
+    /// Require one of the given opcodes, written as in the list (e.g. `F7B0`)
+    pub fn with_opcodes<'a>(mut self, opcodes: impl IntoIterator<Item = &'a str>) -> Self {
+        self.opcodes = opcodes
+            .into_iter()
+            .map(|opcode| {
+                opcode
+                    .strip_prefix("0x")
+                    .or_else(|| opcode.strip_prefix("0X"))
+                    .unwrap_or(opcode)
+                    .to_uppercase()
+            })
+            .collect();
+        self
+    }
+
+    /// Require `Send` or `Recv` (case-insensitive)
+    pub fn with_direction(mut self, direction: &str) -> Self {
+        self.direction = Some(direction.to_string());
+        self
+    }
+
+    pub fn with_category(mut self, category: Option<MessageCategory>) -> Self {
+        self.category = category;
+        self
+    }
+
+    /// Require the timestamp to fall in `range` (inclusive)
+    pub fn with_time_range(mut self, range: Option<RangeInclusive<f64>>) -> Self {
+        self.time_range = range;
+        self
+    }
+
+    /// Require `pattern` to match the message data serialized as JSON
+    pub fn with_data_regex(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.data_regex = Some(regex::Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Whether the message meets every criterion
+    pub fn matches(&self, m: &ParsedMessage) -> bool {
+        (self.search.is_empty() || message_matches_search(m, &self.search))
+            && self
+                .type_contains
+                .as_ref()
+                .is_none_or(|text| m.message_type.to_lowercase().contains(text))
+            && (self.opcodes.is_empty() || self.opcodes.contains(&m.opcode))
+            && self
+                .direction
+                .as_ref()
+                .is_none_or(|direction| m.direction.eq_ignore_ascii_case(direction))
+            && self
+                .category
+                .is_none_or(|category| m.category() == category)
+            && self
+                .time_range
+                .as_ref()
+                .is_none_or(|range| range.contains(&m.timestamp))
+            && self.data_regex.as_ref().is_none_or(|regex| {
+                regex.is_match(&serde_json::to_string(&m.data).unwrap_or_default())
+            })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_type_highlights() {
+        assert_eq!(
+            type_highlights("Magic_UpdateMagicEnchantment", "MAGIC"),
+            vec![0..5, 12..17]
+        );
+        let whole = type_highlights("Magic_UpdateEnchantment", "type:magic_updateenchantment");
+        assert_eq!(whole.first(), Some(&(0..23)));
+        assert_eq!(whole.len(), 1);
+        assert!(type_highlights("Magic_UpdateEnchantment", "type:Magic").is_empty());
+        assert!(type_highlights("Magic_UpdateEnchantment", "objectid:5").is_empty());
+        assert!(type_highlights("Magic_UpdateEnchantment", "").is_empty());
+    }
+
     #[test]
     fn test_parse_string_filter() {
         let filters = parse_filter_string("apple");
@@ -482,4 +787,133 @@ mod tests {
         // Should match via DecimalValue filter matching the "63408" in data
         assert!(matches_any_filter(&filters, json_data));
     }
+
+    #[test]
+    fn test_parse_field_filter() {
+        let field = parse_field_filter("ObjectId:0x50000123").unwrap();
+        assert_eq!(field.key, "ObjectId");
+        assert!(field.filters.contains(&Filter::HexValue(0x5000_0123)));
+
+        assert_eq!(
+            parse_field_filter(&field_filter_term("Name", "Drudge Skulker"))
+                .unwrap()
+                .filters,
+            vec![Filter::StringValue("drudge skulker".to_string())]
+        );
+
+        // Not field-scoped: no key, no value, or a key that isn't an identifier
+        assert!(parse_field_filter(":5").is_none());
+        assert!(parse_field_filter("ObjectId:").is_none());
+        assert!(parse_field_filter("see http://example").is_none());
+        assert!(parse_field_filter("0xF7B0").is_none());
+    }
+
+    #[test]
+    fn test_field_filter_matches_only_that_field() {
+        let data = serde_json::json!({
+            "ObjectId": 0x5000_0123u32,
+            "Items": [{"ContainerId": 0x5000_0456u32, "Name": "Pantaloons"}],
+        });
+
+        let matches = |term: &str| json_field_matches(&data, &parse_field_filter(term).unwrap());
+        assert!(matches("objectid:0x50000123"));
+        assert!(matches(&format!("ObjectId:{}", 0x5000_0123u32)));
+        assert!(matches("name:pantaloons"));
+        assert!(matches("ContainerId:0x50000456"));
+
+        // Values in other fields, or partial values, don't match
+        assert!(!matches("ObjectId:0x50000456"));
+        assert!(!matches("Name:pant"));
+    }
+
+    #[test]
+    fn test_field_filter_matches_negative_and_64_bit_values() {
+        let data = serde_json::json!({
+            "Amount": -5,
+            "Guid": 0x1_0000_0005u64,
+            "Max": u64::MAX,
+        });
+
+        let matches = |term: &str| json_field_matches(&data, &parse_field_filter(term).unwrap());
+        assert!(matches("Amount:-5"));
+        assert!(!matches("Amount:5"));
+        assert!(matches(&format!("Guid:{}", 0x1_0000_0005u64)));
+        assert!(matches("Guid:0x100000005"));
+        assert!(!matches("Guid:5"));
+        assert!(matches(&format!("Max:{}", u64::MAX)));
+        assert!(matches("Max:0xFFFFFFFFFFFFFFFF"));
+        assert!(!matches("Max:-1"));
+    }
+
+    fn message(message_type: &str, opcode: &str, direction: &str, timestamp: f64) -> ParsedMessage {
+        ParsedMessage {
+            id: 7,
+            message_type: message_type.to_string(),
+            data: serde_json::json!({"ObjectId": 0x5000_0123u32, "Name": "Drudge Skulker"}),
+            direction: direction.to_string(),
+            opcode: opcode.to_string(),
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_message_filter_default_matches_everything() {
+        assert!(MessageFilter::new().matches(&message("Unknown", "DEAD", "Recv", 0.0)));
+    }
+
+    #[test]
+    fn test_message_filter_criteria() {
+        let m = message("Item_SetAppraiseInfo", "F7B0", "Recv", 10.0);
+        let matches = |filter: MessageFilter| filter.matches(&m);
+
+        assert!(matches(MessageFilter::new().with_search("SKULKER")));
+        assert!(matches(
+            MessageFilter::new().with_search("objectid:0x50000123")
+        ));
+        assert!(!matches(MessageFilter::new().with_search("pantaloons")));
+
+        assert!(matches(MessageFilter::new().with_type("appraise")));
+        assert!(!matches(MessageFilter::new().with_type("movement")));
+
+        assert!(matches(
+            MessageFilter::new().with_opcodes(["f745", "0xF7B0"])
+        ));
+        assert!(!matches(MessageFilter::new().with_opcodes(["F745"])));
+        assert!(matches(MessageFilter::new().with_opcodes(["0XF7B0"])));
+        assert!(!matches(MessageFilter::new().with_opcodes(["0x0xF7B0"])));
+
+        assert!(matches(MessageFilter::new().with_direction("recv")));
+        assert!(!matches(MessageFilter::new().with_direction("Send")));
+
+        assert!(matches(
+            MessageFilter::new().with_category(Some(m.category()))
+        ));
+        assert!(matches(MessageFilter::new().with_category(None)));
+
+        assert!(matches(
+            MessageFilter::new().with_time_range(Some(10.0..=20.0))
+        ));
+        assert!(!matches(
+            MessageFilter::new().with_time_range(Some(10.5..=20.0))
+        ));
+
+        assert!(matches(
+            MessageFilter::new().with_data_regex(r"Drudge \w+").unwrap()
+        ));
+        assert!(!matches(
+            MessageFilter::new().with_data_regex("^Drudge").unwrap()
+        ));
+        assert!(MessageFilter::new().with_data_regex("(").is_err());
+    }
+
+    #[test]
+    fn test_message_filter_requires_all_criteria() {
+        let m = message("Item_SetAppraiseInfo", "F7B0", "Recv", 10.0);
+        let filter = MessageFilter::new()
+            .with_search("skulker")
+            .with_direction("Send");
+        assert!(!filter.matches(&m));
+        assert!(filter.with_direction("Recv").matches(&m));
+    }
 }
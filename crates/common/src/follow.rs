@@ -0,0 +1,116 @@
+//! Incremental parsing of a PCAP file that is still being written
+//!
+//! A capture tool appending to a file may stop mid-record, so each read keeps
+//! the trailing partial record until the rest arrives. The parser and its
+//! fragment state live as long as the follower, so a message whose fragments
+//! land in different appends is still reassembled.
+
+use crate::{PacketParser, ParsedPacket, messages::ParsedMessage};
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Size of the PCAP global header
+const PCAP_HEADER_LEN: usize = 24;
+
+/// Parses a growing PCAP capture as new bytes arrive
+pub struct PcapFollower {
+    parser: PacketParser,
+    /// Global header, kept to prefix the records of every later read
+    header: Vec<u8>,
+    /// Bytes of a record that hasn't been fully written yet
+    pending: Vec<u8>,
+    /// Bytes of the file read so far
+    offset: u64,
+    next_packet_id: usize,
+    next_message_id: usize,
+}
+
+impl Default for PcapFollower {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PcapFollower {
+    pub fn new() -> Self {
+        Self::with_parser(PacketParser::new())
+    }
+
+    /// Follow with `parser`, keeping its server ports and other settings
+    pub fn with_parser(parser: PacketParser) -> Self {
+        Self {
+            parser: parser.with_quiet(true),
+            header: Vec::new(),
+            pending: Vec::new(),
+            offset: 0,
+            next_packet_id: 0,
+            next_message_id: 0,
+        }
+    }
+
+    /// Bytes of the file read so far
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Forget everything read so far, as when the file was truncated or rotated
+    pub fn reset(&mut self) {
+        let mut parser = std::mem::take(&mut self.parser);
+        parser.reassembler.clear();
+        *self = Self::with_parser(parser);
+    }
+
+    /// Parse bytes appended to the capture, returning the packets and messages
+    /// that are now complete
+    pub fn feed(&mut self, data: &[u8]) -> Result<(Vec<ParsedPacket>, Vec<ParsedMessage>)> {
+        self.offset += data.len() as u64;
+        self.pending.extend_from_slice(data);
+
+        if self.header.is_empty() {
+            if self.pending.len() < PCAP_HEADER_LEN {
+                return Ok((Vec::new(), Vec::new()));
+            }
+            self.header = self.pending.drain(..PCAP_HEADER_LEN).collect();
+        }
+
+        let buffer = [self.header.as_slice(), self.pending.as_slice()].concat();
+        let (packets, messages, consumed) = self.parser.parse_records(
+            &buffer,
+            &mut self.next_packet_id,
+            &mut self.next_message_id,
+            &mut |_, _| {},
+        )?;
+        let records_len = consumed.saturating_sub(PCAP_HEADER_LEN);
+        self.pending.drain(..records_len.min(self.pending.len()));
+
+        Ok((packets, messages))
+    }
+
+    /// Read and parse whatever was appended to `path` since the last call
+    ///
+    /// Starts over from the beginning when the file got smaller than what was
+    /// already read, which is how truncation and rotation show up.
+    pub fn poll_file(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<(Vec<ParsedPacket>, Vec<ParsedMessage>)> {
+        let path = path.as_ref();
+        let mut file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            self.reset();
+        }
+        if len == self.offset {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut appended = Vec::new();
+        file.read_to_end(&mut appended)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        self.feed(&appended)
+    }
+}
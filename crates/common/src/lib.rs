@@ -5,85 +5,37 @@
 
 use acprotocol::network::packet::PacketHeader;
 pub use acprotocol::network::packet::PacketHeaderFlags;
-use acprotocol::network::reader::BinaryReader;
-use acprotocol::unified::Direction;
+pub use acprotocol::unified::Direction;
 use anyhow::{Context, Result};
-use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+pub use pcap_parser::Linktype;
 use pcap_parser::traits::PcapReaderIterator;
 use pcap_parser::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::ops::RangeInclusive;
 
+pub use reassembly::FragmentReassembler;
+
+pub mod appraisal_flags;
+pub mod combat;
+pub mod coordinates;
+pub mod coverage;
+pub mod echo;
+pub mod export;
+pub mod filter;
+pub mod follow;
 pub mod messages;
 pub mod packet_flags;
+pub mod reassembly;
+pub mod schema;
 pub mod serialization;
+pub mod summary;
 pub mod tree;
 pub mod weenie;
 pub mod weenie_extractor;
 
-// Fragment and FragmentHeader structures for managing packet fragments
-#[derive(Clone, Debug)]
-struct Fragment {
-    header: FragmentHeader,
-    data: Vec<u8>,
-    length: usize,
-    received: usize,
-}
-
-#[derive(Clone, Debug)]
-struct FragmentHeader {
-    #[allow(dead_code)]
-    sequence: u32,
-    #[allow(dead_code)]
-    id: u32,
-    count: u16,
-    #[allow(dead_code)]
-    size: u16,
-    #[allow(dead_code)]
-    index: u16,
-    #[allow(dead_code)]
-    group: Option<u16>,
-}
-
-impl Fragment {
-    fn new(sequence: u32, count: u16) -> Self {
-        const CHUNK_SIZE: usize = 448;
-        Self {
-            header: FragmentHeader {
-                sequence,
-                id: 0,
-                count,
-                size: 0,
-                index: 0,
-                group: None,
-            },
-            data: vec![0; count as usize * CHUNK_SIZE],
-            length: 0,
-            received: 0,
-        }
-    }
-
-    fn add_chunk(&mut self, data: &[u8], index: usize) {
-        const CHUNK_SIZE: usize = 448;
-        let start = index * CHUNK_SIZE;
-        let end = start + data.len();
-        if end <= self.data.len() {
-            self.data[start..end].copy_from_slice(data);
-            if end > self.length {
-                self.length = end;
-            }
-            if index >= self.received {
-                self.received = index + 1;
-            }
-        }
-    }
-
-    fn is_complete(&self) -> bool {
-        self.received >= self.header.count as usize
-    }
-}
-
 // Re-export properties from acprotocol via protocol module
 
 /// UI tab selection
@@ -91,11 +43,12 @@ impl Fragment {
 pub enum Tab {
     #[default]
     Messages,
+    Packets,
     Weenies,
 }
 
 /// UI view mode
-#[derive(Default, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum ViewMode {
     #[default]
     Tree,
@@ -104,20 +57,22 @@ pub enum ViewMode {
 }
 
 /// Sort field options
-#[derive(Default, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum SortField {
     #[default]
     Id,
     Type,
     Direction,
     OpCode,
+    Time,
 }
 
 /// Fragment info as stored in packets
 #[derive(Debug, Clone, Serialize)]
 pub struct FragmentInfo {
+    /// Base64 of the reassembled message, empty until its last chunk arrives
     #[serde(rename = "Data")]
-    pub data: String, // Base64 encoded
+    pub data: String,
     #[serde(rename = "Count")]
     pub count: u16,
     #[serde(rename = "Received")]
@@ -133,6 +88,9 @@ pub struct FragmentInfo {
 pub struct ParsedPacket {
     #[serde(rename = "Header")]
     pub header: PacketHeader,
+    /// Names of the flags set in `header.flags`, which serializes as a raw bitfield
+    #[serde(rename = "FlagNames")]
+    pub flag_names: Vec<&'static str>,
     #[serde(rename = "Direction")]
     pub direction: String,
     #[serde(rename = "Messages")]
@@ -143,22 +101,330 @@ pub struct ParsedPacket {
     pub id: usize,
     #[serde(rename = "Timestamp")]
     pub timestamp: f64, // Seconds since epoch (with microsecond precision)
+    /// Set when neither or both UDP ports were server ports, so the direction is a guess
+    #[serde(
+        rename = "AmbiguousDirection",
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub ambiguous_direction: bool,
+    /// Client local time from an EchoRequest header section
+    #[serde(rename = "EchoRequest", skip_serializing_if = "Option::is_none")]
+    pub echo_request: Option<f32>,
+    #[serde(rename = "EchoResponse", skip_serializing_if = "Option::is_none")]
+    pub echo_response: Option<echo::EchoResponse>,
     #[serde(skip)]
     pub raw_payload: Vec<u8>,
 }
 
+/// Length of the link-layer header preceding the IP header, if the link type is supported
+fn link_header_len(linktype: Linktype) -> Option<usize> {
+    match linktype {
+        Linktype::ETHERNET => Some(14),
+        Linktype::LINUX_SLL => Some(16),
+        Linktype::RAW | Linktype::IPV4 | Linktype::IPV6 => Some(0),
+        _ => None,
+    }
+}
+
+/// A UDP datagram found in a captured frame
+struct UdpDatagram<'a> {
+    source: IpAddr,
+    destination: IpAddr,
+    src_port: u16,
+    dst_port: u16,
+    payload: &'a [u8],
+}
+
+/// Locate the addresses, UDP ports and payload in a captured frame
+///
+/// Returns `None` for frames that are not IPv4/UDP or IPv6/UDP, or are too
+/// short. IPv6 extension headers are not followed.
+fn udp_datagram(linktype: Linktype, data: &[u8]) -> Option<UdpDatagram<'_>> {
+    let ip = data.get(link_header_len(linktype)?..)?;
+
+    let version_ihl = *ip.first()?;
+    let (source, destination, udp) = match version_ihl >> 4 {
+        // The IPv4 header length comes from IHL so options are skipped
+        4 if *ip.get(9)? == 17 => {
+            let source: [u8; 4] = ip.get(12..16)?.try_into().ok()?;
+            let destination: [u8; 4] = ip.get(16..20)?.try_into().ok()?;
+            (
+                IpAddr::from(Ipv4Addr::from(source)),
+                IpAddr::from(Ipv4Addr::from(destination)),
+                ip.get((version_ihl & 0x0F) as usize * 4..)?,
+            )
+        }
+        6 if *ip.get(6)? == 17 => {
+            let source: [u8; 16] = ip.get(8..24)?.try_into().ok()?;
+            let destination: [u8; 16] = ip.get(24..40)?.try_into().ok()?;
+            (
+                IpAddr::from(Ipv6Addr::from(source)),
+                IpAddr::from(Ipv6Addr::from(destination)),
+                ip.get(40..)?,
+            )
+        }
+        _ => return None,
+    };
+
+    Some(UdpDatagram {
+        source,
+        destination,
+        src_port: u16::from_be_bytes([*udp.first()?, *udp.get(1)?]),
+        dst_port: u16::from_be_bytes([*udp.get(2)?, *udp.get(3)?]),
+        payload: udp.get(8..).filter(|payload| !payload.is_empty())?,
+    })
+}
+
+/// Seconds since epoch for a record timestamp
+///
+/// The fractional field holds microseconds, or nanoseconds when the global
+/// header used the nanosecond magic (`0xa1b23c4d`).
+fn record_timestamp(ts_sec: u32, ts_frac: u32, nanosecond: bool) -> f64 {
+    let units_per_second = if nanosecond {
+        1_000_000_000.0
+    } else {
+        1_000_000.0
+    };
+    ts_sec as f64 + ts_frac as f64 / units_per_second
+}
+
+/// UDP ports used by retail AC servers
+pub const DEFAULT_SERVER_PORTS: RangeInclusive<u16> = 9000..=9013;
+
+/// Parse server port ranges such as `9000-9013,9050`
+///
+/// Ranges are comma separated; a single port stands for a range of one.
+pub fn parse_port_ranges(spec: &str) -> Result<Vec<RangeInclusive<u16>>> {
+    spec.split(',')
+        .map(|part| {
+            let part = part.trim();
+            let (start, end) = part.split_once('-').unwrap_or((part, part));
+            let parse = |port: &str| {
+                port.trim()
+                    .parse::<u16>()
+                    .with_context(|| format!("Invalid port in {part:?}"))
+            };
+            let (start, end) = (parse(start)?, parse(end)?);
+            if start > end {
+                anyhow::bail!("Port range {part:?} is backwards");
+            }
+            Ok(start..=end)
+        })
+        .collect()
+}
+
+/// Decide a datagram's direction from its UDP ports
+///
+/// Traffic from a server port is received and traffic to a server port is sent.
+/// When neither or both ports are server ports the result falls back to the
+/// source-port rule and is flagged as ambiguous.
+fn classify_direction(
+    server_ports: &[RangeInclusive<u16>],
+    src_port: u16,
+    dst_port: u16,
+) -> (Direction, bool) {
+    let is_server = |port: u16| server_ports.iter().any(|range| range.contains(&port));
+    match (is_server(src_port), is_server(dst_port)) {
+        (true, false) => (Direction::ServerToClient, false),
+        (false, true) => (Direction::ClientToServer, false),
+        (true, true) => (Direction::ServerToClient, true),
+        (false, false) => (Direction::ClientToServer, true),
+    }
+}
+
+/// Fragment reassembly counts from the last parse, for gauging capture loss
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FragmentStats {
+    #[serde(rename = "CompletedSend")]
+    pub completed_send: usize,
+    #[serde(rename = "CompletedRecv")]
+    pub completed_recv: usize,
+    /// Fragments still missing chunks when the capture ended
+    #[serde(rename = "PendingSend")]
+    pub pending_send: usize,
+    #[serde(rename = "PendingRecv")]
+    pub pending_recv: usize,
+    /// Completed fragments keyed by how many chunks they needed
+    #[serde(rename = "ChunkCounts")]
+    pub chunk_counts: std::collections::BTreeMap<u16, usize>,
+}
+
+/// Wall-clock span of a parsed capture
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CaptureInfo {
+    /// Timestamp of the earliest packet, in seconds since the epoch
+    #[serde(rename = "Start")]
+    pub start: f64,
+    /// Timestamp of the latest packet
+    #[serde(rename = "End")]
+    pub end: f64,
+    #[serde(rename = "DurationSecs")]
+    pub duration_secs: f64,
+    #[serde(rename = "PacketCount")]
+    pub packet_count: usize,
+}
+
+impl CaptureInfo {
+    /// Span of `packets`, which need not be in timestamp order
+    ///
+    /// `None` when there are no packets.
+    pub fn from_packets(packets: &[ParsedPacket]) -> Option<Self> {
+        let (start, end) = packets.iter().fold(None, |span, p| match span {
+            None => Some((p.timestamp, p.timestamp)),
+            Some((start, end)) => Some((p.timestamp.min(start), p.timestamp.max(end))),
+        })?;
+        Some(Self {
+            start,
+            end,
+            duration_secs: end - start,
+            packet_count: packets.len(),
+        })
+    }
+}
+
+/// Number of PCAP records between progress callbacks
+pub const PROGRESS_INTERVAL: usize = 1000;
+
+/// Message cap for parsing untrusted captures, far above any real session
+pub const DEFAULT_MAX_MESSAGES: usize = 2_000_000;
+
+/// Rewrites a UDP payload in place before it is parsed, see
+/// [`PacketParser::with_payload_transform`]
+pub type PayloadTransform = Box<dyn Fn(&mut [u8], Direction) + Send>;
+
 /// Main parser for PCAP files
+///
+/// Records, fragments and messages that fail to parse are skipped. Each one is
+/// reported as a `tracing` event with its offset or opcode and the error chain:
+/// dropped records and messages and decode failures at `DEBUG`, malformed
+/// fragments at `TRACE`.
 pub struct PacketParser {
-    pending_fragments: HashMap<u32, Fragment>,
+    reassembler: FragmentReassembler,
+    /// Whether to skip the extraction summary printed to stderr
+    quiet: bool,
+    /// Only parse datagrams to or from this address
+    ip_filter: Option<IpAddr>,
+    max_messages: Option<usize>,
+    max_packets: Option<usize>,
+    /// Ports that identify the server end of a datagram
+    server_ports: Vec<RangeInclusive<u16>>,
+    /// Whether the last parse stopped at `max_messages` or `max_packets`
+    truncated: bool,
+    capture_info: Option<CaptureInfo>,
+    payload_transform: Option<PayloadTransform>,
 }
 
 impl PacketParser {
     pub fn new() -> Self {
         Self {
-            pending_fragments: HashMap::new(),
+            reassembler: FragmentReassembler::new(),
+            quiet: false,
+            ip_filter: None,
+            max_messages: None,
+            max_packets: None,
+            server_ports: vec![DEFAULT_SERVER_PORTS],
+            truncated: false,
+            capture_info: None,
+            payload_transform: None,
         }
     }
 
+    /// Choose whether parsed messages and packets keep their raw bytes
+    ///
+    /// The binary view needs them, as do the sub-types in the `coverage` report
+    /// for messages that decoded, so batch dumps of large captures can turn this
+    /// off to save memory when those aren't wanted. Defaults to on.
+    pub fn with_raw_bytes(mut self, keep: bool) -> Self {
+        self.reassembler.keep_raw_bytes = keep;
+        self
+    }
+
+    /// Suppress the informational extraction summary written to stderr
+    ///
+    /// Useful when stderr is captured alongside machine-readable output.
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Only parse datagrams sent to or from `ip`
+    ///
+    /// Scopes a capture with several game sessions or unrelated UDP traffic to
+    /// one client. `None` (the default) parses every datagram.
+    pub fn with_ip_filter(mut self, ip: Option<IpAddr>) -> Self {
+        self.ip_filter = ip;
+        self
+    }
+
+    /// Use `ranges` instead of [`DEFAULT_SERVER_PORTS`] to tell server from client
+    ///
+    /// For private servers listening outside the retail range. A port in any of
+    /// the ranges counts as a server port.
+    pub fn with_server_ports(
+        mut self,
+        ranges: impl IntoIterator<Item = RangeInclusive<u16>>,
+    ) -> Self {
+        self.server_ports = ranges.into_iter().collect();
+        self
+    }
+
+    /// Run `transform` on every UDP payload before it is parsed as AC packets
+    ///
+    /// For captures from servers that XOR or otherwise obfuscate their
+    /// datagrams. The transform gets a copy of the payload and the direction
+    /// from the ports, so the parser itself stays unaware of the scheme. By
+    /// default payloads are parsed as captured.
+    pub fn with_payload_transform(
+        mut self,
+        transform: impl Fn(&mut [u8], Direction) + Send + 'static,
+    ) -> Self {
+        self.payload_transform = Some(Box::new(transform));
+        self
+    }
+
+    /// Stop parsing a capture once it has produced `max` messages
+    ///
+    /// Guards against corrupt or hostile captures that would otherwise decode
+    /// into an unbounded number of tiny messages. Later records are read but not
+    /// parsed, and [`truncated`](Self::truncated) reports that it happened.
+    /// [`parse_many`](Self::parse_many) applies it to the merged total, so the
+    /// later captures are the ones cut short.
+    /// `None` (the default) means no limit.
+    pub fn with_max_messages(mut self, max: Option<usize>) -> Self {
+        self.max_messages = max;
+        self
+    }
+
+    /// Stop parsing a capture once it has produced `max` packets
+    ///
+    /// Works like [`with_max_messages`](Self::with_max_messages).
+    pub fn with_max_packets(mut self, max: Option<usize>) -> Self {
+        self.max_packets = max;
+        self
+    }
+
+    /// Whether the most recent parse hit the message or packet limit
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    fn limit_reached(&self, packet_id: usize, message_id: usize) -> bool {
+        self.max_packets.is_some_and(|max| packet_id >= max)
+            || self.max_messages.is_some_and(|max| message_id >= max)
+    }
+
+    /// Time span and packet count of the most recent parse
+    ///
+    /// `None` before the first parse and when it found no AC packets.
+    pub fn capture_info(&self) -> Option<CaptureInfo> {
+        self.capture_info
+    }
+
+    /// Fragment completion counts from the most recent parse
+    pub fn fragment_stats(&self) -> &FragmentStats {
+        self.reassembler.stats()
+    }
+
     /// Parse a PCAP file from a reader
     pub fn parse_pcap<R: Read>(
         &mut self,
@@ -184,59 +450,203 @@ impl PacketParser {
         Vec<ParsedPacket>,
         Vec<messages::ParsedMessage>,
         weenie::WeenieDatabase,
+    )> {
+        self.parse_pcap_bytes_with_progress(buffer, |_, _| {})
+    }
+
+    /// Parse PCAP data from bytes, reporting progress as records are read
+    ///
+    /// `progress` receives the bytes consumed so far and the total buffer size.
+    /// It is called every [`PROGRESS_INTERVAL`] records and once more when the
+    /// whole buffer has been read.
+    pub fn parse_pcap_bytes_with_progress(
+        &mut self,
+        buffer: &[u8],
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<(
+        Vec<ParsedPacket>,
+        Vec<messages::ParsedMessage>,
+        weenie::WeenieDatabase,
+    )> {
+        self.reassembler.reset_stats();
+        self.truncated = false;
+        let (packets, all_messages) = self.parse_capture(buffer, &mut 0, &mut 0, &mut progress)?;
+        self.capture_info = CaptureInfo::from_packets(&packets);
+        let weenie_db = extract_weenies(&all_messages, self.quiet);
+
+        Ok((packets, all_messages, weenie_db))
+    }
+
+    /// Parse several PCAP captures and merge them into one chronological session
+    ///
+    /// Each capture is parsed with its own fragment state, so a pending fragment in
+    /// one file can never be completed by a sequence number from another. As a
+    /// consequence, messages whose fragments are split across two files are not
+    /// reassembled. Packets and messages are sorted by timestamp and given
+    /// contiguous ids (with message packet links remapped to match) before
+    /// weenies are extracted.
+    pub fn parse_many<R: Read>(
+        &mut self,
+        readers: impl IntoIterator<Item = R>,
+    ) -> Result<(
+        Vec<ParsedPacket>,
+        Vec<messages::ParsedMessage>,
+        weenie::WeenieDatabase,
     )> {
         let mut packets = Vec::new();
         let mut all_messages = Vec::new();
-        let mut weenie_db = weenie::WeenieDatabase::new();
-        let mut packet_id = 0;
-        let mut message_id = 0;
+        self.reassembler.reset_stats();
+        self.truncated = false;
+
+        // Ids run on across captures, which keeps them unique until renumbering
+        // and makes the limits count the merged total
+        let (mut packet_id, mut message_id) = (0, 0);
+        for (index, mut reader) in readers.into_iter().enumerate() {
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .with_context(|| format!("Failed to read pcap data for capture {index}"))?;
+
+            self.reassembler.clear();
+            let (file_packets, file_messages) = self
+                .parse_capture(&buffer, &mut packet_id, &mut message_id, &mut |_, _| {})
+                .with_context(|| format!("Failed to parse capture {index}"))?;
+            packets.extend(file_packets);
+            all_messages.extend(file_messages);
+        }
+        self.reassembler.clear();
+
+        // Stable sorts keep the original order for equal timestamps
+        packets.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        all_messages.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+
+        let mut packet_id_map = HashMap::with_capacity(packets.len());
+        for (id, packet) in packets.iter_mut().enumerate() {
+            packet_id_map.insert(packet.id, id);
+            packet.id = id;
+        }
+        for (id, message) in all_messages.iter_mut().enumerate() {
+            message.id = id;
+            message
+                .packet_ids
+                .retain_mut(|packet_id| match packet_id_map.get(packet_id) {
+                    Some(&id) => {
+                        *packet_id = id;
+                        true
+                    }
+                    None => false,
+                });
+        }
+
+        self.capture_info = CaptureInfo::from_packets(&packets);
+        let weenie_db = extract_weenies(&all_messages, self.quiet);
+
+        Ok((packets, all_messages, weenie_db))
+    }
+
+    /// Walk the PCAP records and parse every AC packet they carry
+    ///
+    /// Ids start at `packet_id` and `message_id` and are advanced past the
+    /// returned items.
+    fn parse_capture(
+        &mut self,
+        buffer: &[u8],
+        packet_id: &mut usize,
+        message_id: &mut usize,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<(Vec<ParsedPacket>, Vec<messages::ParsedMessage>)> {
+        let (packets, all_messages, _) =
+            self.parse_records(buffer, packet_id, message_id, progress)?;
+        progress(buffer.len(), buffer.len());
+
+        self.reassembler.count_pending();
+
+        Ok((packets, all_messages))
+    }
+
+    /// Parse the complete PCAP records in `buffer`
+    ///
+    /// Ids start at `packet_id` and `message_id` and are advanced past the
+    /// returned items. Also returns how many bytes the global header and the
+    /// complete records took up; a trailing partial record is left unconsumed.
+    fn parse_records(
+        &mut self,
+        buffer: &[u8],
+        packet_id: &mut usize,
+        message_id: &mut usize,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<(Vec<ParsedPacket>, Vec<messages::ParsedMessage>, usize)> {
+        let mut packets = Vec::new();
+        let mut all_messages = Vec::new();
+        let mut consumed = 0;
+        let mut records = 0;
 
         let mut reader =
             LegacyPcapReader::new(65536, buffer).context("Failed to create pcap reader")?;
+        let mut linktype = Linktype::ETHERNET;
+        let mut nanosecond = false;
 
         loop {
             match reader.next() {
                 Ok((offset, block)) => {
                     match block {
+                        PcapBlockOwned::Legacy(_)
+                            if self.limit_reached(*packet_id, *message_id) =>
+                        {
+                            self.truncated = true;
+                        }
                         PcapBlockOwned::Legacy(packet) => {
-                            let data = packet.data;
-                            // Extract timestamp (seconds + microseconds)
                             let timestamp =
-                                packet.ts_sec as f64 + (packet.ts_usec as f64 / 1_000_000.0);
-
-                            // Skip to UDP payload (Ethernet + IP + UDP headers = 42 bytes)
-                            if data.len() > 42 {
-                                let udp_payload = &data[42..];
-
-                                // Determine direction from port
-                                let src_port = u16::from_be_bytes([data[34], data[35]]);
-                                let direction = if (9000..=9013).contains(&src_port) {
-                                    Direction::ServerToClient // From server
-                                } else {
-                                    Direction::ClientToServer // To server
-                                };
-
-                                match self.parse_packet(
-                                    udp_payload,
-                                    direction,
-                                    timestamp,
-                                    &mut packet_id,
-                                    &mut message_id,
-                                ) {
-                                    Ok((mut parsed_packets, msgs)) => {
-                                        packets.append(&mut parsed_packets);
-                                        all_messages.extend(msgs);
-                                    }
-                                    Err(_e) => {
-                                        // Skip failed packets silently
-                                    }
+                                record_timestamp(packet.ts_sec, packet.ts_usec, nanosecond);
+
+                            match self.parse_frame(
+                                linktype,
+                                packet.data,
+                                timestamp,
+                                packet_id,
+                                message_id,
+                            ) {
+                                Ok((mut parsed_packets, msgs)) => {
+                                    packets.append(&mut parsed_packets);
+                                    all_messages.extend(msgs);
+                                    self.trim_to_limits(
+                                        &mut packets,
+                                        &mut all_messages,
+                                        packet_id,
+                                        message_id,
+                                    );
                                 }
+                                Err(e) => {
+                                    // Skip failed packets
+                                    tracing::debug!(
+                                        offset = consumed,
+                                        timestamp,
+                                        "dropped record: {e:#}"
+                                    );
+                                }
+                            }
+                        }
+                        PcapBlockOwned::LegacyHeader(header) => {
+                            // pcap_parser already handles the byte order the magic
+                            // indicates; only the timestamp unit is left to us
+                            nanosecond = header.is_nanosecond_precision();
+                            linktype = header.network;
+                            if link_header_len(linktype).is_none() {
+                                anyhow::bail!(
+                                    "Unsupported link-layer type {} (expected Ethernet, Linux SLL, or raw IP)",
+                                    linktype.0
+                                );
                             }
                         }
-                        PcapBlockOwned::LegacyHeader(_) => {}
                         _ => {}
                     }
                     reader.consume(offset);
+
+                    consumed += offset;
+                    records += 1;
+                    if records % PROGRESS_INTERVAL == 0 {
+                        progress(consumed, buffer.len());
+                    }
                 }
                 Err(PcapError::Eof) => break,
                 Err(PcapError::Incomplete(_)) => {
@@ -251,196 +661,1012 @@ impl PacketParser {
             }
         }
 
-        // Extract weenie updates from all messages
-        let mut type_counts: std::collections::HashMap<String, (usize, usize)> =
-            std::collections::HashMap::new();
-        for msg in &all_messages {
-            let updates = weenie_extractor::extract_weenie_updates(msg);
-            let entry = type_counts
-                .entry(msg.message_type.clone())
-                .or_insert((0, 0));
-            entry.0 += 1; // total messages
-            entry.1 += updates.len(); // successful extractions
-            for update in updates {
-                weenie_db.add_or_update(update);
-            }
-        }
+        Ok((packets, all_messages, consumed))
+    }
+
+    /// Parse the AC packets in one captured link-layer frame
+    ///
+    /// For live captures that feed frames one at a time. Fragment state carries
+    /// over between calls, so messages split across frames are reassembled.
+    /// Packet and message ids start at `packet_id` and `message_id`, which are
+    /// advanced past the returned items. Frames that aren't UDP yield nothing.
+    pub fn parse_frame(
+        &mut self,
+        linktype: Linktype,
+        frame: &[u8],
+        timestamp: f64,
+        packet_id: &mut usize,
+        message_id: &mut usize,
+    ) -> Result<(Vec<ParsedPacket>, Vec<messages::ParsedMessage>)> {
+        let Some(datagram) = udp_datagram(linktype, frame).filter(|datagram| {
+            self.ip_filter
+                .is_none_or(|ip| datagram.source == ip || datagram.destination == ip)
+        }) else {
+            return Ok((Vec::new(), Vec::new()));
+        };
 
-        eprintln!("\n=== Extraction Summary ===");
-        eprintln!("Total messages processed: {}", all_messages.len());
-        let mut types: Vec<_> = type_counts.iter().collect();
-        types.sort_by_key(|(_, (_, extracted))| std::cmp::Reverse(*extracted));
-        for (msg_type, (total, extracted)) in types.iter().take(20) {
-            if *extracted > 0 {
-                eprintln!(
-                    "{}: {} extracted from {} messages",
-                    msg_type, extracted, total
-                );
+        // Determine direction from ports
+        let (direction, ambiguous) =
+            classify_direction(&self.server_ports, datagram.src_port, datagram.dst_port);
+        let transformed;
+        let payload = match &self.payload_transform {
+            Some(transform) => {
+                let mut bytes = datagram.payload.to_vec();
+                transform(&mut bytes, direction);
+                transformed = bytes;
+                &transformed
             }
+            None => datagram.payload,
+        };
+        let (mut packets, messages) = self
+            .reassembler
+            .parse_packet(payload, direction, timestamp, packet_id, message_id)?;
+        for packet in &mut packets {
+            packet.ambiguous_direction = ambiguous;
         }
-        eprintln!(
-            "Total message types with 0 extractions: {}",
-            types.iter().filter(|(_, (_, e))| *e == 0).count()
-        );
-        eprintln!("Final weenie count: {}\n", weenie_db.count());
-
-        Ok((packets, all_messages, weenie_db))
+        Ok((packets, messages))
     }
 
-    fn parse_packet(
+    /// Drop whatever the last frame produced beyond the packet and message limits
+    fn trim_to_limits(
         &mut self,
-        data: &[u8],
-        direction: Direction,
-        timestamp: f64,
+        packets: &mut Vec<ParsedPacket>,
+        messages: &mut Vec<messages::ParsedMessage>,
         packet_id: &mut usize,
         message_id: &mut usize,
-    ) -> Result<(Vec<ParsedPacket>, Vec<messages::ParsedMessage>)> {
-        let mut packets = Vec::new();
-        let mut all_messages = Vec::new();
-        let mut reader = BinaryReader::new(data);
+    ) {
+        if let Some(max) = self.max_packets
+            && *packet_id > max
+        {
+            packets.truncate(packets.len() - (*packet_id - max));
+            *packet_id = max;
+            // A message completed by a dropped packet goes with it. Such messages
+            // come last, and every other message only links to kept packets.
+            let dropped = messages
+                .iter()
+                .rev()
+                .take_while(|m| m.packet_ids.last().is_some_and(|&id| id >= max))
+                .count();
+            messages.truncate(messages.len() - dropped);
+            *message_id -= dropped;
+            self.truncated = true;
+        }
+        if let Some(max) = self.max_messages
+            && *message_id > max
+        {
+            messages.truncate(messages.len() - (*message_id - max));
+            *message_id = max;
+            self.truncated = true;
+        }
+    }
+}
 
-        while reader.remaining() > 0 {
-            let start_pos = reader.position();
+impl Default for PacketParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-            let header = PacketHeader::parse(&mut reader)?;
+/// Build the weenie database from parsed messages, logging an extraction summary unless quiet
+fn extract_weenies(
+    all_messages: &[messages::ParsedMessage],
+    quiet: bool,
+) -> weenie::WeenieDatabase {
+    let mut weenie_db = weenie::WeenieDatabase::new();
 
-            let packet_end = start_pos + PacketHeader::BASE_SIZE + header.size as usize;
-            let payload_start = reader.position();
-            let payload_size = packet_end.saturating_sub(payload_start);
+    // Extract weenie updates from all messages
+    let mut type_counts: std::collections::HashMap<String, (usize, usize)> =
+        std::collections::HashMap::new();
+    for msg in all_messages {
+        let updates = weenie_extractor::extract_weenie_updates(msg);
+        let entry = type_counts
+            .entry(msg.message_type.clone())
+            .or_insert((0, 0));
+        entry.0 += 1; // total messages
+        entry.1 += updates.len(); // successful extractions
+        for update in updates {
+            weenie_db.add_or_update(update);
+        }
+    }
 
-            // Capture raw payload bytes
-            let raw_payload = if payload_size > 0 && payload_size <= data.len() - payload_start {
-                data[payload_start..payload_start + payload_size].to_vec()
-            } else {
-                Vec::new()
-            };
+    if quiet {
+        return weenie_db;
+    }
 
-            let direction_str = match direction {
-                Direction::ClientToServer => "Send".to_string(),
-                Direction::ServerToClient => "Recv".to_string(),
-            };
+    eprintln!("\n=== Extraction Summary ===");
+    eprintln!("Total messages processed: {}", all_messages.len());
+    let mut types: Vec<_> = type_counts.iter().collect();
+    types.sort_by_key(|(_, (_, extracted))| std::cmp::Reverse(*extracted));
+    for (msg_type, (total, extracted)) in types.iter().take(20) {
+        if *extracted > 0 {
+            eprintln!(
+                "{}: {} extracted from {} messages",
+                msg_type, extracted, total
+            );
+        }
+    }
+    eprintln!(
+        "Total message types with 0 extractions: {}",
+        types.iter().filter(|(_, (_, e))| *e == 0).count()
+    );
+    eprintln!("Final weenie count: {}\n", weenie_db.count());
 
-            let mut parsed_packet = ParsedPacket {
-                header: header.clone(),
-                direction: direction_str,
-                messages: Vec::new(),
-                fragment: None,
-                id: *packet_id,
-                timestamp,
-                raw_payload,
-            };
-            *packet_id += 1;
-
-            if header.flags.contains(PacketHeaderFlags::BLOB_FRAGMENTS) {
-                while reader.position() < packet_end && reader.remaining() > 0 {
-                    match self.parse_fragment(&mut reader, direction, timestamp, message_id) {
-                        Ok((frag_info, msgs)) => {
-                            parsed_packet.fragment = Some(frag_info);
-                            for msg in msgs {
-                                parsed_packet.messages.push(msg.data.clone());
-                                all_messages.push(msg);
-                            }
-                        }
-                        Err(_e) => {
-                            break;
-                        }
-                    }
+    weenie_db
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build an AC packet carrying a single complete fragment with the given message
+    fn ac_packet(sequence: u32, fragment_sequence: u32, message: &[u8]) -> Vec<u8> {
+        ac_fragment_packet(sequence, fragment_sequence, 1, 0, message)
+    }
+
+    /// Build an AC packet carrying one chunk of a (possibly multi-part) fragment
+    pub(crate) fn ac_fragment_packet(
+        sequence: u32,
+        fragment_sequence: u32,
+        count: u16,
+        index: u16,
+        chunk: &[u8],
+    ) -> Vec<u8> {
+        let frag_size = 16 + chunk.len() as u16;
+        let mut out = Vec::new();
+        out.extend_from_slice(&sequence.to_le_bytes());
+        out.extend_from_slice(&PacketHeaderFlags::BLOB_FRAGMENTS.bits().to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes()); // checksum
+        out.extend_from_slice(&0u16.to_le_bytes()); // id
+        out.extend_from_slice(&0u16.to_le_bytes()); // time
+        out.extend_from_slice(&frag_size.to_le_bytes()); // size
+        out.extend_from_slice(&0u16.to_le_bytes()); // iteration
+        out.extend_from_slice(&fragment_sequence.to_le_bytes());
+        out.extend_from_slice(&0x8000_0000u32.to_le_bytes()); // fragment id
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&frag_size.to_le_bytes());
+        out.extend_from_slice(&index.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // group
+        out.extend_from_slice(chunk);
+        out
+    }
+
+    /// Wrap a UDP payload in Ethernet + IPv4 + UDP headers
+    fn ethernet_frame(src_port: u16, dst_port: u16, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![0u8; 42];
+        out[12..14].copy_from_slice(&0x0800u16.to_be_bytes());
+        out[14] = 0x45;
+        out[23] = 17;
+        out[34..36].copy_from_slice(&src_port.to_be_bytes());
+        out[36..38].copy_from_slice(&dst_port.to_be_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    /// Ethernet + IPv4 frame between the given addresses
+    fn ethernet_frame_between(
+        source: [u8; 4],
+        destination: [u8; 4],
+        src_port: u16,
+        dst_port: u16,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let mut out = ethernet_frame(src_port, dst_port, payload);
+        out[26..30].copy_from_slice(&source);
+        out[30..34].copy_from_slice(&destination);
+        out
+    }
+
+    /// Wrap a UDP payload in IPv6 + UDP headers with no link layer
+    fn ipv6_packet(source: Ipv6Addr, src_port: u16, dst_port: u16, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![0u8; 48];
+        out[0] = 0x60;
+        out[6] = 17;
+        out[8..24].copy_from_slice(&source.octets());
+        out[40..42].copy_from_slice(&src_port.to_be_bytes());
+        out[42..44].copy_from_slice(&dst_port.to_be_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    /// Wrap a UDP payload in IPv4 + UDP headers with no link layer
+    fn ip_packet(src_port: u16, dst_port: u16, payload: &[u8]) -> Vec<u8> {
+        ethernet_frame(src_port, dst_port, payload)[14..].to_vec()
+    }
+
+    /// Wrap a UDP payload in a Linux cooked (SLL) header + IPv4 + UDP headers
+    fn sll_frame(src_port: u16, dst_port: u16, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![0u8; 16];
+        out[14..16].copy_from_slice(&0x0800u16.to_be_bytes());
+        out.extend_from_slice(&ip_packet(src_port, dst_port, payload));
+        out
+    }
+
+    /// Build a little-endian microsecond Ethernet pcap file from (timestamp, frame) records
+    fn pcap_file(records: &[(f64, Vec<u8>)]) -> Vec<u8> {
+        pcap_file_with_linktype(1, records)
+    }
+
+    /// Build a little-endian microsecond pcap file with the given link-layer type
+    fn pcap_file_with_linktype(linktype: u32, records: &[(f64, Vec<u8>)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+        out.extend_from_slice(&2u16.to_le_bytes());
+        out.extend_from_slice(&4u16.to_le_bytes());
+        out.extend_from_slice(&0i32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&65535u32.to_le_bytes());
+        out.extend_from_slice(&linktype.to_le_bytes());
+        for (timestamp, frame) in records {
+            let secs = timestamp.trunc() as u32;
+            let usecs = (timestamp.fract() * 1_000_000.0).round() as u32;
+            out.extend_from_slice(&secs.to_le_bytes());
+            out.extend_from_slice(&usecs.to_le_bytes());
+            out.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            out.extend_from_slice(frame);
+        }
+        out
+    }
+
+    /// An OrderedGameEvent message with the given event type
+    pub(crate) fn game_event(event_type: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&0xF7B0u32.to_le_bytes());
+        out.extend_from_slice(&0x5000_0001u32.to_le_bytes()); // object id
+        out.extend_from_slice(&1u32.to_le_bytes()); // sequence
+        out.extend_from_slice(&event_type.to_le_bytes());
+        out
+    }
+
+    fn recv_record(timestamp: f64, fragment_sequence: u32) -> (f64, Vec<u8>) {
+        let packet = ac_packet(fragment_sequence, fragment_sequence, &game_event(0x00C9));
+        (timestamp, ethernet_frame(9000, 50000, &packet))
+    }
+
+    #[test]
+    fn test_classify_direction() {
+        let ports = [DEFAULT_SERVER_PORTS];
+        assert!(matches!(
+            classify_direction(&ports, 9000, 50000),
+            (Direction::ServerToClient, false)
+        ));
+        assert!(matches!(
+            classify_direction(&ports, 50000, 9013),
+            (Direction::ClientToServer, false)
+        ));
+        assert!(matches!(
+            classify_direction(&ports, 9000, 9001),
+            (Direction::ServerToClient, true)
+        ));
+        assert!(matches!(
+            classify_direction(&ports, 50000, 50001),
+            (Direction::ClientToServer, true)
+        ));
+
+        let custom = [9000..=9013, 2000..=2000];
+        assert!(matches!(
+            classify_direction(&custom, 2000, 50000),
+            (Direction::ServerToClient, false)
+        ));
+    }
+
+    #[test]
+    fn test_parse_port_ranges() {
+        assert_eq!(
+            parse_port_ranges("9000-9013, 9050").unwrap(),
+            vec![9000..=9013, 9050..=9050]
+        );
+        assert!(parse_port_ranges("9013-9000").is_err());
+        assert!(parse_port_ranges("9000-").is_err());
+        assert!(parse_port_ranges("70000").is_err());
+    }
+
+    #[test]
+    fn test_packet_direction_from_ports() {
+        let packet = ac_packet(1, 1, &game_event(0x00C9));
+        let capture = pcap_file(&[
+            (1.0, ethernet_frame(9000, 50000, &packet)),
+            (2.0, ethernet_frame(50000, 9000, &packet)),
+            (3.0, ethernet_frame(50000, 50001, &packet)),
+        ]);
+
+        let mut parser = PacketParser::new();
+        let (packets, _, _) = parser.parse_pcap_bytes(&capture).unwrap();
+
+        let directions: Vec<(&str, bool)> = packets
+            .iter()
+            .map(|p| (p.direction.as_str(), p.ambiguous_direction))
+            .collect();
+        assert_eq!(
+            directions,
+            vec![("Recv", false), ("Send", false), ("Send", true)]
+        );
+    }
+
+    #[test]
+    fn test_linux_sll_and_raw_ip_link_layers() {
+        let packet = ac_packet(1, 1, &game_event(0x00C9));
+        let sll = pcap_file_with_linktype(113, &[(1.0, sll_frame(9000, 50000, &packet))]);
+        let raw = pcap_file_with_linktype(101, &[(1.0, ip_packet(50000, 9000, &packet))]);
+
+        let (packets, messages, _) = PacketParser::new().parse_pcap_bytes(&sll).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(packets[0].direction, "Recv");
+
+        let (packets, messages, _) = PacketParser::new().parse_pcap_bytes(&raw).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(packets[0].direction, "Send");
+    }
+
+    #[test]
+    fn test_ip_filter_keeps_one_client() {
+        let packet = ac_packet(1, 1, &game_event(0x00C9));
+        let client = [10, 0, 0, 2];
+        let server = [192, 0, 2, 1];
+        let capture = pcap_file(&[
+            (
+                1.0,
+                ethernet_frame_between(server, client, 9000, 50000, &packet),
+            ),
+            (
+                2.0,
+                ethernet_frame_between(client, server, 50000, 9000, &packet),
+            ),
+            (
+                3.0,
+                ethernet_frame_between(server, [10, 0, 0, 3], 9000, 50001, &packet),
+            ),
+        ]);
+
+        let (packets, _, _) = PacketParser::new().parse_pcap_bytes(&capture).unwrap();
+        assert_eq!(packets.len(), 3);
+
+        let (packets, _, _) = PacketParser::new()
+            .with_ip_filter(Some(IpAddr::from(client)))
+            .parse_pcap_bytes(&capture)
+            .unwrap();
+        let timestamps: Vec<f64> = packets.iter().map(|p| p.timestamp).collect();
+        assert_eq!(timestamps, vec![1.0, 2.0]);
+
+        let client_v6: Ipv6Addr = "2001:db8::2".parse().unwrap();
+        let capture_v6 =
+            pcap_file_with_linktype(229, &[(1.0, ipv6_packet(client_v6, 50000, 9000, &packet))]);
+        let (packets, messages, _) = PacketParser::new()
+            .with_ip_filter(Some(IpAddr::from(client_v6)))
+            .parse_pcap_bytes(&capture_v6)
+            .unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(packets[0].direction, "Send");
+    }
+
+    #[test]
+    fn test_nanosecond_and_big_endian_captures() {
+        let (_, frame) = recv_record(0.0, 1);
+        let record = |out: &mut Vec<u8>, values: [u32; 4], big_endian: bool| {
+            for value in values {
+                if big_endian {
+                    out.extend_from_slice(&value.to_be_bytes());
+                } else {
+                    out.extend_from_slice(&value.to_le_bytes());
                 }
             }
+            out.extend_from_slice(&frame);
+        };
+        let len = frame.len() as u32;
 
-            let current_pos = reader.position();
-            if current_pos < packet_end {
-                reader.set_position(packet_end);
-            }
+        // Nanosecond magic, little-endian
+        let mut nano = pcap_file(&[]);
+        nano[..4].copy_from_slice(&0xa1b23c4du32.to_le_bytes());
+        record(&mut nano, [100, 250_000_000, len, len], false);
 
-            packets.push(parsed_packet);
+        // Microsecond magic, big-endian
+        let mut big = Vec::new();
+        for value in [0xa1b2c3d4u32, 0x0002_0004, 0, 0, 65535, 1] {
+            big.extend_from_slice(&value.to_be_bytes());
         }
+        record(&mut big, [100, 250_000, len, len], true);
 
-        Ok((packets, all_messages))
+        for capture in [nano, big] {
+            let (packets, messages, _) = PacketParser::new().parse_pcap_bytes(&capture).unwrap();
+            assert_eq!(packets.len(), 1);
+            assert_eq!(messages.len(), 1);
+            assert!((packets[0].timestamp - 100.25).abs() < 1e-9);
+        }
     }
 
-    fn parse_fragment(
-        &mut self,
-        reader: &mut BinaryReader,
-        direction: Direction,
-        timestamp: f64,
-        message_id: &mut usize,
-    ) -> Result<(FragmentInfo, Vec<messages::ParsedMessage>)> {
-        let mut parsed_messages = Vec::new();
-
-        let sequence = reader.read_u32()?;
-        let id = reader.read_u32()?;
-        let count = reader.read_u16()?;
-        let size = reader.read_u16()?;
-        let index = reader.read_u16()?;
-        let _group = reader.read_u16()?;
-
-        if size < 16 {
-            anyhow::bail!("Invalid fragment size: {size}");
+    #[test]
+    fn test_unsupported_link_layer_is_an_error() {
+        let capture = pcap_file_with_linktype(105, &[]); // IEEE 802.11
+        assert!(PacketParser::new().parse_pcap_bytes(&capture).is_err());
+    }
+
+    #[test]
+    fn test_non_udp_frames_are_skipped() {
+        let mut frame = ethernet_frame(9000, 50000, &ac_packet(1, 1, &game_event(0x00C9)));
+        frame[23] = 6; // TCP
+        let capture = pcap_file(&[(1.0, frame)]);
+
+        let (packets, _, _) = PacketParser::new().parse_pcap_bytes(&capture).unwrap();
+        assert!(packets.is_empty());
+    }
+
+    #[test]
+    fn test_parse_many_merges_chronologically() {
+        let first = pcap_file(&[recv_record(10.0, 1), recv_record(30.0, 2)]);
+        let second = pcap_file(&[recv_record(20.0, 1), recv_record(40.0, 2)]);
+
+        let mut parser = PacketParser::new();
+        let (packets, messages, _) = parser
+            .parse_many([first.as_slice(), second.as_slice()])
+            .unwrap();
+
+        let timestamps: Vec<f64> = messages.iter().map(|m| m.timestamp).collect();
+        assert_eq!(timestamps, vec![10.0, 20.0, 30.0, 40.0]);
+        let ids: Vec<usize> = messages.iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![0, 1, 2, 3]);
+        let packet_ids: Vec<usize> = packets.iter().map(|p| p.id).collect();
+        assert_eq!(packet_ids, vec![0, 1, 2, 3]);
+
+        // Message links follow the renumbered packets
+        for message in &messages {
+            assert_eq!(message.packet_ids, vec![message.id]);
+            assert_eq!(packets[message.packet_ids[0]].timestamp, message.timestamp);
         }
+    }
+
+    #[test]
+    fn test_payload_transform() {
+        let mut payload = ac_packet(1, 1, &game_event(0x00C9));
+        payload.iter_mut().for_each(|b| *b ^= 0x5A);
+        let capture = pcap_file(&[(1.0, ethernet_frame(9000, 50000, &payload))]);
+
+        let (_, messages, _) = PacketParser::new().parse_pcap_bytes(&capture).unwrap();
+        assert!(messages.is_empty());
+
+        let mut parser = PacketParser::new().with_payload_transform(|bytes, direction| {
+            assert!(matches!(direction, Direction::ServerToClient));
+            bytes.iter_mut().for_each(|b| *b ^= 0x5A);
+        });
+        let (packets, messages, _) = parser.parse_pcap_bytes(&capture).unwrap();
+        assert_eq!((packets.len(), messages.len()), (1, 1));
+    }
+
+    #[test]
+    fn test_capture_info() {
+        let mut parser = PacketParser::new();
+        assert_eq!(parser.capture_info(), None);
+
+        let capture = pcap_file(&[recv_record(12.5, 1), recv_record(10.0, 2)]);
+        parser.parse_pcap_bytes(&capture).unwrap();
+        assert_eq!(
+            parser.capture_info(),
+            Some(CaptureInfo {
+                start: 10.0,
+                end: 12.5,
+                duration_secs: 2.5,
+                packet_count: 2,
+            })
+        );
+
+        parser.parse_pcap_bytes(&pcap_file(&[])).unwrap();
+        assert_eq!(parser.capture_info(), None);
+    }
 
-        let frag_length = size as usize - 16;
+    #[test]
+    fn test_parse_frame_reassembles_across_calls() {
+        let mut first_chunk = game_event(0x00C9);
+        first_chunk.resize(448, 0);
+        let first = ethernet_frame(9000, 50000, &ac_fragment_packet(1, 5, 2, 0, &first_chunk));
+        let second = ethernet_frame(9000, 50000, &ac_fragment_packet(2, 5, 2, 1, &[0u8; 8]));
 
-        if reader.remaining() < frag_length {
-            anyhow::bail!("Fragment data too short");
+        let mut parser = PacketParser::new();
+        let (mut packet_id, mut message_id) = (10, 20);
+        let (packets, messages) = parser
+            .parse_frame(
+                Linktype::ETHERNET,
+                &first,
+                1.0,
+                &mut packet_id,
+                &mut message_id,
+            )
+            .unwrap();
+        assert_eq!(packets.len(), 1);
+        assert!(messages.is_empty());
+
+        let (packets, messages) = parser
+            .parse_frame(
+                Linktype::ETHERNET,
+                &second,
+                2.0,
+                &mut packet_id,
+                &mut message_id,
+            )
+            .unwrap();
+        assert_eq!(packets[0].id, 11);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].id, 20);
+        assert_eq!(messages[0].packet_ids, vec![10, 11]);
+        assert_eq!((packet_id, message_id), (12, 21));
+    }
+
+    #[test]
+    fn test_follower_completes_fragments_across_appends() {
+        let mut first_chunk = game_event(0x00C9);
+        first_chunk.resize(448, 0);
+        let first = ethernet_frame(9000, 50000, &ac_fragment_packet(1, 5, 2, 0, &first_chunk));
+        let second = ethernet_frame(9000, 50000, &ac_fragment_packet(2, 5, 2, 1, &[0u8; 8]));
+        let second_record = 24 + 16 + first.len();
+        let capture = pcap_file(&[(1.0, first), (2.0, second), recv_record(3.0, 6)]);
+
+        // Split inside the global header and inside the second record
+        let mut follower = follow::PcapFollower::new();
+        let (packets, messages) = follower.feed(&capture[..10]).unwrap();
+        assert!(packets.is_empty() && messages.is_empty());
+        let (packets, messages) = follower.feed(&capture[10..second_record + 20]).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert!(messages.is_empty());
+
+        let (packets, messages) = follower.feed(&capture[second_record + 20..]).unwrap();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].packet_ids, vec![0, 1]);
+        assert_eq!(messages[1].id, 1);
+        assert_eq!(follower.offset(), capture.len() as u64);
+    }
+
+    #[test]
+    fn test_follower_restarts_when_file_shrinks() {
+        let path = std::env::temp_dir().join(format!("follow-{}.pcap", std::process::id()));
+        let long = pcap_file(&[recv_record(1.0, 1), recv_record(2.0, 2)]);
+        std::fs::write(&path, &long).unwrap();
+
+        let mut follower = follow::PcapFollower::new();
+        let (_, messages) = follower.poll_file(&path).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert!(follower.poll_file(&path).unwrap().1.is_empty());
+
+        std::fs::write(&path, pcap_file(&[recv_record(5.0, 1)])).unwrap();
+        let (_, messages) = follower.poll_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].id, 0);
+        assert_eq!(messages[0].timestamp, 5.0);
+    }
+
+    #[test]
+    fn test_message_links_to_all_carrying_packets() {
+        let mut first_chunk = game_event(0x00C9);
+        first_chunk.resize(448, 0);
+        let first = ac_fragment_packet(1, 5, 2, 0, &first_chunk);
+        let second = ac_fragment_packet(2, 5, 2, 1, &[0u8; 8]);
+        let unrelated = ac_packet(3, 6, &game_event(0x00C9));
+        let capture = pcap_file(&[
+            (1.0, ethernet_frame(9000, 50000, &first)),
+            (2.0, ethernet_frame(9000, 50000, &unrelated)),
+            (3.0, ethernet_frame(9000, 50000, &second)),
+        ]);
+
+        let mut parser = PacketParser::new();
+        let (packets, messages, _) = parser.parse_pcap_bytes(&capture).unwrap();
+
+        assert_eq!(packets.len(), 3);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].packet_ids, vec![1]);
+        assert_eq!(messages[0].fragment_sequence, 6);
+        assert_eq!(messages[1].packet_ids, vec![0, 2]);
+        assert_eq!(messages[1].fragment_sequence, 5);
+        assert_eq!(messages[1].packet_sequence, 1);
+        assert_ne!(messages[0].key(), messages[1].key());
+    }
+
+    #[test]
+    fn test_unknown_opcode_keeps_port_direction() {
+        let mut message = 0xDEAD_0001u32.to_le_bytes().to_vec();
+        message.extend_from_slice(&[1, 2, 3, 4]);
+        let packet = ac_packet(1, 1, &message);
+        let capture = pcap_file(&[
+            (1.0, ethernet_frame(50000, 9000, &packet)),
+            (2.0, ethernet_frame(9000, 50000, &packet)),
+        ]);
+
+        let (_, messages, _) = PacketParser::new().parse_pcap_bytes(&capture).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].message_type, "Unknown");
+        assert_eq!(messages[0].direction, "Send");
+        assert_eq!(messages[1].direction, "Recv");
+
+        assert!(messages::parse_message(&message, 0).is_err());
+    }
+
+    #[test]
+    fn test_fragments_reassemble_out_of_order() {
+        let mut first_chunk = game_event(0x00C9);
+        first_chunk.resize(448, 0);
+        let capture = pcap_file(&[
+            (
+                1.0,
+                ethernet_frame(9000, 50000, &ac_fragment_packet(1, 5, 2, 1, &[0u8; 8])),
+            ),
+            // A repeated chunk must not count towards completion
+            (
+                2.0,
+                ethernet_frame(9000, 50000, &ac_fragment_packet(2, 5, 2, 1, &[0u8; 8])),
+            ),
+            (
+                3.0,
+                ethernet_frame(9000, 50000, &ac_fragment_packet(3, 5, 2, 0, &first_chunk)),
+            ),
+        ]);
+
+        let mut parser = PacketParser::new();
+        let (packets, messages, _) = parser.parse_pcap_bytes(&capture).unwrap();
+        assert_eq!(packets[1].fragment.as_ref().unwrap().received, 1);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].size, 456);
+        assert_eq!(messages[0].packet_ids, vec![0, 1, 2]);
+        assert_eq!(parser.fragment_stats().pending_recv, 0);
+    }
+
+    #[test]
+    fn test_parse_stops_at_limits() {
+        let capture = pcap_file(
+            &(1..=5)
+                .map(|i| recv_record(i as f64, i))
+                .collect::<Vec<_>>(),
+        );
+
+        let mut parser = PacketParser::new();
+        let (_, messages, _) = parser.parse_pcap_bytes(&capture).unwrap();
+        assert_eq!(messages.len(), 5);
+        assert!(!parser.truncated());
+
+        let mut parser = PacketParser::new().with_max_messages(Some(3));
+        let (packets, messages, _) = parser.parse_pcap_bytes(&capture).unwrap();
+        assert_eq!((packets.len(), messages.len()), (3, 3));
+        assert!(parser.truncated());
+
+        let mut parser = PacketParser::new().with_max_packets(Some(2));
+        let (packets, messages, _) = parser.parse_pcap_bytes(&capture).unwrap();
+        assert_eq!((packets.len(), messages.len()), (2, 2));
+        assert!(parser.truncated());
+
+        // Merged captures share one budget
+        let mut parser = PacketParser::new().with_max_messages(Some(7));
+        let (packets, messages, _) = parser
+            .parse_many([capture.as_slice(), capture.as_slice()])
+            .unwrap();
+        assert_eq!((packets.len(), messages.len()), (7, 7));
+        assert!(parser.truncated());
+    }
+
+    #[test]
+    fn test_packet_limit_drops_messages_completed_past_it() {
+        let mut first_chunk = game_event(0x00C9);
+        first_chunk.resize(448, 0);
+        // The second datagram carries an unrelated message, then the chunk that
+        // completes fragment 5 in a packet beyond the limit
+        let mut datagram = ac_packet(2, 6, &game_event(0x00C9));
+        datagram.extend_from_slice(&ac_fragment_packet(3, 5, 2, 1, &[0u8; 8]));
+        let capture = pcap_file(&[
+            (
+                1.0,
+                ethernet_frame(9000, 50000, &ac_fragment_packet(1, 5, 2, 0, &first_chunk)),
+            ),
+            (2.0, ethernet_frame(9000, 50000, &datagram)),
+        ]);
+
+        let mut parser = PacketParser::new().with_max_packets(Some(2));
+        let (packets, messages, _) = parser.parse_pcap_bytes(&capture).unwrap();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].fragment_sequence, 6);
+        assert_eq!(messages[0].packet_ids, vec![1]);
+        assert!(parser.truncated());
+
+        let (packets, messages, _) = parser
+            .parse_many([capture.as_slice(), capture.as_slice()])
+            .unwrap();
+        for message in &messages {
+            assert!(message.packet_ids.iter().all(|&id| id < packets.len()));
         }
+    }
 
-        let bytes = reader.read_bytes(frag_length)?;
+    #[test]
+    fn test_fragment_stats() {
+        let mut first_chunk = game_event(0x00C9);
+        first_chunk.resize(448, 0);
+        let capture = pcap_file(&[
+            (
+                1.0,
+                ethernet_frame(9000, 50000, &ac_fragment_packet(1, 5, 2, 0, &first_chunk)),
+            ),
+            (
+                2.0,
+                ethernet_frame(9000, 50000, &ac_packet(2, 6, &game_event(0x00C9))),
+            ),
+            (
+                3.0,
+                ethernet_frame(9000, 50000, &ac_fragment_packet(3, 5, 2, 1, &[0u8; 8])),
+            ),
+            (
+                4.0,
+                ethernet_frame(50000, 9000, &ac_fragment_packet(1, 7, 3, 0, &first_chunk)),
+            ),
+        ]);
 
-        let fragment = self
-            .pending_fragments
-            .entry(sequence)
-            .or_insert_with(|| Fragment::new(sequence, count));
+        let mut parser = PacketParser::new();
+        parser.parse_pcap_bytes(&capture).unwrap();
 
-        fragment.add_chunk(&bytes, index as usize);
+        let stats = parser.fragment_stats();
+        assert_eq!((stats.completed_recv, stats.completed_send), (2, 0));
+        assert_eq!((stats.pending_recv, stats.pending_send), (0, 1));
+        assert_eq!(stats.chunk_counts, [(1, 1), (2, 1)].into_iter().collect());
+    }
 
-        fragment.header = FragmentHeader {
-            sequence,
-            id,
-            count,
-            size,
-            index,
-            group: None,
-        };
+    #[test]
+    fn test_fragment_stats_count_each_direction() {
+        // Both sides leave fragment 5 unfinished and both finish fragment 6, so
+        // every count is split evenly however the sequences line up
+        let mut first_chunk = game_event(0x00C9);
+        first_chunk.resize(448, 0);
+        let capture = pcap_file(&[
+            (
+                1.0,
+                ethernet_frame(50000, 9000, &ac_fragment_packet(1, 5, 2, 0, &first_chunk)),
+            ),
+            (
+                2.0,
+                ethernet_frame(9000, 50000, &ac_fragment_packet(1, 5, 2, 1, &[0u8; 8])),
+            ),
+            (
+                3.0,
+                ethernet_frame(50000, 9000, &ac_fragment_packet(2, 6, 2, 0, &first_chunk)),
+            ),
+            (
+                4.0,
+                ethernet_frame(9000, 50000, &ac_fragment_packet(2, 6, 2, 0, &first_chunk)),
+            ),
+            (
+                5.0,
+                ethernet_frame(9000, 50000, &ac_fragment_packet(3, 6, 2, 1, &[0u8; 8])),
+            ),
+            (
+                6.0,
+                ethernet_frame(50000, 9000, &ac_fragment_packet(3, 6, 2, 1, &[0u8; 8])),
+            ),
+        ]);
 
-        let is_complete = fragment.is_complete();
-        let frag_data = fragment.data[..fragment.length].to_vec();
-        let frag_received = fragment.received;
-        let frag_length = fragment.length;
-
-        let frag_info = FragmentInfo {
-            data: BASE64.encode(&frag_data),
-            count,
-            received: frag_received,
-            length: frag_length,
-            sequence,
-        };
+        let mut parser = PacketParser::new();
+        let (_, messages, _) = parser.parse_pcap_bytes(&capture).unwrap();
 
-        if is_complete {
-            self.pending_fragments.remove(&sequence);
-
-            match messages::parse_message(&frag_data, *message_id) {
-                Ok(mut parsed) => {
-                    parsed.direction = match direction {
-                        Direction::ClientToServer => "Send".to_string(),
-                        Direction::ServerToClient => "Recv".to_string(),
-                    };
-                    parsed.timestamp = timestamp;
-                    parsed_messages.push(parsed);
-                    *message_id += 1;
-                }
-                Err(_e) => {
-                    // Skip failed messages
-                }
+        let directions: Vec<_> = messages.iter().map(|m| m.direction.as_str()).collect();
+        assert_eq!(directions, ["Recv", "Send"]);
+        let stats = parser.fragment_stats();
+        assert_eq!((stats.completed_send, stats.completed_recv), (1, 1));
+        assert_eq!((stats.pending_send, stats.pending_recv), (1, 1));
+    }
+
+    #[test]
+    fn test_many_chunk_message_interleaved_with_other_direction() {
+        // A Login_PlayerDescription-sized message: six chunks from the server,
+        // out of order, with the client's own fragments using the same sequence
+        // numbers arriving in between
+        let mut description = game_event(0x0013);
+        description.resize(5 * 448 + 100, 0xAB);
+        let chunks: Vec<&[u8]> = description.chunks(448).collect();
+        assert_eq!(chunks.len(), 6);
+
+        let mut client_message = description[..448].to_vec();
+        client_message[8] = 2;
+
+        let mut records = Vec::new();
+        let mut server_sequence = 1;
+        for (n, index) in [2u16, 0, 5, 1, 4, 3].into_iter().enumerate() {
+            let chunk = chunks[index as usize];
+            records.push((
+                records.len() as f64,
+                ethernet_frame(
+                    9000,
+                    50000,
+                    &ac_fragment_packet(server_sequence, 40, 6, index, chunk),
+                ),
+            ));
+            server_sequence += 1;
+            // Client fragment 40 is its own two-chunk message, split around the
+            // server's chunks
+            if n == 1 || n == 4 {
+                let index = u16::from(n == 4);
+                let chunk = if index == 0 {
+                    &client_message[..]
+                } else {
+                    &[0u8; 12][..]
+                };
+                records.push((
+                    records.len() as f64,
+                    ethernet_frame(
+                        50000,
+                        9000,
+                        &ac_fragment_packet(n as u32, 40, 2, index, chunk),
+                    ),
+                ));
             }
         }
 
-        Ok((frag_info, parsed_messages))
+        let mut parser = PacketParser::new().with_quiet(true);
+        let (_, messages, _) = parser.parse_pcap_bytes(&pcap_file(&records)).unwrap();
+
+        let stats = parser.fragment_stats();
+        assert_eq!((stats.pending_send, stats.pending_recv), (0, 0));
+        assert_eq!(stats.chunk_counts.get(&6), Some(&1));
+        assert_eq!(stats.chunk_counts.get(&2), Some(&1));
+
+        let received = messages
+            .iter()
+            .find(|m| m.direction == "Recv" && m.fragment_sequence == 40)
+            .expect("six-chunk message was not reassembled");
+        assert_eq!(received.size, description.len());
+        assert_eq!(received.raw_bytes, description);
+        assert_eq!(received.packet_ids.len(), 6);
+
+        let sent = messages
+            .iter()
+            .find(|m| m.direction == "Send" && m.fragment_sequence == 40)
+            .expect("client message was not reassembled");
+        assert_eq!(sent.size, 448 + 12);
+        assert_eq!(&sent.raw_bytes[..448], &client_message[..]);
     }
-}
 
-impl Default for PacketParser {
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn test_truncated_packets_do_not_panic() {
+        let packet = ac_fragment_packet(1, 1, 2, 1, &game_event(0x00C9));
+        for len in 0..=packet.len() {
+            let file = pcap_file(&[(1.0, ethernet_frame(9000, 50000, &packet[..len]))]);
+            // Any outcome is fine as long as malformed input is reported, not a
+            // panic, including one the decode guard caught
+            if let Ok((_, messages, _)) = PacketParser::new().parse_pcap_bytes(&file) {
+                assert!(!messages.iter().any(messages::decoder_panicked));
+            }
+        }
+    }
+
+    #[test]
+    fn test_oversized_header_lengths_are_clamped() {
+        let mut packet = ac_packet(1, 1, &game_event(0x00C9));
+        packet[16..18].copy_from_slice(&u16::MAX.to_le_bytes()); // packet size
+        packet[30..32].copy_from_slice(&u16::MAX.to_le_bytes()); // fragment size
+        let file = pcap_file(&[(1.0, ethernet_frame(9000, 50000, &packet))]);
+
+        let (packets, messages, _) = PacketParser::new().parse_pcap_bytes(&file).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(
+            packets[0].raw_payload.len(),
+            packet.len() - PacketHeader::BASE_SIZE
+        );
+        assert!(messages.is_empty());
+    }
+
+    #[test]
+    fn test_fragment_overrunning_its_packet_keeps_next_packet() {
+        // First packet's fragment claims 20 more bytes than the packet holds
+        let mut first = ac_packet(1, 1, &game_event(0x00C9));
+        let frag_size = u16::from_le_bytes([first[30], first[31]]) + 20;
+        first[30..32].copy_from_slice(&frag_size.to_le_bytes());
+        // Zero-size packet flagged as carrying fragments
+        let mut empty = ac_packet(2, 2, &[]);
+        empty.truncate(PacketHeader::BASE_SIZE);
+        empty[16..18].copy_from_slice(&0u16.to_le_bytes());
+        let last = ac_packet(3, 3, &game_event(0x00C9));
+
+        let datagram = [first, empty, last].concat();
+        let file = pcap_file(&[(1.0, ethernet_frame(9000, 50000, &datagram))]);
+
+        let (packets, messages, _) = PacketParser::new().parse_pcap_bytes(&file).unwrap();
+        let sequences: Vec<u32> = packets.iter().map(|p| p.header.sequence).collect();
+        assert_eq!(sequences, vec![1, 2, 3]);
+        assert!(packets[0].fragment.is_none());
+        assert!(packets[1].fragment.is_none());
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].packet_sequence, 3);
+    }
+
+    /// An AC packet with only echo header sections
+    fn echo_packet(flags: PacketHeaderFlags, sections: &[f32]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&1u32.to_le_bytes()); // sequence
+        out.extend_from_slice(&flags.bits().to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes()); // checksum
+        out.extend_from_slice(&0u16.to_le_bytes()); // id
+        out.extend_from_slice(&0u16.to_le_bytes()); // time
+        out.extend_from_slice(&(sections.len() as u16 * 4).to_le_bytes()); // size
+        out.extend_from_slice(&0u16.to_le_bytes()); // iteration
+        for value in sections {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn test_echo_round_trip() {
+        let request = echo_packet(PacketHeaderFlags::ECHO_REQUEST, &[42.5]);
+        let response = echo_packet(PacketHeaderFlags::ECHO_RESPONSE, &[42.5, 0.05]);
+        let file = pcap_file(&[
+            (1.0, ethernet_frame(50000, 9000, &request)),
+            (1.25, ethernet_frame(9000, 50000, &response)),
+        ]);
+
+        let (packets, _, _) = PacketParser::new().parse_pcap_bytes(&file).unwrap();
+        assert_eq!(packets[0].echo_request, Some(42.5));
+        assert_eq!(packets[1].echo_response.unwrap().holding_time, 0.05);
+
+        let trips = echo::round_trips(&packets);
+        assert_eq!(trips.len(), 1);
+        assert_eq!((trips[0].request_packet, trips[0].response_packet), (0, 1));
+        assert!((trips[0].rtt - 0.25).abs() < 1e-6);
+        assert!((trips[0].network_rtt - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_progress_reaches_total() {
+        let records: Vec<(f64, Vec<u8>)> = (0..PROGRESS_INTERVAL as u32 + 1)
+            .map(|i| recv_record(i as f64, i + 1))
+            .collect();
+        let file = pcap_file(&records);
+
+        let mut calls = Vec::new();
+        PacketParser::new()
+            .parse_pcap_bytes_with_progress(&file, |processed, total| {
+                calls.push((processed, total))
+            })
+            .unwrap();
+
+        assert_eq!(calls.len(), 2);
+        assert!(calls[0].0 < file.len());
+        assert_eq!(calls[1], (file.len(), file.len()));
+    }
+
+    #[test]
+    fn test_raw_bytes_can_be_dropped() {
+        let file = pcap_file(&[recv_record(1.0, 1)]);
+
+        let (packets, messages, _) = PacketParser::new().parse_pcap_bytes(&file).unwrap();
+        assert!(!packets[0].raw_payload.is_empty());
+        assert_eq!(messages[0].raw_bytes, game_event(0x00C9));
+
+        let (packets, messages, _) = PacketParser::new()
+            .with_raw_bytes(false)
+            .parse_pcap_bytes(&file)
+            .unwrap();
+        assert!(packets[0].raw_payload.is_empty());
+        assert!(messages[0].raw_bytes.is_empty());
+        assert_eq!(messages[0].size, game_event(0x00C9).len());
+        assert_eq!(messages[0].opcode, "F7B0");
+    }
+
+    #[test]
+    fn test_parse_many_isolates_fragments_per_file() {
+        // First file ends with the first half of a two-part message
+        let mut partial = ac_packet(1, 7, &game_event(0x00C9));
+        partial[28..30].copy_from_slice(&2u16.to_le_bytes()); // fragment count
+        let first = pcap_file(&[(1.0, ethernet_frame(9000, 50000, &partial))]);
+
+        // Second file reuses fragment sequence 7 for an unrelated second chunk
+        let mut stray = ac_packet(1, 7, &[0xAB; 8]);
+        stray[28..30].copy_from_slice(&2u16.to_le_bytes());
+        stray[32..34].copy_from_slice(&1u16.to_le_bytes()); // fragment index
+        let second = pcap_file(&[(2.0, ethernet_frame(9000, 50000, &stray))]);
+
+        let mut parser = PacketParser::new();
+        let (packets, messages, _) = parser
+            .parse_many([first.as_slice(), second.as_slice()])
+            .unwrap();
+
+        assert_eq!(packets.len(), 2);
+        // Joined across files the two halves would complete a message; kept
+        // apart, each file ends with its half still pending
+        assert!(messages.is_empty());
+        assert_eq!(parser.fragment_stats().pending_recv, 2);
     }
 }
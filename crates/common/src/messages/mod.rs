@@ -2,16 +2,23 @@ use anyhow::Result;
 use serde::Serialize;
 use std::io::Cursor;
 
+use acprotocol::enums::{C2SMessage, GameAction, GameEvent, S2CMessage};
 use acprotocol::readers::ACReader;
-use acprotocol::unified::{Direction, MessageKind};
+use acprotocol::unified::{Direction, MessageKind as ProtocolMessage};
 
 /// Parsed AC message with all fields decoded
-#[derive(Debug, Clone, Serialize)]
+///
+/// The `Default` value is an empty unknown message, handy as the base of a
+/// struct update when only a few fields matter.
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct ParsedMessage {
     #[serde(rename = "Id")]
     pub id: usize,
     #[serde(rename = "Type")]
     pub message_type: String,
+    /// Typed form of `message_type` for matching in code
+    #[serde(rename = "Kind")]
+    pub kind: MessageKind,
     #[serde(rename = "Data")]
     pub data: serde_json::Value,
     #[serde(rename = "Direction")]
@@ -20,12 +27,300 @@ pub struct ParsedMessage {
     pub opcode: String,
     #[serde(rename = "Timestamp")]
     pub timestamp: f64, // Seconds since epoch (with microsecond precision)
+    /// Ids of the packets whose fragments were reassembled into this message
+    #[serde(rename = "PacketIds")]
+    pub packet_ids: Vec<usize>,
+    /// Header sequence of the first packet that carried this message
+    #[serde(rename = "PacketSequence")]
+    pub packet_sequence: u32,
+    /// Sequence number of the fragment that carried this message
+    #[serde(rename = "FragmentSequence")]
+    pub fragment_sequence: u32,
+    /// Length of the reassembled payload, kept even when `raw_bytes` is dropped
+    #[serde(rename = "Size")]
+    pub size: usize,
     #[serde(skip)]
     pub raw_bytes: Vec<u8>,
 }
 
+impl ParsedMessage {
+    /// Identity of this message that does not depend on parse order
+    pub fn key(&self) -> MessageKey {
+        MessageKey {
+            sent: self.direction == "Send",
+            packet_sequence: self.packet_sequence,
+            fragment_sequence: self.fragment_sequence,
+        }
+    }
+
+    /// High-level category of this message, from its type name
+    pub fn category(&self) -> MessageCategory {
+        MessageCategory::from_type_name(&self.message_type)
+    }
+}
+
+/// Stable identity of a message, unlike `id` which is assigned in parse order
+///
+/// Fragment sequences are per direction, and the carrying packet's sequence
+/// tells apart fragments from different sessions in the same capture. There is
+/// no offset within the fragment: AC puts exactly one message in each fragment
+/// and the reassembler never splits or joins them, so the fragment alone names
+/// the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct MessageKey {
+    pub sent: bool,
+    pub packet_sequence: u32,
+    pub fragment_sequence: u32,
+}
+
+/// High-level grouping of message types for legends, stats, and filtering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum MessageCategory {
+    Combat,
+    Magic,
+    Movement,
+    Communication,
+    Item,
+    House,
+    Trade,
+    Qualities,
+    Other,
+}
+
+impl MessageCategory {
+    /// Every category, in display order
+    pub const ALL: [MessageCategory; 9] = [
+        MessageCategory::Combat,
+        MessageCategory::Magic,
+        MessageCategory::Movement,
+        MessageCategory::Communication,
+        MessageCategory::Item,
+        MessageCategory::House,
+        MessageCategory::Trade,
+        MessageCategory::Qualities,
+        MessageCategory::Other,
+    ];
+
+    /// Categorize a message type name by its prefix (e.g. `Magic_UpdateEnchantment`)
+    pub fn from_type_name(message_type: &str) -> Self {
+        let prefix = message_type
+            .split_once('_')
+            .map_or(message_type, |(prefix, _)| prefix);
+        match prefix {
+            "Combat" => MessageCategory::Combat,
+            "Magic" => MessageCategory::Magic,
+            "Movement" => MessageCategory::Movement,
+            "Communication" => MessageCategory::Communication,
+            "Item" | "Inventory" => MessageCategory::Item,
+            "House" => MessageCategory::House,
+            "Trade" => MessageCategory::Trade,
+            "Qualities" => MessageCategory::Qualities,
+            _ => MessageCategory::Other,
+        }
+    }
+
+    /// Display name
+    pub fn name(self) -> &'static str {
+        match self {
+            MessageCategory::Combat => "Combat",
+            MessageCategory::Magic => "Magic",
+            MessageCategory::Movement => "Movement",
+            MessageCategory::Communication => "Communication",
+            MessageCategory::Item => "Item",
+            MessageCategory::House => "House",
+            MessageCategory::Trade => "Trade",
+            MessageCategory::Qualities => "Qualities",
+            MessageCategory::Other => "Other",
+        }
+    }
+}
+
+/// Message type as a protocol enum variant
+///
+/// Ordered game actions and events are unwrapped to their inner action/event
+/// type when it's recognized; otherwise they stay as the C2S/S2C wrapper.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum MessageKind {
+    C2S(C2SMessage),
+    S2C(S2CMessage),
+    GameAction(GameAction),
+    GameEvent(GameEvent),
+    /// Opcode not known to either direction
+    Unknown(u32),
+}
+
+impl Default for MessageKind {
+    fn default() -> Self {
+        MessageKind::Unknown(0)
+    }
+}
+
+impl MessageKind {
+    /// Classify a message from its opcode and raw bytes (including the opcode)
+    pub fn from_bytes(opcode: u32, data: &[u8]) -> Self {
+        let payload = data.get(4..).unwrap_or_default();
+
+        if let Ok(msg_type) = C2SMessage::try_from(opcode) {
+            if msg_type == C2SMessage::OrderedGameAction
+                && payload.len() >= 8
+                && let Ok(game_action) = GameAction::try_from(u32::from_le_bytes([
+                    payload[4], payload[5], payload[6], payload[7],
+                ]))
+            {
+                return MessageKind::GameAction(game_action);
+            }
+            return MessageKind::C2S(msg_type);
+        }
+
+        if let Ok(msg_type) = S2CMessage::try_from(opcode) {
+            if msg_type == S2CMessage::OrderedGameEvent
+                && let Some(header) = OrderedEventHeader::parse(data)
+                && let Ok(game_event) = GameEvent::try_from(header.event_type)
+            {
+                return MessageKind::GameEvent(game_event);
+            }
+            return MessageKind::S2C(msg_type);
+        }
+
+        MessageKind::Unknown(opcode)
+    }
+
+    /// Human-readable name, matching the serde-renamed protocol enum variant
+    pub fn name(&self) -> String {
+        match self {
+            MessageKind::C2S(C2SMessage::OrderedGameAction) => "OrderedGameAction".to_string(),
+            MessageKind::S2C(S2CMessage::OrderedGameEvent) => "OrderedGameEvent".to_string(),
+            MessageKind::C2S(msg_type) => serde_name(msg_type),
+            MessageKind::S2C(msg_type) => serde_name(msg_type),
+            MessageKind::GameAction(game_action) => serde_name(game_action),
+            MessageKind::GameEvent(game_event) => serde_name(game_event),
+            MessageKind::Unknown(_) => "Unknown".to_string(),
+        }
+    }
+}
+
+/// A message type the decoder knows, for browsing the protocol
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownMessageType {
+    pub kind: MessageKind,
+    /// Which enum it comes from: `C2S`, `S2C`, `GameAction` or `GameEvent`
+    pub family: &'static str,
+    /// Opcode as shown in the list, with the action or event type after a slash
+    /// for ordered messages (e.g. `F7B0/00C9`)
+    pub code: String,
+    pub name: String,
+}
+
+/// Every message type in the protocol enums, by family and then code
+///
+/// Built on first use by probing each enum over the 16-bit code space.
+pub fn known_message_types() -> &'static [KnownMessageType] {
+    static TYPES: std::sync::OnceLock<Vec<KnownMessageType>> = std::sync::OnceLock::new();
+    TYPES.get_or_init(|| {
+        let mut types = Vec::new();
+        let mut add = |kind: MessageKind, family, code| {
+            let name = kind.name();
+            types.push(KnownMessageType {
+                kind,
+                family,
+                code,
+                name,
+            });
+        };
+        for code in 0..=0xFFFF {
+            if let Ok(message) = C2SMessage::try_from(code) {
+                add(MessageKind::C2S(message), "C2S", format!("{code:04X}"));
+            }
+        }
+        for code in 0..=0xFFFF {
+            if let Ok(message) = S2CMessage::try_from(code) {
+                add(MessageKind::S2C(message), "S2C", format!("{code:04X}"));
+            }
+        }
+        for code in 0..=0xFFFF {
+            if let Ok(action) = GameAction::try_from(code) {
+                let code = format!("F7B1/{code:04X}");
+                add(MessageKind::GameAction(action), "GameAction", code);
+            }
+        }
+        for code in 0..=0xFFFF {
+            if let Ok(event) = GameEvent::try_from(code) {
+                let code = format!("F7B0/{code:04X}");
+                add(MessageKind::GameEvent(event), "GameEvent", code);
+            }
+        }
+        types
+    })
+}
+
+/// Framing that precedes every OrderedGameEvent (0xF7B0) payload
+///
+/// Matches ACE's `GameMessageGameEvent`: the opcode is followed by the guid of
+/// the player the event is addressed to, that player's game event sequence and
+/// then the event type, all little-endian u32s. The object id is therefore the
+/// recipient, not the subject of the event: an appraisal's `ObjectId` is the
+/// appraised item while the header carries the player's id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderedEventHeader {
+    pub object_id: u32,
+    pub sequence: u32,
+    pub event_type: u32,
+}
+
+impl OrderedEventHeader {
+    /// Size of the framing including the opcode
+    pub const SIZE: usize = 16;
+
+    /// Read the framing from raw message bytes (including the opcode)
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let field = |index: usize| {
+            let bytes = data.get(4 + index * 4..8 + index * 4)?;
+            Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        };
+        Some(Self {
+            object_id: field(0)?,
+            sequence: field(1)?,
+            event_type: field(2)?,
+        })
+    }
+}
+
+/// Serialize to get the serde-renamed version of an enum variant
+fn serde_name<T: Serialize + std::fmt::Debug>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .unwrap_or_else(|| format!("{:?}", value))
+}
+
 /// Parse a message from raw bytes using acprotocol
+///
+/// This must never panic, whatever the input: captures from modified servers
+/// contain messages the decoder doesn't expect, and those should come back as
+/// `Err` or as a message with an `error` payload. Opcodes in neither the C2S nor
+/// the S2C table are an error, since their direction can't be known.
 pub fn parse_message(data: &[u8], id: usize) -> Result<ParsedMessage> {
+    parse_message_from(data, id, None)
+}
+
+/// Parse a message whose transport direction is already known
+///
+/// The direction from the UDP ports is used for opcodes in neither table, so
+/// unknown messages come back as `Unknown` with an `error` payload and the
+/// right Send/Recv direction instead of being dropped.
+pub fn parse_message_with_direction(
+    data: &[u8],
+    id: usize,
+    direction: Direction,
+) -> Result<ParsedMessage> {
+    parse_message_from(data, id, Some(direction))
+}
+
+fn parse_message_from(
+    data: &[u8],
+    id: usize,
+    transport_direction: Option<Direction>,
+) -> Result<ParsedMessage> {
     if data.len() < 4 {
         anyhow::bail!("Message data too short to contain opcode");
     }
@@ -33,18 +328,25 @@ pub fn parse_message(data: &[u8], id: usize) -> Result<ParsedMessage> {
     // Read the opcode
     let opcode = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
 
-    // Determine direction based on opcode
-    let direction = determine_direction(opcode)?;
+    // Determine direction based on opcode, falling back on the transport
+    let direction = match (determine_direction(opcode), transport_direction) {
+        (Ok(direction), _) => direction,
+        (Err(_), Some(direction)) => direction,
+        (Err(e), None) => return Err(e),
+    };
     let direction_str = match direction {
         Direction::ClientToServer => "Send",
         Direction::ServerToClient => "Recv",
     };
 
     // Parse the message using acprotocol
-    let mut cursor = Cursor::new(data);
-    let reader: &mut dyn ACReader = &mut cursor;
+    let decoded = catch_decode_panic(|| {
+        let mut cursor = Cursor::new(data);
+        let reader: &mut dyn ACReader = &mut cursor;
+        ProtocolMessage::read(reader, direction).map_err(|e| e.to_string())
+    });
 
-    let parsed_data = match MessageKind::read(reader, direction) {
+    let mut parsed_data = match decoded {
         Ok(message) => {
             // Serialize the parsed message to JSON
             serde_json::to_value(&message)?
@@ -59,24 +361,91 @@ pub fn parse_message(data: &[u8], id: usize) -> Result<ParsedMessage> {
         }
     };
 
-    // Get message type name
-    let message_type = get_message_type_name(opcode, data);
+    let kind = MessageKind::from_bytes(opcode, data);
+    let message_type = kind.name();
+
+    if message_type == "Item_SetAppraiseInfo" {
+        crate::appraisal_flags::add_appraisal_flags(&mut parsed_data, data);
+    }
+    if message_type == "Effects_SoundEvent" {
+        add_sound_id(&mut parsed_data, data);
+    }
+    if crate::coordinates::carries_position(&message_type) {
+        crate::coordinates::add_locations(&mut parsed_data);
+    }
 
     Ok(ParsedMessage {
         id,
         message_type,
+        kind,
         data: parsed_data,
         direction: direction_str.to_string(),
         opcode: format!("{:04X}", opcode),
         timestamp: 0.0,
+        packet_ids: Vec::new(),
+        packet_sequence: 0,
+        fragment_sequence: 0,
+        size: data.len(),
         raw_bytes: data.to_vec(),
     })
 }
 
+/// Start of the `error` text of a message whose decoder panicked
+pub const DECODER_PANIC: &str = "Decoder panicked";
+
+/// Run a decoder, turning a panic inside it into an error
+///
+/// One message tripping a bug in a field reader then shows up as an error entry
+/// instead of aborting the whole capture. The panic hook still reports each one
+/// with its location. WASM builds abort on panic and can't unwind, so there the
+/// decoder runs unguarded and fuzzing is the defense.
+fn catch_decode_panic<T>(decode: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(decode)).unwrap_or_else(|panic| {
+            let reason = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown cause".to_string());
+            Err(format!("{DECODER_PANIC}: {reason}"))
+        })
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        decode()
+    }
+}
+
+/// Whether `message` carries the error of a decoder that panicked
+pub fn decoder_panicked(message: &ParsedMessage) -> bool {
+    message
+        .data
+        .get("error")
+        .and_then(|error| error.as_str())
+        .is_some_and(|error| error.starts_with(DECODER_PANIC))
+}
+
+/// Add the numeric `SoundId` next to the `SoundType` name of Effects_SoundEvent
+///
+/// acprotocol only serializes the sound's enum name, so filtering on an exact id
+/// would otherwise need a reverse lookup. The id follows the opcode and object id.
+fn add_sound_id(parsed: &mut serde_json::Value, data: &[u8]) {
+    let Some(bytes) = data.get(8..12) else {
+        return;
+    };
+    let sound_id = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+    if let Some(event) = parsed
+        .pointer_mut("/S2C/EffectsSoundEvent")
+        .and_then(|v| v.as_object_mut())
+    {
+        event.insert("SoundId".to_string(), sound_id.into());
+    }
+}
+
 /// Determine message direction based on opcode
 fn determine_direction(opcode: u32) -> Result<Direction> {
-    use acprotocol::enums::{C2SMessage, S2CMessage};
-
     if C2SMessage::try_from(opcode).is_ok() {
         Ok(Direction::ClientToServer)
     } else if S2CMessage::try_from(opcode).is_ok() {
@@ -89,57 +458,168 @@ fn determine_direction(opcode: u32) -> Result<Direction> {
     }
 }
 
-/// Get human-readable message type name from parsed message data
-fn get_message_type_name(opcode: u32, data: &[u8]) -> String {
-    use acprotocol::enums::{C2SMessage, GameAction, GameEvent, S2CMessage};
+/// Fuzzing entry point for `parse_message`
+///
+/// Discards the result so a fuzz target only has to watch for panics. A panic
+/// that [`parse_message`] caught and turned into an error is raised again, so
+/// the guard doesn't hide decoder bugs from the fuzzer.
+pub fn parse_message_fuzz(data: &[u8]) {
+    if let Ok(message) = parse_message(data, 0)
+        && decoder_panicked(&message)
+    {
+        panic!("{}", message.data["error"]);
+    }
+}
 
-    if data.len() < 4 {
-        return "Unknown".to_string();
-    }
-
-    let payload = &data[4..];
-
-    // Check C2S messages
-    if let Ok(msg_type) = C2SMessage::try_from(opcode) {
-        if msg_type == C2SMessage::OrderedGameAction && payload.len() >= 8 {
-            let action_type_val =
-                u32::from_le_bytes([payload[4], payload[5], payload[6], payload[7]]);
-            if let Ok(game_action) = GameAction::try_from(action_type_val) {
-                // Serialize to get the serde-renamed version
-                return serde_json::to_value(&game_action)
-                    .ok()
-                    .and_then(|v| v.as_str().map(|s| s.to_string()))
-                    .unwrap_or_else(|| format!("{:?}", game_action));
-            }
-            return "OrderedGameAction".to_string();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small deterministic xorshift generator so failures are reproducible
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn test_kind_unwraps_ordered_messages() {
+        let mut event = 0xF7B0u32.to_le_bytes().to_vec();
+        event.extend_from_slice(&[0; 8]);
+        event.extend_from_slice(&0x00C9u32.to_le_bytes());
+        let appraise_info = GameEvent::try_from(0x00C9).unwrap();
+        let kind = MessageKind::from_bytes(0xF7B0, &event);
+        assert_eq!(kind.name(), serde_name(&appraise_info));
+        assert_eq!(kind, MessageKind::GameEvent(appraise_info));
+
+        event[12..16].copy_from_slice(&0xFFFFu32.to_le_bytes());
+        let kind = MessageKind::from_bytes(0xF7B0, &event);
+        assert_eq!(kind, MessageKind::S2C(S2CMessage::OrderedGameEvent));
+        assert_eq!(kind.name(), "OrderedGameEvent");
+
+        let kind = MessageKind::from_bytes(0xDEAD, &0xDEADu32.to_le_bytes());
+        assert_eq!(kind, MessageKind::Unknown(0xDEAD));
+        assert_eq!(kind.name(), "Unknown");
+    }
+
+    #[test]
+    fn test_known_message_types() {
+        let types = known_message_types();
+        let appraise_info = types
+            .iter()
+            .find(|t| t.kind == MessageKind::GameEvent(GameEvent::try_from(0x00C9).unwrap()))
+            .unwrap();
+        assert_eq!(appraise_info.family, "GameEvent");
+        assert_eq!(appraise_info.code, "F7B0/00C9");
+        assert_eq!(appraise_info.name, appraise_info.kind.name());
+
+        let ordered = types
+            .iter()
+            .find(|t| t.kind == MessageKind::S2C(S2CMessage::OrderedGameEvent))
+            .unwrap();
+        assert_eq!(ordered.code, "F7B0");
+    }
+
+    #[test]
+    fn test_ordered_event_header_field_order() {
+        // Appraisal of item 0x80001234 sent to player 0x50000001 as their 7th event
+        let mut data = Vec::new();
+        for value in [0xF7B0u32, 0x5000_0001, 7, 0x00C9, 0x8000_1234] {
+            data.extend_from_slice(&value.to_le_bytes());
         }
-        // Serialize to get the serde-renamed version
-        return serde_json::to_value(&msg_type)
-            .ok()
-            .and_then(|v| v.as_str().map(|s| s.to_string()))
-            .unwrap_or_else(|| format!("{:?}", msg_type));
-    }
-
-    // Check S2C messages
-    if let Ok(msg_type) = S2CMessage::try_from(opcode) {
-        if msg_type == S2CMessage::OrderedGameEvent && payload.len() >= 12 {
-            let event_type_val =
-                u32::from_le_bytes([payload[8], payload[9], payload[10], payload[11]]);
-            if let Ok(game_event) = GameEvent::try_from(event_type_val) {
-                // Serialize to get the serde-renamed version
-                return serde_json::to_value(&game_event)
-                    .ok()
-                    .and_then(|v| v.as_str().map(|s| s.to_string()))
-                    .unwrap_or_else(|| format!("{:?}", game_event));
-            }
-            return "OrderedGameEvent".to_string();
+
+        let header = OrderedEventHeader::parse(&data).unwrap();
+        assert_eq!(header.object_id, 0x5000_0001);
+        assert_eq!(header.sequence, 7);
+        assert_eq!(header.event_type, 0x00C9);
+
+        // The appraised item's id starts the event payload, after the framing
+        let payload = &data[OrderedEventHeader::SIZE..];
+        assert_eq!(
+            u32::from_le_bytes(payload[..4].try_into().unwrap()),
+            0x8000_1234
+        );
+
+        assert!(OrderedEventHeader::parse(&data[..15]).is_none());
+    }
+
+    #[test]
+    fn test_message_category_from_type_name() {
+        let category = MessageCategory::from_type_name;
+        assert_eq!(category("Magic_UpdateEnchantment"), MessageCategory::Magic);
+        assert_eq!(
+            category("Qualities_PrivateUpdateInt"),
+            MessageCategory::Qualities
+        );
+        assert_eq!(
+            category("Inventory_PutItemInContainer"),
+            MessageCategory::Item
+        );
+        assert_eq!(category("Item_SetAppraiseInfo"), MessageCategory::Item);
+        assert_eq!(category("Fellowship_Quit"), MessageCategory::Other);
+        assert_eq!(category("OrderedGameEvent"), MessageCategory::Other);
+    }
+
+    #[test]
+    fn test_decoder_panic_becomes_error() {
+        assert_eq!(catch_decode_panic(|| Ok(1)), Ok(1));
+        let result: Result<(), String> = catch_decode_panic(|| panic!("length underflow"));
+        assert_eq!(
+            result,
+            Err("Decoder panicked: length underflow".to_string())
+        );
+
+        let message = ParsedMessage {
+            data: serde_json::json!({"error": result.unwrap_err()}),
+            ..Default::default()
+        };
+        assert!(decoder_panicked(&message));
+        assert!(!decoder_panicked(&ParsedMessage::default()));
+    }
+
+    #[test]
+    fn test_sound_id_added_to_sound_event() {
+        let mut data = Vec::new();
+        for value in [0xF750u32, 0x5000_0001, 0x98] {
+            data.extend_from_slice(&value.to_le_bytes());
         }
-        // Serialize to get the serde-renamed version
-        return serde_json::to_value(&msg_type)
-            .ok()
-            .and_then(|v| v.as_str().map(|s| s.to_string()))
-            .unwrap_or_else(|| format!("{:?}", msg_type));
+        data.extend_from_slice(&1.0f32.to_le_bytes());
+
+        let mut parsed = serde_json::json!({"S2C": {"EffectsSoundEvent": {
+            "ObjectId": 0x5000_0001u32,
+            "SoundType": "TriggerActivated0",
+        }}});
+        add_sound_id(&mut parsed, &data);
+
+        assert_eq!(parsed["S2C"]["EffectsSoundEvent"]["SoundId"], 0x98);
+        assert_eq!(
+            parsed["S2C"]["EffectsSoundEvent"]["SoundType"],
+            "TriggerActivated0"
+        );
     }
 
-    "Unknown".to_string()
+    #[test]
+    fn test_short_input_is_an_error() {
+        assert!(parse_message(&[0xB0, 0xF7], 0).is_err());
+    }
+
+    #[test]
+    fn test_arbitrary_input_does_not_panic() {
+        // Known opcodes reach the field readers; the rest exercise the fallbacks
+        let opcodes: [u32; 6] = [0xF7B0, 0xF7B1, 0xF745, 0xF74C, 0x02CD, 0xDEAD];
+        let mut state = 0x2545_F491_4F6C_DD1D;
+
+        for _ in 0..2000 {
+            let opcode = opcodes[next_random(&mut state) as usize % opcodes.len()];
+            let len = next_random(&mut state) as usize % 96;
+            let mut data = opcode.to_le_bytes().to_vec();
+            data.extend((0..len).map(|_| next_random(&mut state) as u8));
+
+            parse_message_fuzz(&data);
+            for cut in 0..data.len().min(24) {
+                parse_message_fuzz(&data[..cut]);
+            }
+        }
+    }
 }
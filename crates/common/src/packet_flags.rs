@@ -1,75 +1,98 @@
 use acprotocol::network::packet::PacketHeaderFlags;
 
+/// Every header flag with its full name and the short form used in tables
+const FLAGS: [(PacketHeaderFlags, &str, &str); 22] = [
+    (
+        PacketHeaderFlags::RETRANSMISSION,
+        "Retransmission",
+        "Retrans",
+    ),
+    (
+        PacketHeaderFlags::ENCRYPTED_CHECKSUM,
+        "EncryptedChecksum",
+        "EncCksum",
+    ),
+    (
+        PacketHeaderFlags::BLOB_FRAGMENTS,
+        "BlobFragments",
+        "BlobFrag",
+    ),
+    (
+        PacketHeaderFlags::SERVER_SWITCH,
+        "ServerSwitch",
+        "SrvSwitch",
+    ),
+    (
+        PacketHeaderFlags::LOGON_SERVER_ADDR,
+        "LogonServerAddr",
+        "LogonAddr",
+    ),
+    (
+        PacketHeaderFlags::EMPTY_HEADER1,
+        "EmptyHeader1",
+        "EmptyHdr1",
+    ),
+    (PacketHeaderFlags::REFERRAL, "Referral", "Referral"),
+    (
+        PacketHeaderFlags::REQUEST_RETRANSMIT,
+        "RequestRetransmit",
+        "ReqRetrans",
+    ),
+    (
+        PacketHeaderFlags::REJECT_RETRANSMIT,
+        "RejectRetransmit",
+        "RejRetrans",
+    ),
+    (PacketHeaderFlags::ACK_SEQUENCE, "AckSequence", "Ack"),
+    (PacketHeaderFlags::DISCONNECT, "Disconnect", "Disc"),
+    (PacketHeaderFlags::LOGIN_REQUEST, "LoginRequest", "Login"),
+    (
+        PacketHeaderFlags::WORLD_LOGIN_REQUEST,
+        "WorldLoginRequest",
+        "WorldLogin",
+    ),
+    (
+        PacketHeaderFlags::CONNECT_REQUEST,
+        "ConnectRequest",
+        "ConnectReq",
+    ),
+    (
+        PacketHeaderFlags::CONNECT_RESPONSE,
+        "ConnectResponse",
+        "ConnectResp",
+    ),
+    (PacketHeaderFlags::NET_ERROR, "NetError", "NetErr"),
+    (
+        PacketHeaderFlags::NET_ERROR_DISCONNECT,
+        "NetErrorDisconnect",
+        "NetErrDisc",
+    ),
+    (PacketHeaderFlags::CICMD_COMMAND, "CICMDCommand", "CICMD"),
+    (PacketHeaderFlags::TIME_SYNC, "TimeSync", "TimeSync"),
+    (PacketHeaderFlags::ECHO_REQUEST, "EchoRequest", "EchoReq"),
+    (PacketHeaderFlags::ECHO_RESPONSE, "EchoResponse", "EchoResp"),
+    (PacketHeaderFlags::FLOW, "Flow", "Flow"),
+];
+
+/// Names of the flags set in a packet header, in bit order
+///
+/// Serialized alongside the raw header so the flags can be read without a
+/// reference to the bit values.
+pub fn packet_flag_names(flags: PacketHeaderFlags) -> Vec<&'static str> {
+    FLAGS
+        .iter()
+        .filter(|(flag, _, _)| flags.contains(*flag))
+        .map(|(_, name, _)| *name)
+        .collect()
+}
+
 /// Mapping of flag names to their short display forms for the TUI
 pub fn format_packet_flags(flags: PacketHeaderFlags) -> String {
-    let mut flag_names = Vec::new();
-
-    if flags.contains(PacketHeaderFlags::RETRANSMISSION) {
-        flag_names.push("Retrans");
-    }
-    if flags.contains(PacketHeaderFlags::ENCRYPTED_CHECKSUM) {
-        flag_names.push("EncCksum");
-    }
-    if flags.contains(PacketHeaderFlags::BLOB_FRAGMENTS) {
-        flag_names.push("BlobFrag");
-    }
-    if flags.contains(PacketHeaderFlags::SERVER_SWITCH) {
-        flag_names.push("SrvSwitch");
-    }
-    if flags.contains(PacketHeaderFlags::LOGON_SERVER_ADDR) {
-        flag_names.push("LogonAddr");
-    }
-    if flags.contains(PacketHeaderFlags::EMPTY_HEADER1) {
-        flag_names.push("EmptyHdr1");
-    }
-    if flags.contains(PacketHeaderFlags::REFERRAL) {
-        flag_names.push("Referral");
-    }
-    if flags.contains(PacketHeaderFlags::REQUEST_RETRANSMIT) {
-        flag_names.push("ReqRetrans");
-    }
-    if flags.contains(PacketHeaderFlags::REJECT_RETRANSMIT) {
-        flag_names.push("RejRetrans");
-    }
-    if flags.contains(PacketHeaderFlags::ACK_SEQUENCE) {
-        flag_names.push("Ack");
-    }
-    if flags.contains(PacketHeaderFlags::DISCONNECT) {
-        flag_names.push("Disc");
-    }
-    if flags.contains(PacketHeaderFlags::LOGIN_REQUEST) {
-        flag_names.push("Login");
-    }
-    if flags.contains(PacketHeaderFlags::WORLD_LOGIN_REQUEST) {
-        flag_names.push("WorldLogin");
-    }
-    if flags.contains(PacketHeaderFlags::CONNECT_REQUEST) {
-        flag_names.push("ConnectReq");
-    }
-    if flags.contains(PacketHeaderFlags::CONNECT_RESPONSE) {
-        flag_names.push("ConnectResp");
-    }
-    if flags.contains(PacketHeaderFlags::NET_ERROR) {
-        flag_names.push("NetErr");
-    }
-    if flags.contains(PacketHeaderFlags::NET_ERROR_DISCONNECT) {
-        flag_names.push("NetErrDisc");
-    }
-    if flags.contains(PacketHeaderFlags::CICMD_COMMAND) {
-        flag_names.push("CICMD");
-    }
-    if flags.contains(PacketHeaderFlags::TIME_SYNC) {
-        flag_names.push("TimeSync");
-    }
-    if flags.contains(PacketHeaderFlags::ECHO_REQUEST) {
-        flag_names.push("EchoReq");
-    }
-    if flags.contains(PacketHeaderFlags::ECHO_RESPONSE) {
-        flag_names.push("EchoResp");
-    }
-    if flags.contains(PacketHeaderFlags::FLOW) {
-        flag_names.push("Flow");
-    }
+    let flag_names: Vec<&str> = FLAGS
+        .iter()
+        .filter(|(flag, _, _)| flags.contains(*flag))
+        .map(|(_, _, short)| *short)
+        .collect();
 
     if flag_names.is_empty() {
         "None".to_string()
@@ -77,3 +100,20 @@ pub fn format_packet_flags(flags: PacketHeaderFlags) -> String {
         flag_names.join("|")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packet_flag_names() {
+        let flags = PacketHeaderFlags::BLOB_FRAGMENTS | PacketHeaderFlags::ACK_SEQUENCE;
+        assert_eq!(
+            packet_flag_names(flags),
+            vec!["BlobFragments", "AckSequence"]
+        );
+        assert_eq!(format_packet_flags(flags), "BlobFrag|Ack");
+        assert!(packet_flag_names(PacketHeaderFlags::empty()).is_empty());
+        assert_eq!(format_packet_flags(PacketHeaderFlags::empty()), "None");
+    }
+}
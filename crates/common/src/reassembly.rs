@@ -0,0 +1,510 @@
+//! Reassembly of AC messages from the fragments in UDP payloads
+//!
+//! [`FragmentReassembler`] holds the fragments still waiting for chunks, so it
+//! can be fed payloads from any source: a PCAP file, a live socket or a proxy.
+//! [`PacketParser`](crate::PacketParser) uses one internally for each capture.
+
+use crate::{FragmentInfo, FragmentStats, ParsedPacket, echo, messages, packet_flags};
+use acprotocol::network::packet::{PacketHeader, PacketHeaderFlags};
+use acprotocol::network::reader::BinaryReader;
+use acprotocol::unified::Direction;
+use anyhow::Result;
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use std::collections::HashMap;
+
+/// Largest chunk of a message carried by one fragment
+const CHUNK_SIZE: usize = 448;
+
+/// A message being reassembled from its fragment chunks
+///
+/// Each fragment has a 16-byte header (sequence, id, count, size, index,
+/// group) followed by one chunk of the message. `size` includes the header.
+/// Every chunk but the last is a full [`CHUNK_SIZE`] bytes and chunk `index`
+/// sits at `index * CHUNK_SIZE` in the message. Chunks of a message larger than
+/// one datagram travel in separate packets and can arrive in any order.
+///
+/// `count` is taken from the first chunk and later chunks must agree with it.
+/// It comes straight from the wire, so the buffer grows as chunks arrive
+/// rather than being sized for every chunk up front.
+#[derive(Clone, Debug)]
+struct Fragment {
+    count: u16,
+    data: Vec<u8>,
+    length: usize,
+    /// Number of distinct chunks received so far
+    received: usize,
+    /// Which chunk indices have arrived, so repeats aren't counted twice
+    chunks: Vec<bool>,
+    packet_ids: Vec<usize>,
+    /// Header sequence of the first packet that carried a chunk
+    packet_sequence: u32,
+}
+
+impl Fragment {
+    fn new(count: u16, packet_sequence: u32) -> Self {
+        Self {
+            count,
+            data: Vec::new(),
+            length: 0,
+            received: 0,
+            chunks: vec![false; count as usize],
+            packet_ids: Vec::new(),
+            packet_sequence,
+        }
+    }
+
+    fn add_chunk(&mut self, data: &[u8], index: usize) {
+        let start = index * CHUNK_SIZE;
+        let end = start + data.len();
+        if end <= self.chunks.len() * CHUNK_SIZE && index < self.chunks.len() {
+            if end > self.data.len() {
+                self.data.resize(end, 0);
+            }
+            self.data[start..end].copy_from_slice(data);
+            if end > self.length {
+                self.length = end;
+            }
+            if !self.chunks[index] {
+                self.chunks[index] = true;
+                self.received += 1;
+            }
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.received >= self.count as usize
+    }
+}
+
+/// Identifies a fragment still waiting for chunks: whether the client sent it,
+/// and its fragment sequence. Client and server number their fragments
+/// independently, so the same sequence is in use in both directions.
+type PendingKey = (bool, u32);
+
+/// Turns AC packet payloads into messages, keeping partial fragments between calls
+pub struct FragmentReassembler {
+    pending: HashMap<PendingKey, Fragment>,
+    /// Whether to keep message raw_bytes and packet raw_payload
+    pub(crate) keep_raw_bytes: bool,
+    stats: FragmentStats,
+    next_packet_id: usize,
+    next_message_id: usize,
+}
+
+impl Default for FragmentReassembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FragmentReassembler {
+    pub fn new() -> Self {
+        Self {
+            pending: HashMap::new(),
+            keep_raw_bytes: true,
+            stats: FragmentStats::default(),
+            next_packet_id: 0,
+            next_message_id: 0,
+        }
+    }
+
+    /// Choose whether messages keep their raw bytes; defaults to on
+    pub fn with_raw_bytes(mut self, keep: bool) -> Self {
+        self.keep_raw_bytes = keep;
+        self
+    }
+
+    /// Parse one UDP payload, returning the messages it completed
+    ///
+    /// `payload` holds one or more AC packets as sent in a datagram. Message ids
+    /// count up from 0, and `packet_ids` count the AC packets pushed so far. A
+    /// payload that isn't a valid AC packet yields nothing.
+    pub fn push(
+        &mut self,
+        payload: &[u8],
+        direction: Direction,
+        timestamp: f64,
+    ) -> Vec<messages::ParsedMessage> {
+        let (mut packet_id, mut message_id) = (self.next_packet_id, self.next_message_id);
+        let messages = self
+            .parse_packet(
+                payload,
+                direction,
+                timestamp,
+                &mut packet_id,
+                &mut message_id,
+            )
+            .map(|(_, messages)| messages)
+            .unwrap_or_default();
+        (self.next_packet_id, self.next_message_id) = (packet_id, message_id);
+        messages
+    }
+
+    /// Fragment completion counts since the last [`reset_stats`](Self::reset_stats)
+    pub fn stats(&self) -> &FragmentStats {
+        &self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = FragmentStats::default();
+    }
+
+    /// Drop fragments still waiting for chunks
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Add the fragments still waiting for chunks to the pending counts
+    pub(crate) fn count_pending(&mut self) {
+        for &(sent, _) in self.pending.keys() {
+            if sent {
+                self.stats.pending_send += 1;
+            } else {
+                self.stats.pending_recv += 1;
+            }
+        }
+    }
+
+    pub(crate) fn parse_packet(
+        &mut self,
+        data: &[u8],
+        direction: Direction,
+        timestamp: f64,
+        packet_id: &mut usize,
+        message_id: &mut usize,
+    ) -> Result<(Vec<ParsedPacket>, Vec<messages::ParsedMessage>)> {
+        let mut packets = Vec::new();
+        let mut all_messages = Vec::new();
+        let mut reader = BinaryReader::new(data);
+
+        while reader.remaining() > 0 {
+            let start_pos = reader.position();
+
+            let header = PacketHeader::parse(&mut reader)?;
+
+            // A header claiming more data than was captured must not move the cursor
+            // past the end of the buffer
+            let packet_end =
+                (start_pos + PacketHeader::BASE_SIZE + header.size as usize).min(data.len());
+            let payload_start = reader.position();
+            let payload_size = packet_end.saturating_sub(payload_start);
+
+            // Capture raw payload bytes
+            let raw_payload = if self.keep_raw_bytes && payload_size > 0 {
+                data[payload_start..payload_start + payload_size].to_vec()
+            } else {
+                Vec::new()
+            };
+
+            let direction_str = match direction {
+                Direction::ClientToServer => "Send".to_string(),
+                Direction::ServerToClient => "Recv".to_string(),
+            };
+
+            let echo = echo::read_echo_headers(header.flags, &data[payload_start..packet_end]);
+
+            let mut parsed_packet = ParsedPacket {
+                header: header.clone(),
+                flag_names: packet_flags::packet_flag_names(header.flags),
+                direction: direction_str,
+                messages: Vec::new(),
+                fragment: None,
+                id: *packet_id,
+                timestamp,
+                ambiguous_direction: false,
+                echo_request: echo.request,
+                echo_response: echo.response,
+                raw_payload,
+            };
+            *packet_id += 1;
+
+            if header.flags.contains(PacketHeaderFlags::BLOB_FRAGMENTS) {
+                // Fragments are read from this packet's bytes only, so a fragment
+                // whose size disagrees with the header can't run into the next
+                // packet in the datagram
+                let mut fragments = BinaryReader::new(&data[reader.position()..packet_end]);
+                while fragments.remaining() > 0 {
+                    let offset = reader.position() + fragments.position();
+                    match self.parse_fragment(
+                        &mut fragments,
+                        direction,
+                        timestamp,
+                        parsed_packet.id,
+                        header.sequence,
+                        message_id,
+                    ) {
+                        Ok((frag_info, msgs)) => {
+                            parsed_packet.fragment = Some(frag_info);
+                            for msg in msgs {
+                                parsed_packet.messages.push(msg.data.clone());
+                                all_messages.push(msg);
+                            }
+                        }
+                        Err(e) => {
+                            tracing::trace!(
+                                packet = parsed_packet.id,
+                                sequence = header.sequence,
+                                offset,
+                                "malformed fragment: {e:#}"
+                            );
+                            break;
+                        }
+                    }
+                }
+            }
+
+            // The header size decides where the next packet starts, whatever the
+            // fragments consumed
+            reader.set_position(packet_end);
+
+            packets.push(parsed_packet);
+        }
+
+        Ok((packets, all_messages))
+    }
+
+    fn parse_fragment(
+        &mut self,
+        reader: &mut BinaryReader,
+        direction: Direction,
+        timestamp: f64,
+        packet_id: usize,
+        packet_sequence: u32,
+        message_id: &mut usize,
+    ) -> Result<(FragmentInfo, Vec<messages::ParsedMessage>)> {
+        let mut parsed_messages = Vec::new();
+
+        let sequence = reader.read_u32()?;
+        let _id = reader.read_u32()?;
+        let count = reader.read_u16()?;
+        let size = reader.read_u16()?;
+        let index = reader.read_u16()?;
+        let _group = reader.read_u16()?;
+
+        if size < 16 {
+            anyhow::bail!("Invalid fragment size: {size}");
+        }
+
+        let frag_length = size as usize - 16;
+
+        if reader.remaining() < frag_length {
+            anyhow::bail!("Fragment data too short");
+        }
+
+        let bytes = reader.read_bytes(frag_length)?;
+
+        let sent = matches!(direction, Direction::ClientToServer);
+        let key = (sent, sequence);
+        let mut frag_info = FragmentInfo {
+            data: String::new(),
+            count,
+            received: 0,
+            length: 0,
+            sequence,
+        };
+
+        if index >= count {
+            tracing::trace!(
+                fragment = sequence,
+                index,
+                count,
+                "chunk index out of range"
+            );
+            return Ok((frag_info, parsed_messages));
+        }
+
+        let fragment = self
+            .pending
+            .entry(key)
+            .or_insert_with(|| Fragment::new(count, packet_sequence));
+
+        if fragment.count != count {
+            // Keep the count the fragment started with; a chunk that disagrees
+            // can't be placed reliably
+            tracing::trace!(
+                fragment = sequence,
+                count,
+                expected = fragment.count,
+                "chunk count disagrees with earlier chunks"
+            );
+            frag_info.count = fragment.count;
+            frag_info.received = fragment.received;
+            frag_info.length = fragment.length;
+            return Ok((frag_info, parsed_messages));
+        }
+
+        fragment.add_chunk(&bytes, index as usize);
+        if fragment.packet_ids.last() != Some(&packet_id) {
+            fragment.packet_ids.push(packet_id);
+        }
+
+        frag_info.received = fragment.received;
+        frag_info.length = fragment.length;
+
+        if fragment.is_complete()
+            && let Some(fragment) = self.pending.remove(&key)
+        {
+            let mut frag_data = fragment.data;
+            frag_data.truncate(fragment.length);
+            frag_info.data = BASE64.encode(&frag_data);
+
+            if sent {
+                self.stats.completed_send += 1;
+            } else {
+                self.stats.completed_recv += 1;
+            }
+            *self.stats.chunk_counts.entry(count).or_default() += 1;
+
+            match messages::parse_message_with_direction(&frag_data, *message_id, direction) {
+                Ok(mut parsed) => {
+                    if let Some(error) = parsed.data.get("error") {
+                        tracing::debug!(
+                            message = *message_id,
+                            opcode = %parsed.opcode,
+                            fragment = sequence,
+                            "message failed to decode: {}",
+                            error.as_str().unwrap_or_default()
+                        );
+                    }
+                    parsed.direction = match direction {
+                        Direction::ClientToServer => "Send".to_string(),
+                        Direction::ServerToClient => "Recv".to_string(),
+                    };
+                    parsed.timestamp = timestamp;
+                    parsed.packet_ids = fragment.packet_ids;
+                    parsed.packet_sequence = fragment.packet_sequence;
+                    parsed.fragment_sequence = sequence;
+                    if !self.keep_raw_bytes {
+                        parsed.raw_bytes = Vec::new();
+                    }
+                    parsed_messages.push(parsed);
+                    *message_id += 1;
+                }
+                Err(e) => {
+                    // Skip failed messages
+                    tracing::debug!(
+                        fragment = sequence,
+                        bytes = frag_data.len(),
+                        "dropped message: {e:#}"
+                    );
+                }
+            }
+        }
+
+        Ok((frag_info, parsed_messages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{ac_fragment_packet, game_event};
+
+    #[test]
+    fn test_push_reassembles_payloads() {
+        let mut first_chunk = game_event(0x00C9);
+        first_chunk.resize(448, 0);
+        let mut reassembler = FragmentReassembler::new();
+
+        let last = ac_fragment_packet(1, 5, 2, 1, &[0u8; 8]);
+        assert!(
+            reassembler
+                .push(&last, Direction::ServerToClient, 1.0)
+                .is_empty()
+        );
+        let first = ac_fragment_packet(2, 5, 2, 0, &first_chunk);
+        let messages = reassembler.push(&first, Direction::ServerToClient, 2.0);
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].direction, "Recv");
+        assert_eq!(messages[0].packet_ids, vec![0, 1]);
+        assert_eq!(messages[0].timestamp, 2.0);
+        assert_eq!(reassembler.stats().completed_recv, 1);
+
+        assert!(
+            reassembler
+                .push(b"not a packet", Direction::ServerToClient, 3.0)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_directions_keep_separate_fragments() {
+        // Client and server both use fragment sequence 5, with their chunks
+        // interleaved; neither may pick up the other's chunks
+        let mut first_chunk = game_event(0x00C9);
+        first_chunk.resize(448, 0);
+        let mut reassembler = FragmentReassembler::new();
+
+        let pushes = [
+            (
+                Direction::ClientToServer,
+                ac_fragment_packet(1, 5, 2, 0, &first_chunk),
+            ),
+            (
+                Direction::ServerToClient,
+                ac_fragment_packet(1, 5, 2, 0, &first_chunk),
+            ),
+            (
+                Direction::ServerToClient,
+                ac_fragment_packet(2, 5, 2, 1, &[0u8; 8]),
+            ),
+        ];
+        let mut messages = Vec::new();
+        for (direction, payload) in &pushes {
+            messages.extend(reassembler.push(payload, *direction, 1.0));
+        }
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].direction, "Recv");
+        assert_eq!(messages[0].packet_ids, vec![1, 2]);
+        assert_eq!(messages[0].size, 448 + 8);
+
+        reassembler.count_pending();
+        let stats = reassembler.stats();
+        assert_eq!((stats.completed_send, stats.completed_recv), (0, 1));
+        assert_eq!((stats.pending_send, stats.pending_recv), (1, 0));
+
+        let last = ac_fragment_packet(2, 5, 2, 1, &[0u8; 20]);
+        let messages = reassembler.push(&last, Direction::ClientToServer, 2.0);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].direction, "Send");
+        assert_eq!(messages[0].packet_ids, vec![0, 3]);
+        assert_eq!(messages[0].size, 448 + 20);
+        assert_eq!(reassembler.stats().completed_send, 1);
+    }
+
+    #[test]
+    fn test_chunks_with_a_different_count_are_dropped() {
+        let mut first_chunk = game_event(0x00C9);
+        first_chunk.resize(448, 0);
+        let mut reassembler = FragmentReassembler::new();
+
+        let pushes = [
+            ac_fragment_packet(1, 5, 3, 0, &first_chunk),
+            // Would complete the fragment early if it could lower the count
+            ac_fragment_packet(2, 5, 2, 1, &first_chunk),
+            // Out of range for the fragment's count
+            ac_fragment_packet(3, 5, 3, 3, &[0u8; 8]),
+        ];
+        for payload in &pushes {
+            assert!(
+                reassembler
+                    .push(payload, Direction::ServerToClient, 1.0)
+                    .is_empty()
+            );
+        }
+
+        for (sequence, index) in [(4, 1), (5, 2)] {
+            let chunk = if index == 2 {
+                &[0u8; 8][..]
+            } else {
+                &first_chunk
+            };
+            let payload = ac_fragment_packet(sequence, 5, 3, index, chunk);
+            let messages = reassembler.push(&payload, Direction::ServerToClient, 2.0);
+            assert_eq!(messages.len(), usize::from(index == 2));
+        }
+        assert_eq!(reassembler.stats().chunk_counts.get(&3), Some(&1));
+    }
+}
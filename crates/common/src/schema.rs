@@ -0,0 +1,256 @@
+//! JSON Schema for the serialized parse output
+//!
+//! Describes `ParsedMessage`, `ParsedPacket` and `Weenie` as they are written by
+//! serde, for downstream tools that consume exported captures. Decoded message
+//! payloads (`Data`) and packet headers come from acprotocol and are left open.
+//!
+//! The schema is written by hand because those acprotocol types can't derive
+//! one. `tests/schema_tests.rs` validates a real export against it, so a
+//! serde change that isn't reflected here fails the tests. The desktop app
+//! saves it from File > Export JSON Schema.
+
+use serde_json::{Value, json};
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Schema document with `ParsedMessage`, `ParsedPacket` and `Weenie` definitions
+///
+/// The root describes the `{messages, packets, weenies}` document written by
+/// [`crate::export::write_capture_json`].
+pub fn capture_schema() -> Value {
+    json!({
+        "$schema": DRAFT,
+        "title": "AC PCAP capture",
+        "type": "object",
+        "properties": {
+            "messages": {"type": "array", "items": {"$ref": "#/$defs/ParsedMessage"}},
+            "packets": {"type": "array", "items": {"$ref": "#/$defs/ParsedPacket"}},
+            "weenies": {"type": "array", "items": {"$ref": "#/$defs/Weenie"}},
+        },
+        "required": ["messages", "packets", "weenies"],
+        "$defs": {
+            "ParsedMessage": parsed_message_schema(),
+            "ParsedPacket": parsed_packet_schema(),
+            "Weenie": weenie_schema(),
+        },
+    })
+}
+
+fn integer() -> Value {
+    json!({"type": "integer", "minimum": 0})
+}
+
+fn hex_id() -> Value {
+    json!({"type": "string", "pattern": "^0x[0-9A-F]{8}$"})
+}
+
+fn direction() -> Value {
+    json!({"enum": ["Send", "Recv"]})
+}
+
+fn map_of(values: Value) -> Value {
+    json!({"type": "object", "additionalProperties": values})
+}
+
+fn parsed_message_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "Id": integer(),
+            "Type": {"type": "string"},
+            "Kind": {
+                "description": "Protocol enum the type name came from",
+                "type": "object",
+                "oneOf": [
+                    {"required": ["C2S"], "properties": {"C2S": {"type": "string"}}},
+                    {"required": ["S2C"], "properties": {"S2C": {"type": "string"}}},
+                    {"required": ["GameAction"], "properties": {"GameAction": {"type": "string"}}},
+                    {"required": ["GameEvent"], "properties": {"GameEvent": {"type": "string"}}},
+                    {"required": ["Unknown"], "properties": {"Unknown": integer()}},
+                ],
+            },
+            "Data": {"description": "Decoded message, shaped by its type"},
+            "Direction": direction(),
+            "OpCode": {"type": "string", "pattern": "^[0-9A-F]{4,8}$"},
+            "Timestamp": {"type": "number"},
+            "PacketIds": {"type": "array", "items": integer()},
+            "PacketSequence": integer(),
+            "FragmentSequence": integer(),
+            "Size": integer(),
+        },
+        "required": [
+            "Id", "Type", "Kind", "Data", "Direction", "OpCode", "Timestamp",
+            "PacketIds", "PacketSequence", "FragmentSequence", "Size",
+        ],
+        "additionalProperties": false,
+    })
+}
+
+fn parsed_packet_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "Header": {"description": "AC packet header", "type": "object"},
+            "FlagNames": {
+                "description": "Names of the flags set in the header",
+                "type": "array",
+                "items": {"type": "string"},
+            },
+            "Direction": direction(),
+            "Messages": {"type": "array", "items": {"description": "Decoded message data"}},
+            "Fragment": {
+                "oneOf": [
+                    {"type": "null"},
+                    {
+                        "type": "object",
+                        "properties": {
+                            "Data": {"type": "string", "contentEncoding": "base64"},
+                            "Count": integer(),
+                            "Received": integer(),
+                            "Length": integer(),
+                            "Sequence": integer(),
+                        },
+                        "required": ["Data", "Count", "Received", "Length", "Sequence"],
+                    },
+                ],
+            },
+            "Id": integer(),
+            "Timestamp": {"type": "number"},
+            "AmbiguousDirection": {"type": "boolean"},
+            "EchoRequest": {"type": "number"},
+            "EchoResponse": {
+                "type": "object",
+                "properties": {
+                    "ClientTime": {"type": "number"},
+                    "HoldingTime": {"type": "number"},
+                },
+                "required": ["ClientTime", "HoldingTime"],
+            },
+        },
+        "required": [
+            "Header", "FlagNames", "Direction", "Messages", "Fragment", "Id", "Timestamp",
+        ],
+        "additionalProperties": false,
+    })
+}
+
+fn weenie_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "ObjectId": integer(),
+            "Name": {"type": "string"},
+            "IntProperties": map_of(json!({"type": "integer"})),
+            "Int64Properties": map_of(json!({"type": "integer"})),
+            "BoolProperties": map_of(json!({"type": "boolean"})),
+            "FloatProperties": map_of(json!({"type": "number"})),
+            "StringProperties": map_of(json!({"type": "string"})),
+            "DataIdProperties": map_of(hex_id()),
+            "InstanceIdProperties": map_of(hex_id()),
+            "Enchantments": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "SpellId": integer(),
+                        "Layer": integer(),
+                        "CasterId": hex_id(),
+                        "Added": {"type": "number"},
+                        "Duration": {"type": "number"},
+                        "Removed": {"type": "number"},
+                    },
+                    "required": ["SpellId", "Layer", "CasterId", "Added", "Duration"],
+                },
+            },
+            "FirstSeen": {"type": "number"},
+            "LastUpdated": {"type": "number"},
+            "MessageCount": integer(),
+            "MessageIds": {"type": "array", "items": integer()},
+        },
+        "required": ["ObjectId", "FirstSeen", "LastUpdated", "MessageCount"],
+        "additionalProperties": false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{MessageKind, ParsedMessage};
+    use crate::weenie::{Enchantment, EnchantmentChange, Weenie, WeenieUpdate};
+
+    /// Check that `value` only has properties the schema lists and has all required ones
+    fn assert_fields_match(schema: &Value, value: &Value) {
+        let properties = schema["properties"].as_object().unwrap();
+        let object = value.as_object().unwrap();
+        for key in object.keys() {
+            assert!(properties.contains_key(key), "{key} missing from schema");
+        }
+        for key in schema["required"].as_array().unwrap() {
+            assert!(
+                object.contains_key(key.as_str().unwrap()),
+                "{key} not serialized"
+            );
+        }
+    }
+
+    #[test]
+    fn test_message_schema_matches_serialized_fields() {
+        let message = ParsedMessage {
+            id: 1,
+            message_type: "Unknown".to_string(),
+            kind: MessageKind::Unknown(0xDEAD),
+            data: json!({}),
+            direction: "Recv".to_string(),
+            opcode: "DEAD".to_string(),
+            timestamp: 1.0,
+            packet_ids: vec![0],
+            packet_sequence: 1,
+            fragment_sequence: 1,
+            ..Default::default()
+        };
+
+        assert_fields_match(
+            &parsed_message_schema(),
+            &serde_json::to_value(&message).unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_weenie_schema_matches_serialized_fields() {
+        let mut update = WeenieUpdate::new(0x8000_0001, 1.0, 0);
+        update.name = Some("Pack".to_string());
+        update.int_properties.insert("Value".to_string(), 10);
+        update.int64_properties.insert("TotalXp".to_string(), 10);
+        update.bool_properties.insert("Open".to_string(), true);
+        update.float_properties.insert("Scale".to_string(), 1.0);
+        update
+            .string_properties
+            .insert("Use".to_string(), "x".to_string());
+        update.data_id_properties.insert("Icon".to_string(), 1);
+        update
+            .instance_id_properties
+            .insert("Container".to_string(), 2);
+        update
+            .enchantment_changes
+            .push(EnchantmentChange::Add(Enchantment {
+                spell_id: 1,
+                layer: 1,
+                caster_id: 2,
+                added: 1.0,
+                duration: 60.0,
+                removed: Some(2.0),
+            }));
+        let mut weenie = Weenie::new(0x8000_0001, 1.0, 0);
+        weenie.update(update);
+
+        assert_fields_match(&weenie_schema(), &serde_json::to_value(&weenie).unwrap());
+    }
+
+    #[test]
+    fn test_capture_schema_references_definitions() {
+        let schema = capture_schema();
+        for name in ["ParsedMessage", "ParsedPacket", "Weenie"] {
+            assert!(schema["$defs"][name].is_object());
+        }
+    }
+}
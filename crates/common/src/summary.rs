@@ -0,0 +1,163 @@
+//! Headline numbers for a parsed capture
+//!
+//! [`CaptureSummary`] serializes as JSON for dashboards and prints as text
+//! through its `Display` impl, so both come from the same counts.
+
+use crate::messages::ParsedMessage;
+use crate::weenie::WeenieDatabase;
+use crate::{CaptureInfo, ParsedPacket};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// How many message types a summary lists unless told otherwise
+pub const DEFAULT_TOP_TYPES: usize = 20;
+
+/// Number of messages of one type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TypeCount {
+    #[serde(rename = "Type")]
+    pub message_type: String,
+    #[serde(rename = "Count")]
+    pub count: usize,
+}
+
+/// Totals, direction splits and the most frequent message types of a capture
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CaptureSummary {
+    #[serde(rename = "Packets")]
+    pub packets: usize,
+    #[serde(rename = "SentPackets")]
+    pub sent_packets: usize,
+    #[serde(rename = "ReceivedPackets")]
+    pub received_packets: usize,
+    #[serde(rename = "Messages")]
+    pub messages: usize,
+    #[serde(rename = "SentMessages")]
+    pub sent_messages: usize,
+    #[serde(rename = "ReceivedMessages")]
+    pub received_messages: usize,
+    #[serde(rename = "Weenies")]
+    pub weenies: usize,
+    /// Time span of the packets; `None` for an empty capture
+    #[serde(rename = "Capture")]
+    pub capture: Option<CaptureInfo>,
+    /// Most frequent message types, most frequent first
+    #[serde(rename = "TopMessageTypes")]
+    pub top_message_types: Vec<TypeCount>,
+}
+
+/// Summarize a capture, listing its `top` most frequent message types
+///
+/// Types with the same count are listed alphabetically.
+pub fn capture_summary(
+    packets: &[ParsedPacket],
+    messages: &[ParsedMessage],
+    weenie_db: &WeenieDatabase,
+    top: usize,
+) -> CaptureSummary {
+    let sent_packets = packets.iter().filter(|p| p.direction == "Send").count();
+    let sent_messages = messages.iter().filter(|m| m.direction == "Send").count();
+
+    let mut type_counts: HashMap<&str, usize> = HashMap::new();
+    for message in messages {
+        *type_counts.entry(&message.message_type).or_default() += 1;
+    }
+    let mut top_message_types: Vec<TypeCount> = type_counts
+        .into_iter()
+        .map(|(message_type, count)| TypeCount {
+            message_type: message_type.to_string(),
+            count,
+        })
+        .collect();
+    top_message_types.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.message_type.cmp(&b.message_type))
+    });
+    top_message_types.truncate(top);
+
+    CaptureSummary {
+        packets: packets.len(),
+        sent_packets,
+        received_packets: packets.len() - sent_packets,
+        messages: messages.len(),
+        sent_messages,
+        received_messages: messages.len() - sent_messages,
+        weenies: weenie_db.count(),
+        capture: CaptureInfo::from_packets(packets),
+        top_message_types,
+    }
+}
+
+impl fmt::Display for CaptureSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Packets: {} ({} sent, {} received)",
+            self.packets, self.sent_packets, self.received_packets
+        )?;
+        writeln!(
+            f,
+            "Messages: {} ({} sent, {} received)",
+            self.messages, self.sent_messages, self.received_messages
+        )?;
+        writeln!(f, "Weenies: {}", self.weenies)?;
+        if let Some(capture) = &self.capture {
+            writeln!(f, "Duration: {:.3}s", capture.duration_secs)?;
+        }
+        if !self.top_message_types.is_empty() {
+            writeln!(f, "Top message types:")?;
+            for entry in &self.top_message_types {
+                writeln!(f, "  {:>8}  {}", entry.count, entry.message_type)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(message_type: &str, direction: &str) -> ParsedMessage {
+        ParsedMessage {
+            message_type: message_type.to_string(),
+            direction: direction.to_string(),
+            opcode: "F7B0".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_capture_summary() {
+        let messages = vec![
+            message("Movement_SetObjectMovement", "Recv"),
+            message("Magic_UpdateEnchantment", "Recv"),
+            message("Movement_SetObjectMovement", "Recv"),
+            message("Communication_Talk", "Send"),
+        ];
+        let summary = capture_summary(&[], &messages, &WeenieDatabase::new(), 2);
+
+        assert_eq!((summary.sent_messages, summary.received_messages), (1, 3));
+        assert_eq!(summary.capture, None);
+        let top: Vec<(&str, usize)> = summary
+            .top_message_types
+            .iter()
+            .map(|t| (t.message_type.as_str(), t.count))
+            .collect();
+        assert_eq!(
+            top,
+            vec![("Movement_SetObjectMovement", 2), ("Communication_Talk", 1)]
+        );
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["Messages"], 4);
+        assert_eq!(json["TopMessageTypes"][0]["Count"], 2);
+        assert!(
+            summary
+                .to_string()
+                .contains("Messages: 4 (1 sent, 3 received)")
+        );
+    }
+}
@@ -99,3 +99,90 @@ pub fn collect_all_expandable_paths(node: &TreeNode, path: String, expanded: &mu
         collect_all_expandable_paths(child, current_path.clone(), expanded);
     }
 }
+
+/// Render a JSON value as an indented key/value text tree for plain text output
+///
+/// Mirrors the UI's Tree view: objects and arrays become nested blocks, and
+/// numeric ids (keys ending in `Id`/`_id`) are shown as hex with the decimal
+/// value alongside.
+pub fn render_text_tree(value: &Value) -> String {
+    let mut out = String::new();
+    match value {
+        Value::Object(_) | Value::Array(_) => write_children(&mut out, value, 0),
+        _ => out.push_str(&format_leaf("", value)),
+    }
+    out
+}
+
+fn write_children(out: &mut String, value: &Value, depth: usize) {
+    match value {
+        Value::Object(obj) => {
+            for (key, child) in obj {
+                write_entry(out, key, child, depth);
+            }
+        }
+        Value::Array(arr) => {
+            for (i, child) in arr.iter().enumerate() {
+                write_entry(out, &format!("[{i}]"), child, depth);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn write_entry(out: &mut String, key: &str, value: &Value, depth: usize) {
+    let prefix = "  ".repeat(depth);
+    match value {
+        Value::Object(obj) if !obj.is_empty() => {
+            out.push_str(&format!("{prefix}{key}:\n"));
+            write_children(out, value, depth + 1);
+        }
+        Value::Array(arr) if !arr.is_empty() => {
+            out.push_str(&format!("{prefix}{key}: [{}]\n", arr.len()));
+            write_children(out, value, depth + 1);
+        }
+        _ => out.push_str(&format!("{prefix}{key}: {}\n", format_leaf(key, value))),
+    }
+}
+
+fn format_leaf(key: &str, value: &Value) -> String {
+    let is_id = key.ends_with("Id") || key.ends_with("_id");
+    match value.as_u64() {
+        Some(num) if is_id => format!("0x{num:08X} ({num})"),
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_text_tree_nests_and_hexes_ids() {
+        let value = json!({
+            "Empty": {},
+            "Items": [1, {"ContainerId": 255}],
+            "Key": "Value",
+            "ObjectId": 0x5000_0001u32,
+            "Value": 10,
+        });
+
+        let expected = "\
+Empty: {}
+Items: [2]
+  [0]: 1
+  [1]:
+    ContainerId: 0x000000FF (255)
+Key: \"Value\"
+ObjectId: 0x50000001 (1342177281)
+Value: 10
+";
+        assert_eq!(render_text_tree(&value), expected);
+    }
+
+    #[test]
+    fn test_render_text_tree_scalar() {
+        assert_eq!(render_text_tree(&json!(42)), "42");
+    }
+}
@@ -5,8 +5,10 @@
 //! information about objects, we maintain a database that merges all information
 //! seen about each object throughout the PCAP.
 
+use acprotocol::enums::ItemType;
+use bitflags::Flags;
 use serde::{Serialize, Serializer};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// A weenie (game object) with all its accumulated properties
 #[derive(Debug, Clone, Serialize)]
@@ -40,16 +42,25 @@ pub struct Weenie {
     pub string_properties: HashMap<String, String>,
 
     /// DataId properties (e.g., Icon, Sound IDs)
-    #[serde(rename = "DataIdProperties", skip_serializing_if = "HashMap::is_empty")]
+    #[serde(
+        rename = "DataIdProperties",
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_hex_ids"
+    )]
     pub data_id_properties: HashMap<String, u32>,
 
     /// Instance ID properties (references to other objects)
     #[serde(
         rename = "InstanceIdProperties",
-        skip_serializing_if = "HashMap::is_empty"
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "serialize_hex_ids"
     )]
     pub instance_id_properties: HashMap<String, u32>,
 
+    /// Enchantments applied to this object over the capture, in the order seen
+    #[serde(rename = "Enchantments", skip_serializing_if = "Vec::is_empty")]
+    pub enchantments: Vec<Enchantment>,
+
     /// Metadata: first seen timestamp
     #[serde(rename = "FirstSeen")]
     pub first_seen: f64,
@@ -80,6 +91,7 @@ impl Weenie {
             string_properties: HashMap::new(),
             data_id_properties: HashMap::new(),
             instance_id_properties: HashMap::new(),
+            enchantments: Vec::new(),
             first_seen: timestamp,
             last_updated: timestamp,
             message_count: 1,
@@ -124,7 +136,125 @@ impl Weenie {
         for (key, value) in update.instance_id_properties {
             self.instance_id_properties.insert(key, value);
         }
+
+        for change in update.enchantment_changes {
+            self.apply_enchantment_change(change, update.timestamp);
+        }
+    }
+
+    fn apply_enchantment_change(&mut self, change: EnchantmentChange, timestamp: f64) {
+        let open = self.enchantments.iter_mut().filter(|e| e.removed.is_none());
+        match change {
+            EnchantmentChange::Add(enchantment) => {
+                // A recast replaces the existing enchantment on the same layer
+                for existing in
+                    open.filter(|e| e.same_spell(enchantment.spell_id, enchantment.layer))
+                {
+                    existing.removed = Some(timestamp);
+                }
+                self.enchantments.push(enchantment);
+            }
+            EnchantmentChange::Remove { spell_id, layer } => {
+                for existing in open.filter(|e| e.same_spell(spell_id, layer)) {
+                    existing.removed = Some(timestamp);
+                }
+            }
+            EnchantmentChange::Purge => {
+                for existing in open {
+                    existing.removed = Some(timestamp);
+                }
+            }
+        }
+    }
+
+    /// Enchantments in effect at the given capture time
+    pub fn enchantments_at(&self, time: f64) -> Vec<&Enchantment> {
+        self.enchantments
+            .iter()
+            .filter(|e| e.is_active_at(time))
+            .collect()
     }
+
+    /// Object that wields or contains this weenie, if known
+    ///
+    /// Wielder wins over Container, and a zero id (cleared by the server when an
+    /// item moves) counts as no parent.
+    pub fn parent_id(&self) -> Option<u32> {
+        ["Wielder", "Container"]
+            .iter()
+            .filter_map(|key| self.instance_id_properties.get(*key).copied())
+            .find(|&id| id != 0)
+    }
+}
+
+/// Format a DataId or InstanceId as a fixed-width hex string
+pub fn format_id(id: u32) -> String {
+    format!("0x{id:08X}")
+}
+
+/// Serialize an ID property map with hex values, since these are references
+/// to resources or other objects rather than quantities
+fn serialize_hex_ids<S>(properties: &HashMap<String, u32>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    properties
+        .iter()
+        .map(|(key, value)| (key, format_id(*value)))
+        .collect::<BTreeMap<_, _>>()
+        .serialize(serializer)
+}
+
+/// An enchantment on a weenie, with when it started and stopped in the capture
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Enchantment {
+    #[serde(rename = "SpellId")]
+    pub spell_id: u32,
+    #[serde(rename = "Layer")]
+    pub layer: u32,
+    #[serde(rename = "CasterId", serialize_with = "serialize_hex_id")]
+    pub caster_id: u32,
+    /// Capture time the enchantment was applied
+    #[serde(rename = "Added")]
+    pub added: f64,
+    /// Length in seconds; negative for enchantments that never expire
+    #[serde(rename = "Duration")]
+    pub duration: f64,
+    /// Capture time it was removed, dispelled, purged or recast
+    #[serde(rename = "Removed", skip_serializing_if = "Option::is_none")]
+    pub removed: Option<f64>,
+}
+
+impl Enchantment {
+    fn same_spell(&self, spell_id: u32, layer: u32) -> bool {
+        self.spell_id == spell_id && self.layer == layer
+    }
+
+    /// Whether the enchantment was applied, not yet removed and not expired at `time`
+    pub fn is_active_at(&self, time: f64) -> bool {
+        time >= self.added
+            && self.removed.is_none_or(|removed| time < removed)
+            && (self.duration < 0.0 || time < self.added + self.duration)
+    }
+}
+
+/// A change to a weenie's enchantments carried by a message
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnchantmentChange {
+    Add(Enchantment),
+    Remove {
+        spell_id: u32,
+        layer: u32,
+    },
+    /// Remove every enchantment still in effect
+    Purge,
+}
+
+fn serialize_hex_id<S>(id: &u32, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format_id(*id))
 }
 
 /// An update to a weenie from a message
@@ -141,6 +271,7 @@ pub struct WeenieUpdate {
     pub string_properties: HashMap<String, String>,
     pub data_id_properties: HashMap<String, u32>,
     pub instance_id_properties: HashMap<String, u32>,
+    pub enchantment_changes: Vec<EnchantmentChange>,
 }
 
 impl WeenieUpdate {
@@ -159,6 +290,9 @@ impl WeenieUpdate {
 #[derive(Debug, Clone, Default)]
 pub struct WeenieDatabase {
     weenies: HashMap<u32, Weenie>,
+    /// Ids of the weenies each object holds, sorted, kept in step with their
+    /// `parent_id` so the contents tree doesn't scan every weenie
+    children: HashMap<u32, Vec<u32>>,
 }
 
 impl WeenieDatabase {
@@ -166,6 +300,7 @@ impl WeenieDatabase {
     pub fn new() -> Self {
         Self {
             weenies: HashMap::new(),
+            children: HashMap::new(),
         }
     }
 
@@ -174,8 +309,10 @@ impl WeenieDatabase {
         let object_id = update.object_id;
         let timestamp = update.timestamp;
         let message_id = update.message_id;
+        let old_parent = self.weenies.get(&object_id).and_then(Weenie::parent_id);
 
-        self.weenies
+        let weenie = self
+            .weenies
             .entry(object_id)
             .and_modify(|w| w.update(update.clone()))
             .or_insert_with(|| {
@@ -183,6 +320,19 @@ impl WeenieDatabase {
                 weenie.update(update);
                 weenie
             });
+
+        let new_parent = weenie.parent_id().filter(|&parent| parent != object_id);
+        if new_parent != old_parent {
+            if let Some(ids) = old_parent.and_then(|parent| self.children.get_mut(&parent)) {
+                ids.retain(|&id| id != object_id);
+            }
+            if let Some(parent) = new_parent {
+                let ids = self.children.entry(parent).or_default();
+                if let Err(index) = ids.binary_search(&object_id) {
+                    ids.insert(index, object_id);
+                }
+            }
+        }
     }
 
     /// Get a weenie by object ID
@@ -190,6 +340,26 @@ impl WeenieDatabase {
         self.weenies.get(&object_id)
     }
 
+    /// Get the weenies held in the given object's containers or wielded by it
+    ///
+    /// Uses the `Container` and `Wielder` instance-id properties, sorted by
+    /// object ID. Walk this recursively to build an inventory tree.
+    pub fn children_of(&self, object_id: u32) -> Vec<&Weenie> {
+        self.children
+            .get(&object_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.weenies.get(id))
+            .collect()
+    }
+
+    /// Whether the given object holds or wields anything
+    pub fn has_children(&self, object_id: u32) -> bool {
+        self.children
+            .get(&object_id)
+            .is_some_and(|ids| !ids.is_empty())
+    }
+
     /// Get all weenies
     pub fn weenies(&self) -> &HashMap<u32, Weenie> {
         &self.weenies
@@ -216,3 +386,249 @@ impl Serialize for WeenieDatabase {
         self.weenies.serialize(serializer)
     }
 }
+
+/// How a property filter compares the property value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    /// Numeric/bool equality, or case-insensitive substring for strings
+    Equal,
+    Greater,
+    Less,
+}
+
+/// A filter on one weenie property, written `Name=Value`, `Name>Value` or `Name<Value`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyFilter {
+    pub name: String,
+    pub comparison: Comparison,
+    pub value: String,
+}
+
+impl PropertyFilter {
+    /// Parse a filter term, returning None if it has no operator or an empty side
+    pub fn parse(term: &str) -> Option<Self> {
+        let index = term.find(['=', '>', '<'])?;
+        let comparison = match term.as_bytes()[index] {
+            b'=' => Comparison::Equal,
+            b'>' => Comparison::Greater,
+            _ => Comparison::Less,
+        };
+        let name = term[..index].trim();
+        let value = term[index + 1..].trim();
+        if name.is_empty() || value.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            name: name.to_string(),
+            comparison,
+            value: value.to_string(),
+        })
+    }
+
+    /// Check the filter against every property table of the weenie
+    ///
+    /// Property names are compared case-insensitively; `Name` also matches the
+    /// weenie's name.
+    pub fn matches(&self, weenie: &Weenie) -> bool {
+        let name = self.name.as_str();
+        let number = lookup(&weenie.int_properties, name)
+            .map(|v| *v as f64)
+            .or_else(|| lookup(&weenie.int64_properties, name).map(|v| *v as f64))
+            .or_else(|| lookup(&weenie.float_properties, name).copied())
+            .or_else(|| lookup(&weenie.data_id_properties, name).map(|v| *v as f64))
+            .or_else(|| lookup(&weenie.instance_id_properties, name).map(|v| *v as f64));
+
+        if let Some(value) = number {
+            self.compare_number(value)
+        } else if let Some(value) = lookup(&weenie.bool_properties, name) {
+            self.compare_bool(*value)
+        } else if let Some(value) = lookup(&weenie.string_properties, name) {
+            self.compare_str(value)
+        } else if name.eq_ignore_ascii_case("Name") {
+            weenie.name.as_deref().is_some_and(|v| self.compare_str(v))
+        } else {
+            false
+        }
+    }
+
+    fn compare_number(&self, property: f64) -> bool {
+        let target = match self
+            .value
+            .strip_prefix("0x")
+            .or(self.value.strip_prefix("0X"))
+        {
+            Some(hex) => u64::from_str_radix(hex, 16).ok().map(|v| v as f64),
+            None => self
+                .value
+                .parse::<f64>()
+                .ok()
+                .or_else(|| enum_value(&self.name, &self.value).map(f64::from)),
+        };
+        let Some(target) = target else {
+            return false;
+        };
+        match self.comparison {
+            Comparison::Equal => property == target,
+            Comparison::Greater => property > target,
+            Comparison::Less => property < target,
+        }
+    }
+
+    fn compare_bool(&self, property: bool) -> bool {
+        let target = match self.value.to_ascii_lowercase().as_str() {
+            "true" | "1" => true,
+            "false" | "0" => false,
+            _ => return false,
+        };
+        self.comparison == Comparison::Equal && property == target
+    }
+
+    fn compare_str(&self, property: &str) -> bool {
+        self.comparison == Comparison::Equal
+            && property.to_lowercase().contains(&self.value.to_lowercase())
+    }
+}
+
+/// Value of an `ItemType` flag name, for `ItemType` terms only
+///
+/// Properties are stored as numbers, so this lets `ItemType=MeleeWeapon`
+/// compare like `ItemType=1`. Names come from acprotocol's `ItemType` flags
+/// and match case-insensitively, with or without underscores.
+fn enum_value(property: &str, name: &str) -> Option<u32> {
+    if !property.eq_ignore_ascii_case("ItemType") {
+        return None;
+    }
+    let name = name.replace('_', "");
+    ItemType::FLAGS
+        .iter()
+        .find(|flag| flag.name().replace('_', "").eq_ignore_ascii_case(&name))
+        .map(|flag| flag.value().bits())
+}
+
+/// Find a property by case-insensitive name
+fn lookup<'a, V>(table: &'a HashMap<String, V>, name: &str) -> Option<&'a V> {
+    table
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_parent(object_id: u32, key: &str, parent: u32) -> WeenieUpdate {
+        let mut update = WeenieUpdate::new(object_id, 1.0, 0);
+        update
+            .instance_id_properties
+            .insert(key.to_string(), parent);
+        update
+    }
+
+    #[test]
+    fn test_children_of_uses_container_and_wielder() {
+        let player = 0x5000_0001;
+        let pack = 0x8000_0010;
+
+        let mut db = WeenieDatabase::new();
+        db.add_or_update(WeenieUpdate::new(player, 1.0, 0));
+        db.add_or_update(with_parent(pack, "Container", player));
+        db.add_or_update(with_parent(0x8000_0030, "Container", pack));
+        db.add_or_update(with_parent(0x8000_0020, "Wielder", player));
+
+        let children: Vec<u32> = db.children_of(player).iter().map(|w| w.object_id).collect();
+        assert_eq!(children, vec![pack, 0x8000_0020]);
+
+        let nested: Vec<u32> = db.children_of(pack).iter().map(|w| w.object_id).collect();
+        assert_eq!(nested, vec![0x8000_0030]);
+        assert!(db.children_of(0x8000_0030).is_empty());
+        assert!(db.get(pack).is_some());
+    }
+
+    #[test]
+    fn test_cleared_container_falls_back_to_wielder() {
+        let mut db = WeenieDatabase::new();
+        db.add_or_update(with_parent(0x8000_0020, "Container", 0x5000_0001));
+        db.add_or_update(with_parent(0x8000_0020, "Container", 0));
+        assert_eq!(db.get(0x8000_0020).unwrap().parent_id(), None);
+
+        db.add_or_update(with_parent(0x8000_0020, "Wielder", 0x5000_0001));
+        assert_eq!(db.get(0x8000_0020).unwrap().parent_id(), Some(0x5000_0001));
+    }
+
+    #[test]
+    fn test_children_follow_moved_items() {
+        let (player, pack, item) = (0x5000_0001, 0x8000_0010, 0x8000_0020);
+        let mut db = WeenieDatabase::new();
+        db.add_or_update(with_parent(item, "Container", player));
+        assert!(db.has_children(player));
+
+        db.add_or_update(with_parent(item, "Container", pack));
+        assert!(!db.has_children(player));
+        let children: Vec<u32> = db.children_of(pack).iter().map(|w| w.object_id).collect();
+        assert_eq!(children, vec![item]);
+
+        // A cleared container leaves the item with no parent
+        db.add_or_update(with_parent(item, "Container", 0));
+        assert!(!db.has_children(pack));
+    }
+
+    #[test]
+    fn test_parse_property_filter() {
+        let filter = PropertyFilter::parse("Value>10000").unwrap();
+        assert_eq!(filter.name, "Value");
+        assert_eq!(filter.comparison, Comparison::Greater);
+        assert_eq!(filter.value, "10000");
+
+        assert!(PropertyFilter::parse("Value").is_none());
+        assert!(PropertyFilter::parse("=5").is_none());
+        assert!(PropertyFilter::parse("Value<").is_none());
+    }
+
+    #[test]
+    fn test_property_filter_matches() {
+        let mut update = WeenieUpdate::new(0x8000_0001, 1.0, 0);
+        update.name = Some("Bronze Atlan Sword".to_string());
+        update.int_properties.insert("Value".to_string(), 12_000);
+        update.bool_properties.insert("Attuned".to_string(), true);
+        update
+            .string_properties
+            .insert("LongDesc".to_string(), "An ancient blade".to_string());
+        update
+            .data_id_properties
+            .insert("Icon".to_string(), 0x0600_1234);
+        let mut db = WeenieDatabase::new();
+        db.add_or_update(update);
+        let weenie = db.get(0x8000_0001).unwrap();
+
+        let matches = |term: &str| PropertyFilter::parse(term).unwrap().matches(weenie);
+        assert!(matches("Value>10000"));
+        assert!(matches("value=12000"));
+        assert!(!matches("Value<10000"));
+        assert!(matches("Attuned=true"));
+        assert!(matches("LongDesc=ancient"));
+        assert!(matches("Icon=0x06001234"));
+        assert!(matches("Name=atlan"));
+        assert!(!matches("Burden>0"));
+    }
+
+    #[test]
+    fn test_property_filter_matches_enum_names() {
+        let mut update = WeenieUpdate::new(0x8000_0001, 1.0, 0);
+        update.int_properties.insert("ItemType".to_string(), 1);
+        let mut db = WeenieDatabase::new();
+        db.add_or_update(update);
+        let weenie = db.get(0x8000_0001).unwrap();
+
+        let matches = |term: &str| PropertyFilter::parse(term).unwrap().matches(weenie);
+        assert!(matches("ItemType=MeleeWeapon"));
+        assert!(matches("itemtype=meleeweapon"));
+        assert!(matches("ItemType=MELEE_WEAPON"));
+        assert!(matches("ItemType=1"));
+        assert!(!matches("ItemType=Armor"));
+        assert!(!matches("ItemType=Sword"));
+        // Only enum-typed properties take names
+        assert!(!matches("Value=MeleeWeapon"));
+    }
+}
@@ -4,7 +4,7 @@
 //! creating WeenieUpdate structures that can be used to populate the WeenieDatabase.
 
 use crate::messages::ParsedMessage;
-use crate::weenie::WeenieUpdate;
+use crate::weenie::{Enchantment, EnchantmentChange, WeenieUpdate};
 
 /// Extract weenie updates from a parsed message
 pub fn extract_weenie_updates(message: &ParsedMessage) -> Vec<WeenieUpdate> {
@@ -127,14 +127,48 @@ pub fn extract_weenie_updates(message: &ParsedMessage) -> Vec<WeenieUpdate> {
             }
         }
         "Magic_UpdateEnchantment" => {
+            let caster =
+                extract_ordered_event(message, "MagicUpdateEnchantment", extract_enchantment_data);
+            let recipient = extract_enchantment_change(message, "MagicUpdateEnchantment");
+            match (caster, recipient) {
+                // A self-cast names the same object twice; count the message once
+                (Some(mut caster), Some(recipient)) if caster.object_id == recipient.object_id => {
+                    caster
+                        .enchantment_changes
+                        .extend(recipient.enchantment_changes);
+                    updates.push(caster);
+                }
+                (caster, recipient) => updates.extend(caster.into_iter().chain(recipient)),
+            }
+        }
+        "Magic_RemoveEnchantment" => {
+            if let Some(update) = extract_enchantment_change(message, "MagicRemoveEnchantment") {
+                updates.push(update);
+            }
+        }
+        "Magic_DispelEnchantment" => {
+            if let Some(update) = extract_enchantment_change(message, "MagicDispelEnchantment")
+                .or_else(|| extract_ordered_event_simple(message, "MagicDispelEnchantment"))
+            {
+                updates.push(update);
+            }
+        }
+        "Magic_RemoveMultipleEnchantments" => {
             if let Some(update) =
-                extract_ordered_event(message, "MagicUpdateEnchantment", extract_enchantment_data)
+                extract_enchantment_change(message, "MagicRemoveMultipleEnchantments")
             {
                 updates.push(update);
             }
         }
-        "Magic_DispelEnchantment" => {
-            if let Some(update) = extract_ordered_event_simple(message, "MagicDispelEnchantment") {
+        "Magic_DispelMultipleEnchantments" => {
+            if let Some(update) =
+                extract_enchantment_change(message, "MagicDispelMultipleEnchantments")
+            {
+                updates.push(update);
+            }
+        }
+        "Magic_PurgeEnchantments" => {
+            if let Some(update) = extract_enchantment_change(message, "MagicPurgeEnchantments") {
                 updates.push(update);
             }
         }
@@ -235,6 +269,65 @@ fn extract_ordered_event_simple(message: &ParsedMessage, event_type: &str) -> Op
     Some(update)
 }
 
+/// Extract an enchantment change for the event's recipient (Pattern A)
+///
+/// Enchantment events are sent to the object they affect, so the change is
+/// applied to the OrderedGameEvent object_id rather than the caster.
+fn extract_enchantment_change(message: &ParsedMessage, event_type: &str) -> Option<WeenieUpdate> {
+    let ordered_event = message.data.get("S2C")?.get("OrderedGameEvent")?;
+    let event_data = ordered_event.get("event")?.get(event_type)?;
+
+    let object_id = ordered_event.get("object_id")?.as_u64()? as u32;
+    let mut update = WeenieUpdate::new(object_id, message.timestamp, message.id);
+
+    let remove = |value: &serde_json::Value| {
+        let (spell_id, layer) = layered_spell_id(value)?;
+        Some(EnchantmentChange::Remove { spell_id, layer })
+    };
+    match event_type {
+        "MagicUpdateEnchantment" => {
+            let enchantment = event_data.get("Enchantment")?;
+            let (spell_id, layer) = layered_spell_id(enchantment.get("Id")?)?;
+            update
+                .enchantment_changes
+                .push(EnchantmentChange::Add(Enchantment {
+                    spell_id,
+                    layer,
+                    caster_id: enchantment
+                        .get("CasterId")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(0) as u32,
+                    added: message.timestamp,
+                    duration: enchantment.get("Duration")?.as_f64()?,
+                    removed: None,
+                }));
+        }
+        "MagicRemoveEnchantment" | "MagicDispelEnchantment" => {
+            update
+                .enchantment_changes
+                .push(remove(event_data.get("SpellId")?)?);
+        }
+        "MagicRemoveMultipleEnchantments" | "MagicDispelMultipleEnchantments" => {
+            let spells = event_data.get("Enchantments")?.as_array()?;
+            update
+                .enchantment_changes
+                .extend(spells.iter().filter_map(remove));
+        }
+        "MagicPurgeEnchantments" => update.enchantment_changes.push(EnchantmentChange::Purge),
+        _ => return None,
+    }
+
+    Some(update)
+}
+
+/// Read a LayeredSpellId (`{"Id": spell, "Layer": layer}`)
+fn layered_spell_id(value: &serde_json::Value) -> Option<(u32, u32)> {
+    Some((
+        value.get("Id")?.as_u64()? as u32,
+        value.get("Layer")?.as_u64()? as u32,
+    ))
+}
+
 /// Extract from C2S OrderedGameAction
 fn extract_c2s_action(message: &ParsedMessage, action_type: &str) -> Option<WeenieUpdate> {
     let c2s = message.data.get("C2S")?;
@@ -445,3 +538,213 @@ fn extract_enchantment_data(
     update.object_id = caster_id;
     Some(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weenie::WeenieDatabase;
+    use serde_json::json;
+
+    fn s2c_message(message_type: &str, data: serde_json::Value) -> ParsedMessage {
+        ParsedMessage {
+            message_type: message_type.to_string(),
+            data,
+            direction: "Recv".to_string(),
+            opcode: "0x0000".to_string(),
+            timestamp: 1.0,
+            ..Default::default()
+        }
+    }
+
+    fn enchantment_event(
+        message_type: &str,
+        event_key: &str,
+        timestamp: f64,
+        event: serde_json::Value,
+    ) -> ParsedMessage {
+        let mut message = s2c_message(
+            message_type,
+            json!({"S2C": {"OrderedGameEvent": {
+                "object_id": 0x5000_0001u32,
+                "sequence": 1,
+                "event": {event_key: event},
+            }}}),
+        );
+        message.timestamp = timestamp;
+        message
+    }
+
+    #[test]
+    fn test_enchantments_tracked_on_recipient() {
+        let add = |timestamp, spell_id| {
+            enchantment_event(
+                "Magic_UpdateEnchantment",
+                "MagicUpdateEnchantment",
+                timestamp,
+                json!({"Enchantment": {
+                    "Id": {"Id": spell_id, "Layer": 1},
+                    "Duration": 60.0,
+                    "CasterId": 0x5000_0002u32,
+                }}),
+            )
+        };
+        let messages = [
+            add(10.0, 2053),
+            add(20.0, 2054),
+            enchantment_event(
+                "Magic_DispelEnchantment",
+                "MagicDispelEnchantment",
+                30.0,
+                json!({"SpellId": {"Id": 2053, "Layer": 1}}),
+            ),
+            enchantment_event(
+                "Magic_PurgeEnchantments",
+                "MagicPurgeEnchantments",
+                40.0,
+                json!({}),
+            ),
+        ];
+
+        let mut db = WeenieDatabase::new();
+        for message in &messages {
+            for update in extract_weenie_updates(message) {
+                db.add_or_update(update);
+            }
+        }
+
+        let player = db.get(0x5000_0001).unwrap();
+        assert_eq!(player.enchantments.len(), 2);
+        let active = |time| -> Vec<u32> {
+            player
+                .enchantments_at(time)
+                .iter()
+                .map(|e| e.spell_id)
+                .collect()
+        };
+        assert_eq!(active(5.0), Vec::<u32>::new());
+        assert_eq!(active(25.0), vec![2053, 2054]);
+        assert_eq!(active(35.0), vec![2054]);
+        assert_eq!(active(45.0), Vec::<u32>::new());
+        assert_eq!(player.enchantments[0].removed, Some(30.0));
+
+        // The caster is still recorded as seen
+        assert!(db.get(0x5000_0002).is_some());
+    }
+
+    #[test]
+    fn test_self_cast_enchantment_counts_message_once() {
+        let player_after = |caster_id: u32| {
+            let message = enchantment_event(
+                "Magic_UpdateEnchantment",
+                "MagicUpdateEnchantment",
+                10.0,
+                json!({"Enchantment": {
+                    "Id": {"Id": 2053, "Layer": 1},
+                    "Duration": 60.0,
+                    "CasterId": caster_id,
+                }}),
+            );
+            let mut db = WeenieDatabase::new();
+            for update in extract_weenie_updates(&message) {
+                db.add_or_update(update);
+            }
+            db.get(0x5000_0001).unwrap().clone()
+        };
+
+        // Cast on yourself, the player is seen in one message just as when
+        // someone else casts on them
+        let self_cast = player_after(0x5000_0001);
+        let other_cast = player_after(0x5000_0002);
+        assert_eq!(self_cast.message_count, other_cast.message_count);
+        assert_eq!(self_cast.message_ids, other_cast.message_ids);
+        assert_eq!(self_cast.enchantments.len(), 1);
+    }
+
+    #[test]
+    fn test_data_id_update_uses_data_id_table() {
+        let message = s2c_message(
+            "Qualities_UpdateDataId",
+            json!({"S2C": {"QualitiesUpdateDataId": {
+                "Sequence": 3,
+                "ObjectId": 0x8000_1234u32,
+                "Key": "WieldedTreasureType",
+                "Value": 0x0000_03E9u32,
+            }}}),
+        );
+
+        let updates = extract_weenie_updates(&message);
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].object_id, 0x8000_1234);
+        assert_eq!(
+            updates[0].data_id_properties.get("WieldedTreasureType"),
+            Some(&0x03E9)
+        );
+        assert!(updates[0].instance_id_properties.is_empty());
+
+        let mut db = WeenieDatabase::new();
+        db.add_or_update(updates[0].clone());
+        let json = serde_json::to_value(&db).unwrap();
+        assert_eq!(
+            json[0x8000_1234u32.to_string()]["DataIdProperties"]["WieldedTreasureType"],
+            "0x000003E9"
+        );
+    }
+
+    #[test]
+    fn test_int64_values_keep_all_64_bits() {
+        // Past u32::MAX and f64's exact integer range, so any narrowing shows
+        let experience = 191_226_310_247_i64 << 20;
+        let message = s2c_message(
+            "Qualities_UpdateInt64",
+            json!({"S2C": {"QualitiesUpdateInt64": {
+                "Sequence": 5,
+                "ObjectId": 0x5000_0001u32,
+                "Key": "TotalExperience",
+                "Value": experience,
+            }}}),
+        );
+        let updates = extract_weenie_updates(&message);
+        assert_eq!(updates.len(), 1);
+        assert_eq!(
+            updates[0].int64_properties.get("TotalExperience"),
+            Some(&experience)
+        );
+
+        let appraisal = enchantment_event(
+            "Item_SetAppraiseInfo",
+            "ItemSetAppraiseInfo",
+            2.0,
+            json!({
+                "ObjectId": 0x5000_0001u32,
+                "Int64Properties": {"Table": {"TotalExperience": experience}},
+            }),
+        );
+        let updates = extract_weenie_updates(&appraisal);
+        assert_eq!(updates.len(), 1);
+        assert_eq!(
+            updates[0].int64_properties.get("TotalExperience"),
+            Some(&experience)
+        );
+    }
+
+    #[test]
+    fn test_instance_id_update_uses_instance_id_table() {
+        let message = s2c_message(
+            "Qualities_UpdateInstanceId",
+            json!({"S2C": {"QualitiesUpdateInstanceId": {
+                "Sequence": 4,
+                "ObjectId": 0x8000_1234u32,
+                "Key": "Container",
+                "Value": 0x5000_0001u32,
+            }}}),
+        );
+
+        let updates = extract_weenie_updates(&message);
+        assert_eq!(updates.len(), 1);
+        assert_eq!(
+            updates[0].instance_id_properties.get("Container"),
+            Some(&0x5000_0001)
+        );
+        assert!(updates[0].data_id_properties.is_empty());
+    }
+}
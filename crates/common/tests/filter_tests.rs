@@ -1,61 +1,12 @@
 use common::PacketParser;
+use common::filter::{MessageFilter, json_contains_string};
 use common::messages::ParsedMessage;
 use std::fs::File;
 
-/// Recursively search for a string within a JSON value (case-insensitive)
-/// Searches in both field names and values, including numeric values
-fn json_contains_string(value: &serde_json::Value, search: &str) -> bool {
-    let search_lower = search.to_lowercase();
-
-    match value {
-        serde_json::Value::String(s) => s.to_lowercase().contains(&search_lower),
-        serde_json::Value::Number(n) => {
-            // Check if the search string matches the numeric value as a string
-            n.to_string().contains(&search_lower) ||
-            // Also check if search is a number and matches exactly
-            if let Ok(search_num) = search.parse::<i64>() {
-                n.as_i64() == Some(search_num)
-            } else if let Ok(search_num) = search.parse::<u64>() {
-                n.as_u64() == Some(search_num)
-            } else if let Ok(search_num) = search.parse::<f64>() {
-                n.as_f64() == Some(search_num)
-            } else {
-                false
-            }
-        }
-        serde_json::Value::Array(arr) => arr.iter().any(|v| json_contains_string(v, search)),
-        serde_json::Value::Object(obj) => {
-            // Search in both keys and values
-            obj.keys().any(|k| k.to_lowercase().contains(&search_lower))
-                || obj.values().any(|v| json_contains_string(v, search))
-        }
-        _ => false,
-    }
-}
-
-/// Filter messages by search string (searches in all message fields and data)
+/// Messages the search box would list for `search`
 fn filter_messages<'a>(messages: &'a [ParsedMessage], search: &str) -> Vec<&'a ParsedMessage> {
-    messages
-        .iter()
-        .filter(|m| {
-            // Search in message ID
-            let id_matches = m.id.to_string().contains(&search.to_lowercase());
-            // Search in message type
-            let type_matches = m
-                .message_type
-                .to_lowercase()
-                .contains(&search.to_lowercase());
-            // Search in direction
-            let direction_matches = m.direction.to_lowercase().contains(&search.to_lowercase());
-            // Search in opcode
-            let opcode_matches = m.opcode.to_lowercase().contains(&search.to_lowercase());
-            // Search in message data (deep search including field names and numeric values)
-            let data_matches = json_contains_string(&m.data, search);
-
-            // Match if any field contains the search string
-            id_matches || type_matches || direction_matches || opcode_matches || data_matches
-        })
-        .collect()
+    let filter = MessageFilter::new().with_search(search);
+    messages.iter().filter(|m| filter.matches(m)).collect()
 }
 
 /// Load messages from the test PCAP file
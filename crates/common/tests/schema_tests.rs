@@ -0,0 +1,109 @@
+use common::PacketParser;
+use common::export::write_capture_json;
+use common::schema::capture_schema;
+use serde_json::Value;
+
+/// Capture bundled with the web build, from a real client session
+const EXAMPLE_PCAP: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../static/example.pcap");
+
+/// Check `value` against the subset of JSON Schema that `capture_schema` uses,
+/// collecting a message for every violation
+fn validate(schema: &Value, value: &Value, root: &Value, path: &str, errors: &mut Vec<String>) {
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.trim_start_matches("#/$defs/");
+        return validate(&root["$defs"][name], value, root, path, errors);
+    }
+
+    if let Some(kind) = schema["type"].as_str() {
+        let ok = match kind {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            _ => panic!("{path}: unsupported schema type {kind}"),
+        };
+        if !ok {
+            errors.push(format!("{path}: expected {kind}, found {value}"));
+            return;
+        }
+    }
+    if let Some(allowed) = schema["enum"].as_array()
+        && !allowed.contains(value)
+    {
+        errors.push(format!("{path}: {value} is not one of {allowed:?}"));
+    }
+    if let (Some(pattern), Some(text)) = (schema["pattern"].as_str(), value.as_str())
+        && !regex::Regex::new(pattern).unwrap().is_match(text)
+    {
+        errors.push(format!("{path}: {text:?} does not match {pattern}"));
+    }
+    if let (Some(minimum), Some(number)) = (schema["minimum"].as_f64(), value.as_f64())
+        && number < minimum
+    {
+        errors.push(format!("{path}: {number} is below {minimum}"));
+    }
+    if let Some(options) = schema["oneOf"].as_array() {
+        let matching = options
+            .iter()
+            .filter(|option| {
+                let mut option_errors = Vec::new();
+                validate(option, value, root, path, &mut option_errors);
+                option_errors.is_empty()
+            })
+            .count();
+        if matching != 1 {
+            errors.push(format!("{path}: matches {matching} of the oneOf options"));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        for key in schema["required"].as_array().into_iter().flatten() {
+            if !object.contains_key(key.as_str().unwrap()) {
+                errors.push(format!("{path}: missing required {key}"));
+            }
+        }
+        for (key, item) in object {
+            let item_path = format!("{path}.{key}");
+            match (&schema["properties"][key], &schema["additionalProperties"]) {
+                (Value::Null, Value::Bool(false)) => {
+                    errors.push(format!("{item_path}: not in the schema"))
+                }
+                (Value::Null, Value::Null) => {}
+                (Value::Null, additional) => validate(additional, item, root, &item_path, errors),
+                (property, _) => validate(property, item, root, &item_path, errors),
+            }
+        }
+    }
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (index, item) in array.iter().enumerate() {
+            validate(items, item, root, &format!("{path}[{index}]"), errors);
+        }
+    }
+}
+
+#[test]
+fn test_exported_capture_matches_schema() {
+    let bytes = std::fs::read(EXAMPLE_PCAP).expect("Failed to read example capture");
+    let (packets, messages, weenies) = PacketParser::new()
+        .with_quiet(true)
+        .parse_pcap_bytes(&bytes)
+        .unwrap();
+    assert!(!messages.is_empty());
+
+    let mut json = Vec::new();
+    write_capture_json(&mut json, &messages, &packets, &weenies).unwrap();
+    let document: Value = serde_json::from_slice(&json).unwrap();
+
+    let schema = capture_schema();
+    let mut errors = Vec::new();
+    validate(&schema, &document, &schema, "$", &mut errors);
+    assert!(
+        errors.is_empty(),
+        "{} schema violations, first: {:#?}",
+        errors.len(),
+        &errors[..errors.len().min(10)]
+    );
+}
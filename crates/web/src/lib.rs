@@ -8,13 +8,27 @@ use app::PcapViewerApp;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::prelude::*;
 
-/// Query parameter variants
-enum QueryParams {
+/// Where to load the capture from
+enum CaptureSource {
     Url(String),
-    Discord { channel: String, msg: String },
+    Discord {
+        channel: String,
+        msg: String,
+        attachment: Option<String>,
+    },
 }
 
-/// Get query parameters - either ?url=... or ?channel=X&msg=Y
+/// Query parameters the app starts from
+struct QueryParams {
+    source: Option<CaptureSource>,
+    /// Search filter applied once the capture loads, from ?filter=...
+    filter: Option<String>,
+    /// Message id selected once the capture loads, from ?select=...
+    select: Option<usize>,
+}
+
+/// Get query parameters - either ?url=... or ?channel=X&msg=Y[&index=N|&attachment=name],
+/// optionally with &filter=...&select=N
 fn get_query_params() -> Option<QueryParams> {
     let window = web_sys::window()?;
     let location = window.location();
@@ -27,16 +41,35 @@ fn get_query_params() -> Option<QueryParams> {
     // Remove the leading '?' and parse
     let params = web_sys::UrlSearchParams::new_with_str(&search).ok()?;
 
+    Some(QueryParams {
+        source: get_capture_source(&params),
+        filter: params
+            .get("filter")
+            .and_then(|filter| app::share::sanitize_filter(&filter)),
+        select: params
+            .get("select")
+            .and_then(|id| app::share::parse_message_id(&id)),
+    })
+}
+
+/// Capture location from the query parameters, preferring Discord over ?url=
+fn get_capture_source(params: &web_sys::UrlSearchParams) -> Option<CaptureSource> {
     // Check for Discord params first (channel and msg)
     let channel = params.get("channel");
     let msg = params.get("msg");
 
     if let (Some(channel), Some(msg)) = (channel, msg) {
-        return Some(QueryParams::Discord { channel, msg });
+        // Optional attachment selector, by index or by filename
+        let attachment = params.get("index").or_else(|| params.get("attachment"));
+        return Some(CaptureSource::Discord {
+            channel,
+            msg,
+            attachment,
+        });
     }
 
     // Fall back to URL param
-    params.get("url").map(QueryParams::Url)
+    params.get("url").map(CaptureSource::Url)
 }
 
 // WASM entry point
@@ -67,16 +100,26 @@ pub fn start() -> Result<(), JsValue> {
                     let mut app = PcapViewerApp::new(cc);
 
                     // Check for query parameters on web version
-                    match get_query_params() {
-                        Some(QueryParams::Url(url)) => {
+                    let params = get_query_params();
+                    if let Some(params) = &params {
+                        app.initial_filter = params.filter.clone();
+                        app.initial_selection = params.select;
+                    }
+                    match params.and_then(|params| params.source) {
+                        Some(CaptureSource::Url(url)) => {
                             log::info!("Found URL in query params: {}", url);
                             app.initial_url = Some(url);
                             app.status_message = "Loading PCAP from URL...".to_string();
                         }
-                        Some(QueryParams::Discord { channel, msg }) => {
+                        Some(CaptureSource::Discord {
+                            channel,
+                            msg,
+                            attachment,
+                        }) => {
                             log::info!("Found Discord params: channel={}, msg={}", channel, msg);
                             app.discord_channel_id = channel;
                             app.discord_message_id = msg;
+                            app.discord_attachment = attachment;
                             app.status_message = "Ready to load PCAP from Discord...".to_string();
                             // The app will auto-load on first frame via initial_discord_load
                             app.initial_discord_load = true;